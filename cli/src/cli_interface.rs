@@ -1,4 +1,5 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rindexer::IndexUntil;
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Parser, Debug)]
@@ -45,9 +46,10 @@ pub enum Commands {
         #[clap(long, short)]
         path: Option<String>,
     },
-    /// Start various services like indexers, GraphQL APIs or both together
+    /// Start various services like indexers, GraphQL and REST APIs or all together
     ///
-    /// `rindexer start indexer` or `rindexer start graphql` or `rindexer start all`
+    /// `rindexer start indexer` or `rindexer start graphql` or `rindexer start rest` or `rindexer
+    /// start all`
     #[clap(name = "start")]
     Start {
         #[clap(subcommand)]
@@ -56,6 +58,15 @@ pub enum Commands {
         /// optional - The path to run the command in, default will be where the command is run.
         #[clap(long, short)]
         path: Option<String>,
+
+        /// optional - The log format, overrides the `log_format` in the rindexer.yaml file.
+        #[clap(long, value_enum)]
+        log_format: Option<LogFormat>,
+
+        /// optional - Apply contract changes in the rindexer.yaml without restarting, no-code
+        /// projects only and not with `--until` or `--no-live`.
+        #[clap(long)]
+        watch: bool,
     },
 
     /// Add elements such as contracts to the rindexer.yaml file.
@@ -117,6 +128,45 @@ pub enum Commands {
         #[clap(long, short)]
         path: Option<String>,
     },
+    /// Show the size of the RPC cache or prune it
+    ///
+    /// The RPC cache stores the finalized eth_getLogs responses when `rpc_cache` is in the
+    /// rindexer.yaml file.
+    ///
+    /// Example:
+    /// `rindexer rpc-cache size` or `rindexer rpc-cache prune --older-than-days 30`
+    #[clap(name = "rpc-cache")]
+    RpcCache {
+        #[clap(subcommand)]
+        subcommand: RpcCacheSubcommands,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RpcCacheSubcommands {
+    /// Shows the entries and disk space of the RPC cache for each chain id
+    ///
+    /// Example:
+    /// `rindexer rpc-cache size`
+    Size,
+
+    /// Removes entries from the RPC cache, every entry unless filtered
+    ///
+    /// Example:
+    /// `rindexer rpc-cache prune --chain-id 1 --older-than-days 30`
+    Prune {
+        /// optional - Only remove the entries of this chain id.
+        #[clap(long)]
+        chain_id: Option<u64>,
+
+        /// optional - Only remove the entries written more than this many days ago.
+        #[clap(long)]
+        older_than_days: Option<u64>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -138,15 +188,44 @@ pub enum NewSubcommands {
     Rust,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum LogFormat {
+    /// Human readable coloured text
+    Text,
+    /// A json object per line for log pipelines
+    Json,
+}
+
+impl LogFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum StartSubcommands {
     /// Starts the indexing service based on the rindexer.yaml file.
     ///
     /// Starts an indexer based on the rindexer.yaml file.
     ///
+    /// Pass `--until` or `--no-live` to run a one-shot backfill which exits once every contract is
+    /// indexed up to the block.
+    ///
     /// Example:
-    /// `rindexer start indexer`
-    Indexer,
+    /// `rindexer start indexer` or `rindexer start indexer --until 19000000`
+    Indexer {
+        /// optional - Index up to this block number or `latest` then exit instead of live
+        /// indexing.
+        #[clap(long)]
+        until: Option<IndexUntil>,
+
+        /// optional - Index up to the latest block then exit, the same as `--until latest`.
+        #[clap(long)]
+        no_live: bool,
+    },
 
     /// Starts the GraphQL server based on the rindexer.yaml file.
     ///
@@ -159,7 +238,19 @@ pub enum StartSubcommands {
         port: Option<String>,
     },
 
-    /// Starts the indexers and the GraphQL together based on the rindexer.yaml file.
+    /// Starts the REST API server based on the rindexer.yaml file.
+    ///
+    /// Optionally specify a port to override the default.
+    ///
+    /// Example:
+    /// `rindexer start rest --port 4000`
+    Rest {
+        #[clap(short, long, help = "Specify the port number for the REST server")]
+        port: Option<String>,
+    },
+
+    /// Starts the indexers and the GraphQL together based on the rindexer.yaml file, the REST API
+    /// is also started if the rest section is in the rindexer.yaml file.
     ///
     /// You can specify a port which will be used by all services that require one.
     ///
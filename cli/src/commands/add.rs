@@ -181,6 +181,9 @@ pub async fn handle_add_contract_command(
             index_event_in_order: None,
             dependency_events: None,
             reorg_safe_distance: None,
+            historic_segments: None,
+            combine_get_logs: None,
+            log_buffer_size: None,
             generate_csv: None,
             streams: None,
             chat: None,
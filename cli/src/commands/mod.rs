@@ -3,6 +3,7 @@ pub mod codegen;
 pub mod delete;
 pub mod new;
 pub mod phantom;
+pub mod rpc_cache;
 pub mod start;
 
 const BACKUP_ETHERSCAN_API_KEY: &str = "DHBPB1EJ84JMSWP7C86387NK7IIRRQJVV1";
@@ -141,6 +141,9 @@ pub fn handle_new_command(
             compute_units_per_second: None,
             max_block_range: None,
             disable_logs_bloom_checks: None,
+            max_concurrent_requests: None,
+            finality: None,
+            block_explorer: None,
         }],
         contracts: vec![Contract {
             name: "RocketPoolETH".to_string(),
@@ -160,6 +163,9 @@ pub fn handle_new_command(
             index_event_in_order: None,
             dependency_events: None,
             reorg_safe_distance: None,
+            historic_segments: None,
+            combine_get_logs: None,
+            log_buffer_size: None,
             generate_csv: None,
             streams: None,
             chat: None,
@@ -189,6 +195,11 @@ pub fn handle_new_command(
             },
         },
         graphql: None,
+        rest: None,
+        health: None,
+        telemetry: None,
+        rpc_cache: None,
+        log_format: None,
     };
 
     // Write the rindexer.yaml file
@@ -548,6 +548,9 @@ async fn handle_phantom_deploy(
                             Some(U64::from(2_000))
                         },
                         disable_logs_bloom_checks: None,
+                        max_concurrent_requests: None,
+                        finality: None,
+                        block_explorer: None,
                     });
                 }
 
@@ -0,0 +1,93 @@
+use std::{path::PathBuf, time::Duration};
+
+use rindexer::{
+    manifest::yaml::{read_manifest, YAML_CONFIG_NAME},
+    rpc_cache::{prune_rpc_cache, rpc_cache_path, rpc_cache_size},
+};
+
+use crate::{
+    cli_interface::RpcCacheSubcommands,
+    console::{print_error_message, print_success_message, print_warn_message},
+    rindexer_yaml::validate_rindexer_yaml_exist,
+};
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+pub async fn handle_rpc_cache_command(
+    project_path: PathBuf,
+    subcommand: &RpcCacheSubcommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    validate_rindexer_yaml_exist(&project_path);
+
+    let manifest = read_manifest(&project_path.join(YAML_CONFIG_NAME)).map_err(|e| {
+        print_error_message(&format!("Could not read the rindexer.yaml file: {}", e));
+        e
+    })?;
+
+    if manifest.rpc_cache.as_ref().is_none_or(|rpc_cache| !rpc_cache.enabled) {
+        print_warn_message(
+            "rpc_cache is not enabled in the rindexer.yaml file, using the default cache path",
+        );
+    }
+    let cache_path = rpc_cache_path(&project_path, manifest.rpc_cache.as_ref());
+
+    match subcommand {
+        RpcCacheSubcommands::Size => {
+            let sizes = rpc_cache_size(&cache_path).await.map_err(|e| {
+                print_error_message(&format!("Could not read the RPC cache: {}", e));
+                e
+            })?;
+
+            if sizes.is_empty() {
+                print_success_message(&format!(
+                    "The RPC cache at {} is empty",
+                    cache_path.display()
+                ));
+                return Ok(());
+            }
+
+            println!("RPC cache at {}", cache_path.display());
+            for (chain_id, size) in &sizes {
+                println!(
+                    "chain id {} - {} entries - {}",
+                    chain_id,
+                    size.entries,
+                    format_bytes(size.bytes)
+                );
+            }
+            let bytes = sizes.values().map(|size| size.bytes).sum();
+            let entries: u64 = sizes.values().map(|size| size.entries).sum();
+            print_success_message(&format!(
+                "total - {} entries - {}",
+                entries,
+                format_bytes(bytes)
+            ));
+        }
+        RpcCacheSubcommands::Prune { chain_id, older_than_days } => {
+            let older_than = older_than_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+            let removed =
+                prune_rpc_cache(&cache_path, *chain_id, older_than).await.map_err(|e| {
+                    print_error_message(&format!("Could not prune the RPC cache: {}", e));
+                    e
+                })?;
+
+            print_success_message(&format!(
+                "Removed {} entries - {} from the RPC cache at {}",
+                removed.entries,
+                format_bytes(removed.bytes),
+                cache_path.display()
+            ));
+        }
+    }
+
+    Ok(())
+}
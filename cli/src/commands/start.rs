@@ -5,8 +5,9 @@ use rindexer::{
         core::ProjectType,
         yaml::{read_manifest, YAML_CONFIG_NAME},
     },
-    rindexer_error, rindexer_info, setup_info_logger, start_rindexer_no_code,
-    GraphqlOverrideSettings, IndexerNoCodeDetails, PostgresClient, StartNoCodeDetails,
+    rindexer_error, rindexer_info, setup_info_logger_with_telemetry, start_rindexer_no_code,
+    GraphqlOverrideSettings, IndexUntil, IndexerNoCodeDetails, PostgresClient,
+    RestOverrideSettings, StartNoCodeDetails,
 };
 
 use crate::{
@@ -114,9 +115,8 @@ fn start_docker_compose(project_path: &PathBuf) -> Result<(), String> {
 pub async fn start(
     project_path: PathBuf,
     command: &StartSubcommands,
+    watch: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    setup_info_logger();
-
     validate_rindexer_yaml_exist(&project_path);
 
     let manifest = read_manifest(&project_path.join(YAML_CONFIG_NAME)).map_err(|e| {
@@ -124,6 +124,8 @@ pub async fn start(
         e
     })?;
 
+    setup_info_logger_with_telemetry(&manifest);
+
     if manifest.storage.postgres_enabled() {
         let client = PostgresClient::new().await;
         if client.is_err() {
@@ -150,21 +152,38 @@ pub async fn start(
 
     match manifest.project_type {
         ProjectType::Rust => {
+            if watch {
+                rindexer_error!("--watch is only supported for no-code projects, rust projects need a restart to pick up rindexer.yaml changes");
+            }
+
             let project_cargo_manifest_path = project_path.join("Cargo.toml");
             let status = Command::new("cargo")
                 .arg("run")
                 .arg("--manifest-path")
                 .arg(project_cargo_manifest_path)
                 .arg(match command {
-                    StartSubcommands::Indexer => "-- --indexer".to_string(),
+                    StartSubcommands::Indexer { until, no_live } => {
+                        match until.or(no_live.then_some(IndexUntil::Latest)) {
+                            Some(until) => format!("-- --indexer --until={}", until),
+                            None => "-- --indexer".to_string(),
+                        }
+                    }
                     StartSubcommands::Graphql { port } => match port {
                         Some(port) => format!("-- --graphql --port={}", port),
                         None => "-- --graphql".to_string(),
                     },
-                    StartSubcommands::All { port } => match port {
-                        Some(port) => format!("-- --port={}", port),
-                        None => "".to_string(),
+                    StartSubcommands::Rest { port } => match port {
+                        Some(port) => format!("-- --rest --rest-port={}", port),
+                        None => "-- --rest".to_string(),
                     },
+                    StartSubcommands::All { port } => {
+                        // the same as no-code, REST also starts when it is in the rindexer.yaml
+                        let rest = if manifest.rest.is_some() { " --rest" } else { "" };
+                        match port {
+                            Some(port) => format!("-- --indexer --graphql{} --port={}", rest, port),
+                            None => format!("-- --indexer --graphql{}", rest),
+                        }
+                    }
                 })
                 .status()
                 .expect("Failed to execute cargo run.");
@@ -174,14 +193,22 @@ pub async fn start(
             }
         }
         ProjectType::NoCode => match command {
-            StartSubcommands::Indexer => {
+            StartSubcommands::Indexer { until, no_live } => {
+                let until = until.or(no_live.then_some(IndexUntil::Latest));
+                if watch && until.is_some() {
+                    let error = "--watch can not be used with --until or --no-live, a backfill exits once done so there is nothing to apply the changes to";
+                    print_error_message(error);
+                    return Err(error.into());
+                }
+
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails { enabled: true, watch, until },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: false,
                         override_port: None,
                     },
+                    rest_details: RestOverrideSettings { enabled: false, override_port: None },
                 };
 
                 start_rindexer_no_code(details).await.map_err(|e| {
@@ -192,11 +219,16 @@ pub async fn start(
             StartSubcommands::Graphql { port } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: false },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: false,
+                        watch: false,
+                        until: None,
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
                     },
+                    rest_details: RestOverrideSettings { enabled: false, override_port: None },
                 };
 
                 start_rindexer_no_code(details).await.map_err(|e| {
@@ -204,14 +236,41 @@ pub async fn start(
                     e
                 })?;
             }
+            StartSubcommands::Rest { port } => {
+                let details = StartNoCodeDetails {
+                    manifest_path: &project_path.join(YAML_CONFIG_NAME),
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: false,
+                        watch: false,
+                        until: None,
+                    },
+                    graphql_details: GraphqlOverrideSettings {
+                        enabled: false,
+                        override_port: None,
+                    },
+                    rest_details: RestOverrideSettings {
+                        enabled: true,
+                        override_port: port.as_ref().and_then(|port| port.parse().ok()),
+                    },
+                };
+
+                start_rindexer_no_code(details).await.map_err(|e| {
+                    print_error_message(&format!("Error starting the REST server: {}", e));
+                    e
+                })?;
+            }
             StartSubcommands::All { port } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails { enabled: true, watch, until: None },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
                     },
+                    rest_details: RestOverrideSettings {
+                        enabled: manifest.rest.is_some(),
+                        override_port: None,
+                    },
                 };
 
                 let _ = start_rindexer_no_code(details).await.map_err(|e| {
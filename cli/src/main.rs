@@ -22,7 +22,7 @@ use crate::{
     commands::{
         add::handle_add_contract_command, codegen::handle_codegen_command,
         delete::handle_delete_command, new::handle_new_command, phantom::handle_phantom_commands,
-        start::start,
+        rpc_cache::handle_rpc_cache_command, start::start,
     },
     console::print_error_message,
 };
@@ -101,10 +101,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             load_env_from_path(&resolved_path);
             handle_codegen_command(resolved_path, subcommand).await
         }
-        Commands::Start { subcommand, path } => {
+        Commands::Start { subcommand, path, log_format, watch } => {
             let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
             load_env_from_path(&resolved_path);
-            start(resolved_path, subcommand).await
+            // set as an env var so rust projects started with cargo pick it up too
+            if let Some(log_format) = log_format {
+                env::set_var("RINDEXER_LOG_FORMAT", log_format.as_str());
+            }
+            start(resolved_path, subcommand, *watch).await
         }
         Commands::Delete { path } => {
             let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
@@ -116,5 +120,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             load_env_from_path(&resolved_path);
             handle_phantom_commands(resolved_path, subcommand).await
         }
+        Commands::RpcCache { subcommand, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            load_env_from_path(&resolved_path);
+            handle_rpc_cache_command(resolved_path, subcommand).await
+        }
     }
 }
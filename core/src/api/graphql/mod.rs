@@ -0,0 +1,209 @@
+mod schema;
+mod subscription;
+
+use std::{collections::HashSet, net::SocketAddr, path::Path, sync::Arc};
+
+use async_graphql::{dynamic::Schema, http::GraphiQLSource};
+use async_graphql_axum::{GraphQL, GraphQLSubscription};
+use axum::{
+    extract::Request,
+    http::header,
+    response::{Html, Response},
+    routing::{any, get},
+    Router,
+};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use tower_http::cors::CorsLayer;
+use tracing::info;
+
+use crate::{
+    api::{
+        graphql::{
+            schema::{build_schema, GraphQLContext, SchemaOptions, TableRelation},
+            subscription::IndexedEventsFeed,
+        },
+        tables::event_tables,
+    },
+    database::postgres::{
+        client::{PostgresClient, PostgresConnectionError, PostgresError},
+        generate::{generate_indexer_contract_schema_name, GenerateTablesForIndexerSqlError},
+    },
+    indexer::Indexer,
+    manifest::graphql::GraphQLSettings,
+    shutdown::shutdown_signal,
+};
+
+pub struct GraphqlOverrideSettings {
+    pub enabled: bool,
+    pub override_port: Option<u16>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StartGraphqlServerError {
+    #[error("Could not connect to postgres: {0}")]
+    PostgresConnectionError(#[from] PostgresConnectionError),
+
+    #[error("Could not read the tables from postgres: {0}")]
+    PostgresError(#[from] PostgresError),
+
+    #[error("Could not read the event tables: {0}")]
+    EventTables(#[from] GenerateTablesForIndexerSqlError),
+
+    #[error("Could not build the GraphQL schema: {0}")]
+    SchemaError(#[from] async_graphql::dynamic::SchemaError),
+
+    #[error("Could not start up GraphQL server {0}")]
+    GraphQLServerStartupError(String),
+}
+
+/// Returns the `schema.table.column` of the first column of every index in the schemas
+async fn indexed_columns(
+    client: &PostgresClient,
+    schemas: &[String],
+) -> Result<HashSet<String>, PostgresError> {
+    let rows = client
+        .query(
+            "SELECT n.nspname::TEXT, c.relname::TEXT, a.attname::TEXT
+            FROM pg_index i
+            JOIN pg_class c ON c.oid = i.indrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum = i.indkey[0]
+            WHERE n.nspname = ANY($1)",
+            &[&schemas],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let schema: String = row.get(0);
+            let table: String = row.get(1);
+            let column: String = row.get(2);
+            format!("{}.{}.{}", schema, table, column)
+        })
+        .collect())
+}
+
+/// Returns the foreign keys between the tables in the schemas
+async fn table_relations(
+    client: &PostgresClient,
+    schemas: &[String],
+) -> Result<Vec<TableRelation>, PostgresError> {
+    let rows = client
+        .query(
+            "SELECT sn.nspname::TEXT, sc.relname::TEXT, sa.attname::TEXT,
+                tn.nspname::TEXT, tc.relname::TEXT, ta.attname::TEXT
+            FROM pg_constraint con
+            JOIN pg_class sc ON sc.oid = con.conrelid
+            JOIN pg_namespace sn ON sn.oid = sc.relnamespace
+            JOIN pg_attribute sa ON sa.attrelid = sc.oid AND sa.attnum = con.conkey[1]
+            JOIN pg_class tc ON tc.oid = con.confrelid
+            JOIN pg_namespace tn ON tn.oid = tc.relnamespace
+            JOIN pg_attribute ta ON ta.attrelid = tc.oid AND ta.attnum = con.confkey[1]
+            WHERE con.contype = 'f' AND sn.nspname = ANY($1)",
+            &[&schemas],
+        )
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| TableRelation {
+            table: format!("{}.{}", row.get::<_, String>(0), row.get::<_, String>(1)),
+            column: row.get(2),
+            linked_table: format!("{}.{}", row.get::<_, String>(3), row.get::<_, String>(4)),
+            linked_column: row.get(5),
+        })
+        .collect())
+}
+
+/// Queries are sent over http and subscriptions over a websocket on the same path
+async fn graphql_handler(schema: Schema, request: Request) -> Response {
+    let response = if request.headers().contains_key(header::UPGRADE) {
+        GraphQLSubscription::new(schema).oneshot(request).await
+    } else {
+        GraphQL::new(schema).oneshot(request).await
+    };
+
+    match response {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    }
+}
+
+/// Runs the GraphQL API until ctrl-c is pressed
+pub async fn start_graphql_server(
+    project_path: &Path,
+    indexer: &Indexer,
+    settings: &GraphQLSettings,
+) -> Result<(), StartGraphqlServerError> {
+    info!("Starting GraphQL server");
+
+    let client = Arc::new(PostgresClient::new().await?);
+    let tables = event_tables(project_path, indexer)?;
+
+    let schemas: Vec<String> = indexer
+        .contracts
+        .iter()
+        .map(|contract| {
+            generate_indexer_contract_schema_name(
+                &indexer.name,
+                &contract.before_modify_name_if_filter_readonly(),
+            )
+        })
+        .collect();
+
+    let indexed_columns = if settings.filter_only_on_indexed_columns {
+        Some(indexed_columns(&client, &schemas).await?)
+    } else {
+        None
+    };
+
+    let options = SchemaOptions {
+        disable_advanced_filters: settings.disable_advanced_filters,
+        indexed_columns,
+        relations: table_relations(&client, &schemas).await?,
+    };
+    let schema = build_schema(tables, &options)
+        .data(GraphQLContext { client, page_limit: settings.page_limit, timeout: settings.timeout })
+        .data(IndexedEventsFeed::start())
+        .limit_depth(20)
+        .finish()?;
+
+    let app = Router::new()
+        .route("/graphql", any(move |request: Request| graphql_handler(schema.clone(), request)))
+        .route(
+            "/playground",
+            get(|| async {
+                Html(
+                    GraphiQLSource::build()
+                        .endpoint("/graphql")
+                        .subscription_endpoint("/graphql")
+                        .finish(),
+                )
+            }),
+        )
+        .layer(CorsLayer::permissive());
+
+    let address = SocketAddr::from(([0, 0, 0, 0], settings.port));
+    let listener = TcpListener::bind(address).await.map_err(|e| {
+        StartGraphqlServerError::GraphQLServerStartupError(format!(
+            "Could not bind to port {}: {}",
+            settings.port, e
+        ))
+    })?;
+
+    info!(
+        "🦀 GraphQL API ready at http://localhost:{}/graphql Playground - http://localhost:{}/playground 🦀",
+        settings.port, settings.port
+    );
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .map_err(|e| StartGraphqlServerError::GraphQLServerStartupError(e.to_string()))?;
+
+    info!("GraphQL server stopped");
+
+    Ok(())
+}
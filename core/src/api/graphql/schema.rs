@@ -0,0 +1,871 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_graphql::{
+    dynamic::{
+        Enum, Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ObjectAccessor,
+        ResolverContext, Scalar, SchemaBuilder, Subscription, SubscriptionField,
+        SubscriptionFieldFuture, TypeRef, ValueAccessor,
+    },
+    Error, Result, Value,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::{stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::{
+    api::{
+        graphql::subscription::IndexedEventsFeed,
+        query::{
+            Cursor, Filter, FilterOperator, FilterValue, OrderBy, TablePage, TableQuery, TableRow,
+        },
+        tables::{pluralize, ColumnType, EventTable, EventTableColumn},
+    },
+    database::postgres::client::PostgresClient,
+    indexer::indexed_events::IndexedEvents,
+};
+
+/// Shared state the resolvers read from the schema data
+pub struct GraphQLContext {
+    pub client: Arc<PostgresClient>,
+    pub page_limit: u64,
+    pub timeout: u64,
+}
+
+/// A foreign key between two event tables, the tables are `schema.table` names
+#[derive(Debug, Clone)]
+pub struct TableRelation {
+    pub table: String,
+    pub column: String,
+    pub linked_table: String,
+    pub linked_column: String,
+}
+
+pub struct SchemaOptions {
+    pub disable_advanced_filters: bool,
+    /// When set only these `schema.table.column` columns can be filtered and ordered on
+    pub indexed_columns: Option<HashSet<String>>,
+    pub relations: Vec<TableRelation>,
+}
+
+struct TableSchema {
+    table: EventTable,
+    plural: String,
+    filter_columns: Vec<EventTableColumn>,
+    order_by: HashMap<String, Option<OrderBy>>,
+}
+
+impl TableSchema {
+    fn new(table: EventTable, options: &SchemaOptions) -> Self {
+        let plural = pluralize(&table.type_name);
+        let filter_columns: Vec<EventTableColumn> = table
+            .columns
+            .iter()
+            .filter(|column| column.is_filterable())
+            .filter(|column| {
+                options.indexed_columns.as_ref().is_none_or(|indexed| {
+                    indexed.contains(&format!("{}.{}", table.full_name(), column.name))
+                })
+            })
+            .cloned()
+            .collect();
+
+        let mut order_by = HashMap::from([
+            ("NATURAL".to_string(), None),
+            (
+                "PRIMARY_KEY_ASC".to_string(),
+                Some(OrderBy { column: "rindexer_id".to_string(), descending: false }),
+            ),
+            (
+                "PRIMARY_KEY_DESC".to_string(),
+                Some(OrderBy { column: "rindexer_id".to_string(), descending: true }),
+            ),
+        ]);
+        for column in &filter_columns {
+            for descending in [false, true] {
+                order_by.insert(
+                    format!(
+                        "{}_{}",
+                        column.name.to_uppercase(),
+                        if descending { "DESC" } else { "ASC" }
+                    ),
+                    Some(OrderBy { column: column.name.clone(), descending }),
+                );
+            }
+        }
+
+        Self { table, plural, filter_columns, order_by }
+    }
+
+    fn single_name(&self) -> String {
+        lower_first(&self.table.type_name)
+    }
+
+    fn condition_name(&self) -> String {
+        format!("{}Condition", self.table.type_name)
+    }
+
+    fn filter_name(&self) -> String {
+        format!("{}Filter", self.table.type_name)
+    }
+
+    fn order_by_name(&self) -> String {
+        format!("{}OrderBy", self.plural)
+    }
+
+    fn connection_name(&self) -> String {
+        format!("{}Connection", self.plural)
+    }
+
+    fn edge_name(&self) -> String {
+        format!("{}Edge", self.plural)
+    }
+
+    fn filter_column(&self, field_name: &str) -> Result<&EventTableColumn> {
+        self.filter_columns
+            .iter()
+            .find(|column| column.field_name == field_name)
+            .ok_or_else(|| Error::new(format!("Can not filter on {}", field_name)))
+    }
+}
+
+struct ConnectionValue {
+    page: TablePage,
+    query: TableQuery,
+}
+
+fn lower_first(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn upper_first(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn scalar_name(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Int => "Int",
+        ColumnType::Boolean => "Boolean",
+        ColumnType::String | ColumnType::Bytes => "String",
+        ColumnType::BigFloat => "BigFloat",
+    }
+}
+
+fn filter_type_name(column_type: ColumnType) -> String {
+    format!("{}Filter", scalar_name(column_type))
+}
+
+fn encode_node_id(plural: &str, rindexer_id: &serde_json::Value) -> String {
+    STANDARD.encode(serde_json::json!([plural, rindexer_id]).to_string())
+}
+
+fn decode_node_id(node_id: &str) -> Option<(String, i64)> {
+    let bytes = STANDARD.decode(node_id).ok()?;
+    let (plural, rindexer_id): (String, i64) = serde_json::from_slice(&bytes).ok()?;
+    Some((plural, rindexer_id))
+}
+
+fn value_to_string(value: &Value) -> Result<String> {
+    match value {
+        Value::Number(number) => Ok(number.to_string()),
+        Value::String(value) => Ok(value.clone()),
+        Value::Boolean(value) => Ok(value.to_string()),
+        _ => Err(Error::new("Filter values must be a number, string or boolean")),
+    }
+}
+
+fn parse_condition(schema: &TableSchema, condition: ObjectAccessor<'_>) -> Result<Vec<Filter>> {
+    condition
+        .iter()
+        .map(|(name, value)| {
+            let column = schema.filter_column(name)?;
+            if value.is_null() {
+                return Ok(Filter::Column {
+                    column: column.name.clone(),
+                    operator: FilterOperator::IsNull,
+                    value: FilterValue::Bool(true),
+                });
+            }
+            Ok(Filter::Column {
+                column: column.name.clone(),
+                operator: FilterOperator::EqualTo,
+                value: FilterValue::Single(value_to_string(value.as_value())?),
+            })
+        })
+        .collect()
+}
+
+fn parse_filters(schema: &TableSchema, value: ValueAccessor<'_>) -> Result<Vec<Filter>> {
+    value.list()?.iter().map(|filter| parse_filter(schema, filter.object()?)).collect()
+}
+
+fn parse_filter(schema: &TableSchema, filter: ObjectAccessor<'_>) -> Result<Filter> {
+    let mut filters = vec![];
+
+    for (name, value) in filter.iter() {
+        if value.is_null() {
+            continue;
+        }
+
+        match name.as_str() {
+            "and" => filters.push(Filter::And(parse_filters(schema, value)?)),
+            "or" => filters.push(Filter::Or(parse_filters(schema, value)?)),
+            "not" => filters.push(Filter::Not(Box::new(parse_filter(schema, value.object()?)?))),
+            field_name => {
+                let column = schema.filter_column(field_name)?;
+                for (operator_name, value) in value.object()?.iter() {
+                    if value.is_null() {
+                        continue;
+                    }
+                    let operator = FilterOperator::from_name(operator_name).ok_or_else(|| {
+                        Error::new(format!("Unknown filter operator {}", operator_name))
+                    })?;
+                    let value = match operator {
+                        FilterOperator::IsNull => FilterValue::Bool(value.boolean()?),
+                        FilterOperator::In | FilterOperator::NotIn => FilterValue::List(
+                            value
+                                .list()?
+                                .iter()
+                                .map(|value| value_to_string(value.as_value()))
+                                .collect::<Result<Vec<_>>>()?,
+                        ),
+                        _ => FilterValue::Single(value_to_string(value.as_value())?),
+                    };
+                    filters.push(Filter::Column { column: column.name.clone(), operator, value });
+                }
+            }
+        }
+    }
+
+    Ok(Filter::And(filters))
+}
+
+fn parse_page_arg(ctx: &ResolverContext<'_>, name: &str) -> Result<Option<u64>> {
+    match ctx.args.get(name) {
+        Some(value) if !value.is_null() => {
+            let value = value.i64()?;
+            if value < 0 {
+                return Err(Error::new(format!("{} can not be negative", name)));
+            }
+            Ok(Some(value as u64))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_cursor_arg(ctx: &ResolverContext<'_>, name: &str) -> Result<Option<Cursor>> {
+    match ctx.args.get(name) {
+        Some(value) if !value.is_null() => Ok(Some(Cursor::decode(value.string()?)?)),
+        _ => Ok(None),
+    }
+}
+
+fn parse_query(schema: &TableSchema, ctx: &ResolverContext<'_>) -> Result<TableQuery> {
+    let mut query = TableQuery {
+        first: parse_page_arg(ctx, "first")?,
+        last: parse_page_arg(ctx, "last")?,
+        offset: parse_page_arg(ctx, "offset")?,
+        after: parse_cursor_arg(ctx, "after")?,
+        before: parse_cursor_arg(ctx, "before")?,
+        ..Default::default()
+    };
+
+    if let Some(order_by) = ctx.args.get("orderBy") {
+        for value in order_by.list()?.iter() {
+            let name = value.enum_name()?;
+            match schema.order_by.get(name) {
+                Some(Some(order)) => query.order_by.push(order.clone()),
+                Some(None) => {}
+                None => return Err(Error::new(format!("Unknown order by {}", name))),
+            }
+        }
+    }
+
+    if let Some(condition) = ctx.args.get("condition") {
+        if !condition.is_null() {
+            query.filters.extend(parse_condition(schema, condition.object()?)?);
+        }
+    }
+
+    if let Some(filter) = ctx.args.get("filter") {
+        if !filter.is_null() {
+            query.filters.push(parse_filter(schema, filter.object()?)?);
+        }
+    }
+
+    Ok(query)
+}
+
+async fn fetch_one(
+    ctx: &ResolverContext<'_>,
+    schema: &TableSchema,
+    column: &str,
+    value: String,
+) -> Result<Option<FieldValue<'static>>> {
+    let context = ctx.data::<GraphQLContext>()?;
+    let query = TableQuery {
+        filters: vec![Filter::Column {
+            column: column.to_string(),
+            operator: FilterOperator::EqualTo,
+            value: FilterValue::Single(value),
+        }],
+        first: Some(1),
+        ..Default::default()
+    };
+    let page = query.fetch(&context.client, &schema.table, 1, context.timeout).await?;
+
+    Ok(page.rows.into_iter().next().map(FieldValue::owned_any))
+}
+
+/// The value of a field on the row as the text the filters expect
+fn row_value(row: &TableRow, field_name: &str) -> Option<String> {
+    match row.data.get(field_name)? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        serde_json::Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn register_shared_types(builder: SchemaBuilder) -> SchemaBuilder {
+    let page_info = Object::new("PageInfo")
+        .field(Field::new("hasNextPage", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(Some(Value::from(connection.page.has_next_page)))
+            })
+        }))
+        .field(Field::new("hasPreviousPage", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(Some(Value::from(connection.page.has_previous_page)))
+            })
+        }))
+        .field(Field::new("startCursor", TypeRef::named("Cursor"), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(connection.page.rows.first().map(|row| Value::from(row.cursor.encode())))
+            })
+        }))
+        .field(Field::new("endCursor", TypeRef::named("Cursor"), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(connection.page.rows.last().map(|row| Value::from(row.cursor.encode())))
+            })
+        }));
+
+    let mut builder = builder
+        .register(Scalar::new("BigFloat").description(
+            "A floating point number that requires more precision than IEEE 754 binary 64",
+        ))
+        .register(Scalar::new("Cursor").description("A location in a connection"))
+        .register(page_info);
+
+    for column_type in
+        [ColumnType::Int, ColumnType::Boolean, ColumnType::String, ColumnType::BigFloat]
+    {
+        let scalar = scalar_name(column_type);
+        let mut filter = InputObject::new(filter_type_name(column_type));
+        for operator in FilterOperator::ALL {
+            let type_ref = match operator {
+                FilterOperator::IsNull => TypeRef::named(TypeRef::BOOLEAN),
+                FilterOperator::In | FilterOperator::NotIn => TypeRef::named_nn_list(scalar),
+                _ => TypeRef::named(scalar),
+            };
+            filter = filter.field(InputValue::new(operator.name(), type_ref));
+        }
+        builder = builder.register(filter);
+    }
+
+    builder
+}
+
+/// A connection over the rows of a table, `parent` limits the rows to the ones where the column
+/// matches a field of the parent row
+fn connection_field(
+    name: String,
+    schema: Arc<TableSchema>,
+    options: &SchemaOptions,
+    parent: Option<(String, String)>,
+) -> Field {
+    let connection_name = schema.connection_name();
+    let order_by_name = schema.order_by_name();
+    let condition_name = schema.condition_name();
+    let filter_name = schema.filter_name();
+
+    let field =
+        Field::new(name, TypeRef::named(connection_name), move |ctx| {
+            let schema = Arc::clone(&schema);
+            let parent = parent.clone();
+            FieldFuture::new(async move {
+                let mut query = parse_query(&schema, &ctx)?;
+                if let Some((column, parent_field)) = parent {
+                    let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                    match row_value(row, &parent_field) {
+                        Some(value) => query.filters.push(Filter::Column {
+                            column,
+                            operator: FilterOperator::EqualTo,
+                            value: FilterValue::Single(value),
+                        }),
+                        None => return Ok(None),
+                    }
+                }
+
+                let context = ctx.data::<GraphQLContext>()?;
+                let page = query
+                    .fetch(&context.client, &schema.table, context.page_limit, context.timeout)
+                    .await?;
+                Ok(Some(FieldValue::owned_any(ConnectionValue { page, query })))
+            })
+        })
+        .argument(InputValue::new("first", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("last", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("offset", TypeRef::named(TypeRef::INT)))
+        .argument(InputValue::new("before", TypeRef::named("Cursor")))
+        .argument(InputValue::new("after", TypeRef::named("Cursor")))
+        .argument(InputValue::new("orderBy", TypeRef::named_nn_list(order_by_name)).default_value(
+            Value::List(vec![Value::Enum(async_graphql::Name::new("PRIMARY_KEY_ASC"))]),
+        ))
+        .argument(InputValue::new("condition", TypeRef::named(condition_name)));
+
+    if options.disable_advanced_filters {
+        field
+    } else {
+        field.argument(InputValue::new("filter", TypeRef::named(filter_name)))
+    }
+}
+
+fn node_object(
+    schema: &Arc<TableSchema>,
+    schemas: &HashMap<String, Arc<TableSchema>>,
+    options: &SchemaOptions,
+) -> Object {
+    let plural = schema.plural.clone();
+    let mut node = Object::new(&schema.table.type_name).field(Field::new(
+        "nodeId",
+        TypeRef::named_nn(TypeRef::ID),
+        move |ctx| {
+            let plural = plural.clone();
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                let rindexer_id = row.data.get("rindexerId").cloned().unwrap_or_default();
+                Ok(Some(Value::from(encode_node_id(&plural, &rindexer_id))))
+            })
+        },
+    ));
+
+    for column in &schema.table.columns {
+        let scalar = scalar_name(column.column_type);
+        let type_ref = match (column.is_array, column.not_null) {
+            (true, _) => TypeRef::named_list(scalar),
+            (false, true) => TypeRef::named_nn(scalar),
+            (false, false) => TypeRef::named(scalar),
+        };
+        let field_name = column.field_name.clone();
+        node = node.field(Field::new(&column.field_name, type_ref, move |ctx| {
+            let field_name = field_name.clone();
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                let value = row.data.get(&field_name).cloned().unwrap_or_default();
+                Ok(Some(Value::from_json(value)?))
+            })
+        }));
+    }
+
+    let full_name = schema.table.full_name();
+    for relation in &options.relations {
+        // the row holding the foreign key links to a single row of the linked table
+        if relation.table == full_name {
+            let (Some(column), Some(linked)) =
+                (schema.table.column(&relation.column), schemas.get(&relation.linked_table))
+            else {
+                continue;
+            };
+            let field_name = column.field_name.clone();
+            let linked_column = relation.linked_column.clone();
+            let linked_schema = Arc::clone(linked);
+            node = node.field(Field::new(
+                format!("{}By{}", linked.single_name(), upper_first(&column.field_name)),
+                TypeRef::named(&linked.table.type_name),
+                move |ctx| {
+                    let field_name = field_name.clone();
+                    let linked_column = linked_column.clone();
+                    let linked_schema = Arc::clone(&linked_schema);
+                    FieldFuture::new(async move {
+                        let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                        match row_value(row, &field_name) {
+                            Some(value) => {
+                                fetch_one(&ctx, &linked_schema, &linked_column, value).await
+                            }
+                            None => Ok(None),
+                        }
+                    })
+                },
+            ));
+        }
+
+        // the linked row can page over all the rows pointing at it
+        if relation.linked_table == full_name {
+            let (Some(source), Some(linked_column)) =
+                (schemas.get(&relation.table), schema.table.column(&relation.linked_column))
+            else {
+                continue;
+            };
+            let Some(column) = source.table.column(&relation.column) else {
+                continue;
+            };
+            node = node.field(connection_field(
+                format!("{}By{}", lower_first(&source.plural), upper_first(&column.field_name)),
+                Arc::clone(source),
+                options,
+                Some((column.name.clone(), linked_column.field_name.clone())),
+            ));
+        }
+    }
+
+    node
+}
+
+fn register_table(
+    mut builder: SchemaBuilder,
+    query: Object,
+    schema: &Arc<TableSchema>,
+    schemas: &HashMap<String, Arc<TableSchema>>,
+    options: &SchemaOptions,
+) -> (SchemaBuilder, Object) {
+    let edge = Object::new(schema.edge_name())
+        .field(Field::new("cursor", TypeRef::named("Cursor"), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                Ok(Some(Value::from(row.cursor.encode())))
+            })
+        }))
+        .field(Field::new("node", TypeRef::named_nn(&schema.table.type_name), |ctx| {
+            FieldFuture::new(async move {
+                let row = ctx.parent_value.try_downcast_ref::<TableRow>()?;
+                Ok(Some(FieldValue::borrowed_any(row)))
+            })
+        }));
+
+    let table_schema = Arc::clone(schema);
+    let connection = Object::new(schema.connection_name())
+        .field(Field::new("nodes", TypeRef::named_nn_list_nn(&schema.table.type_name), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(Some(FieldValue::list(
+                    connection.page.rows.iter().map(|row| FieldValue::borrowed_any(row)),
+                )))
+            })
+        }))
+        .field(Field::new("edges", TypeRef::named_nn_list_nn(schema.edge_name()), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(Some(FieldValue::list(
+                    connection.page.rows.iter().map(|row| FieldValue::borrowed_any(row)),
+                )))
+            })
+        }))
+        .field(Field::new("pageInfo", TypeRef::named_nn("PageInfo"), |ctx| {
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                Ok(Some(FieldValue::borrowed_any(connection)))
+            })
+        }))
+        .field(Field::new("totalCount", TypeRef::named_nn(TypeRef::INT), move |ctx| {
+            let schema = Arc::clone(&table_schema);
+            FieldFuture::new(async move {
+                let connection = ctx.parent_value.try_downcast_ref::<ConnectionValue>()?;
+                let context = ctx.data::<GraphQLContext>()?;
+                let count =
+                    connection.query.count(&context.client, &schema.table, context.timeout).await?;
+                Ok(Some(Value::from(count)))
+            })
+        }));
+
+    let mut order_by_names: Vec<&String> = schema.order_by.keys().collect();
+    order_by_names.sort();
+    let order_by = Enum::new(schema.order_by_name()).items(order_by_names);
+
+    let mut condition = InputObject::new(schema.condition_name());
+    for column in &schema.filter_columns {
+        condition = condition.field(InputValue::new(
+            &column.field_name,
+            TypeRef::named(scalar_name(column.column_type)),
+        ));
+    }
+
+    if !options.disable_advanced_filters {
+        let mut filter = InputObject::new(schema.filter_name())
+            .field(InputValue::new("and", TypeRef::named_nn_list(schema.filter_name())))
+            .field(InputValue::new("or", TypeRef::named_nn_list(schema.filter_name())))
+            .field(InputValue::new("not", TypeRef::named(schema.filter_name())));
+        for column in &schema.filter_columns {
+            filter = filter.field(InputValue::new(
+                &column.field_name,
+                TypeRef::named(filter_type_name(column.column_type)),
+            ));
+        }
+        builder = builder.register(filter);
+    }
+
+    let all = connection_field(format!("all{}", schema.plural), Arc::clone(schema), options, None);
+
+    let node_schema = Arc::clone(schema);
+    let by_node_id =
+        Field::new(schema.single_name(), TypeRef::named(&schema.table.type_name), move |ctx| {
+            let schema = Arc::clone(&node_schema);
+            FieldFuture::new(async move {
+                let node_id = ctx.args.try_get("nodeId")?;
+                match decode_node_id(node_id.string()?) {
+                    Some((plural, rindexer_id)) if plural == schema.plural => {
+                        fetch_one(&ctx, &schema, "rindexer_id", rindexer_id.to_string()).await
+                    }
+                    _ => Ok(None),
+                }
+            })
+        })
+        .argument(InputValue::new("nodeId", TypeRef::named_nn(TypeRef::ID)));
+
+    let rindexer_id_schema = Arc::clone(schema);
+    let by_rindexer_id = Field::new(
+        format!("{}ByRindexerId", schema.single_name()),
+        TypeRef::named(&schema.table.type_name),
+        move |ctx| {
+            let schema = Arc::clone(&rindexer_id_schema);
+            FieldFuture::new(async move {
+                let rindexer_id = ctx.args.try_get("rindexerId")?.i64()?;
+                fetch_one(&ctx, &schema, "rindexer_id", rindexer_id.to_string()).await
+            })
+        },
+    )
+    .argument(InputValue::new("rindexerId", TypeRef::named_nn(TypeRef::INT)));
+
+    let builder = builder
+        .register(node_object(schema, schemas, options))
+        .register(edge)
+        .register(connection)
+        .register(order_by)
+        .register(condition);
+
+    (builder, query.field(all).field(by_node_id).field(by_rindexer_id))
+}
+
+/// All the rows of the table matching the query which were written by the indexed events
+async fn indexed_rows(
+    context: &GraphQLContext,
+    schema: &TableSchema,
+    query: &TableQuery,
+    events: &IndexedEvents,
+) -> Result<Vec<TableRow>> {
+    let mut query = TableQuery { first: Some(context.page_limit), ..query.clone() };
+    query.filters.extend([
+        Filter::Column {
+            column: "network".to_string(),
+            operator: FilterOperator::EqualTo,
+            value: FilterValue::Single(events.network.clone()),
+        },
+        Filter::Column {
+            column: "block_number".to_string(),
+            operator: FilterOperator::GreaterThanOrEqualTo,
+            value: FilterValue::Single(events.from_block.to_string()),
+        },
+        Filter::Column {
+            column: "block_number".to_string(),
+            operator: FilterOperator::LessThanOrEqualTo,
+            value: FilterValue::Single(events.to_block.to_string()),
+        },
+    ]);
+
+    let mut rows = Vec::new();
+    loop {
+        let page = query
+            .fetch(&context.client, &schema.table, context.page_limit, context.timeout)
+            .await?;
+        query.after = page.rows.last().map(|row| row.cursor.clone());
+        rows.extend(page.rows);
+        if !page.has_next_page || query.after.is_none() {
+            return Ok(rows);
+        }
+    }
+}
+
+/// Streams every new row of the table matching the condition and filter once it is safe from
+/// reorgs
+fn indexed_field(schema: &Arc<TableSchema>, options: &SchemaOptions) -> SubscriptionField {
+    let table_schema = Arc::clone(schema);
+    let field = SubscriptionField::new(
+        format!("{}Indexed", schema.single_name()),
+        TypeRef::named_nn(&schema.table.type_name),
+        move |ctx| {
+            let schema = Arc::clone(&table_schema);
+            SubscriptionFieldFuture::new(async move {
+                let query = parse_query(&schema, &ctx)?;
+                let context = ctx.data::<GraphQLContext>()?;
+                let receiver = ctx.data::<IndexedEventsFeed>()?.subscribe();
+                let table_name = schema.table.full_name();
+
+                let indexed_events = stream::unfold(receiver, move |mut receiver| {
+                    let table_name = table_name.clone();
+                    async move {
+                        loop {
+                            match receiver.recv().await {
+                                Ok(events) if events.table_name == table_name => {
+                                    return Some((events, receiver))
+                                }
+                                Ok(_) => {}
+                                Err(RecvError::Lagged(skipped)) => {
+                                    warn!("GraphQL subscription for {} fell behind - skipped {} indexed block ranges", table_name, skipped);
+                                }
+                                Err(RecvError::Closed) => return None,
+                            }
+                        }
+                    }
+                });
+
+                Ok(indexed_events
+                    .then(move |events| {
+                        let schema = Arc::clone(&schema);
+                        let query = query.clone();
+                        async move { indexed_rows(context, &schema, &query, &events).await }
+                    })
+                    .flat_map(|rows| {
+                        let values: Vec<Result<FieldValue>> = match rows {
+                            Ok(rows) => rows.into_iter().map(|row| Ok(FieldValue::owned_any(row))).collect(),
+                            Err(e) => vec![Err(e)],
+                        };
+                        stream::iter(values)
+                    }))
+            })
+        },
+    )
+    .argument(InputValue::new("condition", TypeRef::named(schema.condition_name())));
+
+    if options.disable_advanced_filters {
+        field
+    } else {
+        field.argument(InputValue::new("filter", TypeRef::named(schema.filter_name())))
+    }
+}
+
+/// Builds the GraphQL schema for the event tables, the caller adds the `GraphQLContext` and
+/// `IndexedEventsFeed` data
+pub fn build_schema(tables: Vec<EventTable>, options: &SchemaOptions) -> SchemaBuilder {
+    let schemas: Vec<Arc<TableSchema>> =
+        tables.into_iter().map(|table| Arc::new(TableSchema::new(table, options))).collect();
+    let schemas_by_table: HashMap<String, Arc<TableSchema>> =
+        schemas.iter().map(|schema| (schema.table.full_name(), Arc::clone(schema))).collect();
+
+    let mut query =
+        Object::new("Query").field(Field::new("nodeId", TypeRef::named_nn(TypeRef::ID), |_| {
+            FieldFuture::from_value(Some(Value::from("query")))
+        }));
+
+    let subscription_name = if schemas.is_empty() { None } else { Some("Subscription") };
+    let mut builder = register_shared_types(async_graphql::dynamic::Schema::build(
+        "Query",
+        None,
+        subscription_name,
+    ));
+    let mut subscription = Subscription::new("Subscription");
+
+    for schema in &schemas {
+        (builder, query) = register_table(builder, query, schema, &schemas_by_table, options);
+        subscription = subscription.field(indexed_field(schema, options));
+    }
+
+    if subscription_name.is_some() {
+        builder = builder.register(subscription);
+    }
+
+    builder.register(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abi::ABIInput,
+        api::tables::{event_columns, transfer_table},
+    };
+
+    #[test]
+    fn test_build_schema() {
+        let options = SchemaOptions {
+            disable_advanced_filters: false,
+            indexed_columns: None,
+            relations: vec![],
+        };
+        let sdl = build_schema(vec![transfer_table()], &options).finish().unwrap().sdl();
+
+        assert!(sdl.contains("allTransfers("));
+        assert!(sdl.contains("transfer(nodeId: ID!): Transfer"));
+        assert!(sdl.contains("transferByRindexerId(rindexerId: Int!): Transfer"));
+        assert!(sdl.contains("BLOCK_NUMBER_DESC"));
+        assert!(sdl.contains("input TransferFilter"));
+        assert!(sdl.contains("blockNumber: BigFloat!"));
+        assert!(sdl.contains(
+            "transferIndexed(condition: TransferCondition, filter: TransferFilter): Transfer!"
+        ));
+
+        let options = SchemaOptions {
+            disable_advanced_filters: true,
+            indexed_columns: Some(HashSet::from([
+                "indexer_rocket_pool_eth.transfer.rindexer_id".to_string()
+            ])),
+            relations: vec![],
+        };
+        let sdl = build_schema(vec![transfer_table()], &options).finish().unwrap().sdl();
+
+        assert!(!sdl.contains("input TransferFilter"));
+        assert!(!sdl.contains("BLOCK_NUMBER_DESC"));
+    }
+
+    #[test]
+    fn test_relations() {
+        let approval = EventTable {
+            contract_name: "RocketPoolETH".to_string(),
+            event_name: "Approval".to_string(),
+            schema_name: "indexer_rocket_pool_eth".to_string(),
+            table_name: "approval".to_string(),
+            type_name: "Approval".to_string(),
+            columns: event_columns(
+                &serde_json::from_value::<Vec<ABIInput>>(serde_json::json!([
+                    { "indexed": true, "name": "owner", "type": "address" }
+                ]))
+                .unwrap(),
+            ),
+        };
+        let options = SchemaOptions {
+            disable_advanced_filters: false,
+            indexed_columns: None,
+            relations: vec![TableRelation {
+                table: "indexer_rocket_pool_eth.transfer".to_string(),
+                column: "from".to_string(),
+                linked_table: "indexer_rocket_pool_eth.approval".to_string(),
+                linked_column: "owner".to_string(),
+            }],
+        };
+        let sdl = build_schema(vec![transfer_table(), approval], &options).finish().unwrap().sdl();
+
+        assert!(sdl.contains("approvalByFrom: Approval"));
+        assert!(sdl.contains("transfersByFrom("));
+    }
+
+    #[test]
+    fn test_node_id_round_trip() {
+        let node_id = encode_node_id("Transfers", &serde_json::json!(12));
+        assert_eq!(decode_node_id(&node_id), Some(("Transfers".to_string(), 12)));
+        assert_eq!(decode_node_id("nope"), None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use ethers::types::U64;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+use crate::indexer::indexed_events::{subscribe_indexed_events, IndexedEvents};
+
+/// Indexed events which are past the reorg safe distance, shared by all the subscriptions
+#[derive(Clone)]
+pub struct IndexedEventsFeed {
+    sender: broadcast::Sender<IndexedEvents>,
+}
+
+impl IndexedEventsFeed {
+    /// Listens to the indexer and holds back events until their network has indexed far enough
+    /// past them to be safe from reorgs
+    pub fn start() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        let feed = Self { sender: sender.clone() };
+        let mut receiver = subscribe_indexed_events();
+
+        tokio::spawn(async move {
+            let mut latest_blocks: HashMap<String, U64> = HashMap::new();
+            let mut pending: Vec<IndexedEvents> = Vec::new();
+
+            loop {
+                let events = match receiver.recv().await {
+                    Ok(events) => events,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("GraphQL subscriptions fell behind the indexer - skipped {} indexed block ranges", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let latest_block = latest_blocks.entry(events.network.clone()).or_default();
+                if events.to_block > *latest_block {
+                    *latest_block = events.to_block;
+                }
+
+                if events.has_events {
+                    pending.push(events);
+                }
+
+                let (safe, waiting): (Vec<_>, Vec<_>) =
+                    pending.drain(..).partition(|events| is_reorg_safe(events, &latest_blocks));
+                pending = waiting;
+                for events in safe {
+                    // an error only means no subscription is listening
+                    let _ = sender.send(events);
+                }
+            }
+        });
+
+        feed
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<IndexedEvents> {
+        self.sender.subscribe()
+    }
+}
+
+fn is_reorg_safe(events: &IndexedEvents, latest_blocks: &HashMap<String, U64>) -> bool {
+    latest_blocks
+        .get(&events.network)
+        .is_some_and(|latest_block| *latest_block >= events.to_block + events.reorg_safe_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_reorg_safe() {
+        let events = IndexedEvents {
+            table_name: "indexer_rocket_pool_eth.transfer".to_string(),
+            network: "ethereum".to_string(),
+            from_block: U64::from(100),
+            to_block: U64::from(100),
+            has_events: true,
+            reorg_safe_distance: U64::from(12),
+        };
+
+        let mut latest_blocks = HashMap::from([("ethereum".to_string(), U64::from(111))]);
+        assert!(!is_reorg_safe(&events, &latest_blocks));
+
+        latest_blocks.insert("ethereum".to_string(), U64::from(112));
+        assert!(is_reorg_safe(&events, &latest_blocks));
+
+        let other_network = IndexedEvents { network: "base".to_string(), ..events };
+        assert!(!is_reorg_safe(&other_network, &latest_blocks));
+    }
+}
@@ -1,6 +1,10 @@
 mod generate_operations;
 mod generate_schema;
 mod graphql;
+mod query;
+mod rest;
+mod tables;
 
 pub use generate_schema::generate_graphql_queries;
 pub use graphql::{start_graphql_server, GraphqlOverrideSettings, StartGraphqlServerError};
+pub use rest::{start_rest_server, RestOverrideSettings};
@@ -0,0 +1,501 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    api::tables::{EventTable, EventTableColumn},
+    database::postgres::client::{PostgresClient, PostgresError},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError {
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+
+    #[error("Column {0} can not be filtered or ordered on")]
+    ColumnNotFilterable(String),
+
+    #[error("Invalid cursor")]
+    InvalidCursor,
+
+    #[error("Pagination limit exceeded. Maximum allowed is {0}.")]
+    PageLimitExceeded(u64),
+
+    #[error("Can not use first and last at the same time")]
+    FirstAndLast,
+
+    #[error("{0}")]
+    Postgres(#[from] PostgresError),
+
+    #[error("Could not parse row: {0}")]
+    InvalidRow(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOperator {
+    IsNull,
+    EqualTo,
+    NotEqualTo,
+    DistinctFrom,
+    NotDistinctFrom,
+    LessThan,
+    LessThanOrEqualTo,
+    GreaterThan,
+    GreaterThanOrEqualTo,
+    In,
+    NotIn,
+}
+
+impl FilterOperator {
+    pub const ALL: [FilterOperator; 11] = [
+        FilterOperator::IsNull,
+        FilterOperator::EqualTo,
+        FilterOperator::NotEqualTo,
+        FilterOperator::DistinctFrom,
+        FilterOperator::NotDistinctFrom,
+        FilterOperator::LessThan,
+        FilterOperator::LessThanOrEqualTo,
+        FilterOperator::GreaterThan,
+        FilterOperator::GreaterThanOrEqualTo,
+        FilterOperator::In,
+        FilterOperator::NotIn,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterOperator::IsNull => "isNull",
+            FilterOperator::EqualTo => "equalTo",
+            FilterOperator::NotEqualTo => "notEqualTo",
+            FilterOperator::DistinctFrom => "distinctFrom",
+            FilterOperator::NotDistinctFrom => "notDistinctFrom",
+            FilterOperator::LessThan => "lessThan",
+            FilterOperator::LessThanOrEqualTo => "lessThanOrEqualTo",
+            FilterOperator::GreaterThan => "greaterThan",
+            FilterOperator::GreaterThanOrEqualTo => "greaterThanOrEqualTo",
+            FilterOperator::In => "in",
+            FilterOperator::NotIn => "notIn",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|operator| operator.name() == name)
+    }
+
+    fn sql(&self) -> &'static str {
+        match self {
+            FilterOperator::EqualTo => "=",
+            FilterOperator::NotEqualTo => "<>",
+            FilterOperator::DistinctFrom => "IS DISTINCT FROM",
+            FilterOperator::NotDistinctFrom => "IS NOT DISTINCT FROM",
+            FilterOperator::LessThan => "<",
+            FilterOperator::LessThanOrEqualTo => "<=",
+            FilterOperator::GreaterThan => ">",
+            FilterOperator::GreaterThanOrEqualTo => ">=",
+            FilterOperator::IsNull | FilterOperator::In | FilterOperator::NotIn => {
+                unreachable!("operator has no binary sql")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterValue {
+    Bool(bool),
+    Single(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Column { column: String, operator: FilterOperator, value: FilterValue },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderBy {
+    pub column: String,
+    pub descending: bool,
+}
+
+/// The values of the order by columns of a row, used for keyset pagination
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor(Vec<String>);
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        STANDARD.encode(serde_json::to_string(&self.0).expect("Failed to serialize cursor"))
+    }
+
+    pub fn decode(value: &str) -> Result<Self, QueryError> {
+        let bytes = STANDARD.decode(value).map_err(|_| QueryError::InvalidCursor)?;
+        let values: Vec<String> =
+            serde_json::from_slice(&bytes).map_err(|_| QueryError::InvalidCursor)?;
+        Ok(Self(values))
+    }
+}
+
+#[derive(Debug)]
+pub struct TableRow {
+    pub cursor: Cursor,
+    pub data: Value,
+}
+
+#[derive(Debug)]
+pub struct TablePage {
+    pub rows: Vec<TableRow>,
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+}
+
+type SqlParam = Box<dyn ToSql + Sync + Send>;
+
+#[derive(Default)]
+struct SqlParams {
+    values: Vec<SqlParam>,
+}
+
+impl SqlParams {
+    fn push_text(&mut self, value: String) -> String {
+        self.values.push(Box::new(value));
+        format!("${}::TEXT", self.values.len())
+    }
+
+    fn push_list(&mut self, values: Vec<String>) -> String {
+        self.values.push(Box::new(values));
+        format!("${}::TEXT[]", self.values.len())
+    }
+
+    fn as_refs(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.values.iter().map(|value| value.as_ref() as &(dyn ToSql + Sync)).collect()
+    }
+}
+
+/// Builds the SQL to page over an event table using connection style pagination
+#[derive(Debug, Clone, Default)]
+pub struct TableQuery {
+    pub filters: Vec<Filter>,
+    pub order_by: Vec<OrderBy>,
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+    pub offset: Option<u64>,
+    pub after: Option<Cursor>,
+    pub before: Option<Cursor>,
+}
+
+impl TableQuery {
+    fn filterable_column<'a>(
+        table: &'a EventTable,
+        name: &str,
+    ) -> Result<&'a EventTableColumn, QueryError> {
+        let column =
+            table.column(name).ok_or_else(|| QueryError::UnknownColumn(name.to_string()))?;
+        if !column.is_filterable() {
+            return Err(QueryError::ColumnNotFilterable(name.to_string()));
+        }
+        Ok(column)
+    }
+
+    fn filter_sql(
+        table: &EventTable,
+        filter: &Filter,
+        params: &mut SqlParams,
+    ) -> Result<String, QueryError> {
+        let combine = |filters: &[Filter], joiner: &str, params: &mut SqlParams| {
+            if filters.is_empty() {
+                return Ok("TRUE".to_string());
+            }
+            let parts = filters
+                .iter()
+                .map(|filter| Self::filter_sql(table, filter, params))
+                .collect::<Result<Vec<_>, QueryError>>()?;
+            Ok(format!("({})", parts.join(joiner)))
+        };
+
+        match filter {
+            Filter::And(filters) => combine(filters, " AND ", params),
+            Filter::Or(filters) => combine(filters, " OR ", params),
+            Filter::Not(filter) => {
+                Ok(format!("NOT ({})", Self::filter_sql(table, filter, params)?))
+            }
+            Filter::Column { column, operator, value } => {
+                let column = Self::filterable_column(table, column)?;
+                let name = format!("\"{}\"", column.name);
+                let sql = match (operator, value) {
+                    (FilterOperator::IsNull, FilterValue::Bool(true)) => {
+                        format!("{} IS NULL", name)
+                    }
+                    (FilterOperator::IsNull, _) => format!("{} IS NOT NULL", name),
+                    (FilterOperator::In, FilterValue::List(values)) |
+                    (FilterOperator::NotIn, FilterValue::List(values)) => {
+                        let list = params.push_list(values.clone());
+                        let sql = format!(
+                            "{} = ANY(ARRAY(SELECT {} FROM unnest({}) AS v))",
+                            name,
+                            column.cast_sql("v"),
+                            list
+                        );
+                        if *operator == FilterOperator::NotIn {
+                            format!("NOT ({})", sql)
+                        } else {
+                            sql
+                        }
+                    }
+                    (operator, FilterValue::Single(value)) => {
+                        let value = params.push_text(value.clone());
+                        format!("{} {} {}", name, operator.sql(), column.cast_sql(&value))
+                    }
+                    (operator, FilterValue::Bool(value)) => {
+                        let value = params.push_text(value.to_string());
+                        format!("{} {} {}", name, operator.sql(), column.cast_sql(&value))
+                    }
+                    (_, FilterValue::List(_)) => "TRUE".to_string(),
+                };
+                Ok(sql)
+            }
+        }
+    }
+
+    fn where_sql(&self, table: &EventTable, params: &mut SqlParams) -> Result<String, QueryError> {
+        if self.filters.is_empty() {
+            return Ok("TRUE".to_string());
+        }
+
+        Self::filter_sql(table, &Filter::And(self.filters.clone()), params)
+    }
+
+    /// The order by with the primary key always last so the order is stable for the cursors
+    fn full_order_by(&self, table: &EventTable) -> Result<Vec<OrderBy>, QueryError> {
+        let mut order_by = self.order_by.clone();
+        for order in &order_by {
+            Self::filterable_column(table, &order.column)?;
+        }
+        if !order_by.iter().any(|order| order.column == "rindexer_id") {
+            order_by.push(OrderBy { column: "rindexer_id".to_string(), descending: false });
+        }
+        Ok(order_by)
+    }
+
+    /// Rows after the cursor in the order, or before it if `after` is false
+    fn cursor_sql(
+        table: &EventTable,
+        order_by: &[OrderBy],
+        cursor: &Cursor,
+        after: bool,
+        params: &mut SqlParams,
+    ) -> Result<String, QueryError> {
+        if cursor.0.len() != order_by.len() {
+            return Err(QueryError::InvalidCursor);
+        }
+
+        let values = order_by
+            .iter()
+            .zip(&cursor.0)
+            .map(|(order, value)| {
+                let column = Self::filterable_column(table, &order.column)?;
+                let value = column.cast_sql(&params.push_text(value.clone()));
+                Ok((format!("\"{}\"", column.name), value, order.descending))
+            })
+            .collect::<Result<Vec<_>, QueryError>>()?;
+
+        let conditions = (0..values.len())
+            .map(|index| {
+                let mut parts: Vec<String> = values[..index]
+                    .iter()
+                    .map(|(name, value, _)| format!("{} = {}", name, value))
+                    .collect();
+                let (name, value, descending) = &values[index];
+                let operator = if *descending == after { "<" } else { ">" };
+                parts.push(format!("{} {} {}", name, operator, value));
+                format!("({})", parts.join(" AND "))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(format!("({})", conditions.join(" OR ")))
+    }
+
+    /// Returns the page size and if the page is taken from the end
+    pub fn page_size(&self, page_limit: u64) -> Result<(u64, bool), QueryError> {
+        match (self.first, self.last) {
+            (Some(_), Some(_)) => Err(QueryError::FirstAndLast),
+            (Some(first), None) if first > page_limit => {
+                Err(QueryError::PageLimitExceeded(page_limit))
+            }
+            (None, Some(last)) if last > page_limit => {
+                Err(QueryError::PageLimitExceeded(page_limit))
+            }
+            (Some(first), None) => Ok((first, false)),
+            (None, Some(last)) => Ok((last, true)),
+            (None, None) => Ok((page_limit, false)),
+        }
+    }
+
+    fn page_sql(
+        &self,
+        table: &EventTable,
+        page_limit: u64,
+    ) -> Result<(String, SqlParams), QueryError> {
+        let (size, from_end) = self.page_size(page_limit)?;
+        let order_by = self.full_order_by(table)?;
+        let mut params = SqlParams::default();
+
+        let mut conditions = vec![self.where_sql(table, &mut params)?];
+        if let Some(after) = &self.after {
+            conditions.push(Self::cursor_sql(table, &order_by, after, true, &mut params)?);
+        }
+        if let Some(before) = &self.before {
+            conditions.push(Self::cursor_sql(table, &order_by, before, false, &mut params)?);
+        }
+
+        let fields = table
+            .columns
+            .iter()
+            .map(|column| format!("{} AS \"{}\"", column.select_sql(), column.field_name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut cursor_columns = vec![];
+        for order in &order_by {
+            let column = Self::filterable_column(table, &order.column)?;
+            cursor_columns.push(format!("({})::TEXT", column.select_sql()));
+        }
+
+        let order_sql = order_by
+            .iter()
+            .map(|order| {
+                // taking the last rows means reading the order backwards then reversing them
+                let descending = order.descending != from_end;
+                format!("\"{}\" {}", order.column, if descending { "DESC" } else { "ASC" })
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let sql = format!(
+            "SELECT (SELECT row_to_json(d) FROM (SELECT {}) d)::TEXT, ARRAY[{}] FROM {} WHERE {} ORDER BY {} LIMIT {} OFFSET {}",
+            fields,
+            cursor_columns.join(", "),
+            table.full_name(),
+            conditions.join(" AND "),
+            order_sql,
+            // fetch one more row to know if there is another page
+            size + 1,
+            self.offset.unwrap_or(0)
+        );
+
+        Ok((sql, params))
+    }
+
+    pub async fn fetch(
+        &self,
+        client: &PostgresClient,
+        table: &EventTable,
+        page_limit: u64,
+        timeout_ms: u64,
+    ) -> Result<TablePage, QueryError> {
+        let (size, from_end) = self.page_size(page_limit)?;
+        let (sql, params) = self.page_sql(table, page_limit)?;
+        let rows = client.query_with_timeout(&sql, &params.as_refs(), timeout_ms).await?;
+
+        let has_more = rows.len() as u64 > size;
+        let mut rows = rows
+            .into_iter()
+            .take(size as usize)
+            .map(|row| {
+                let data: String = row.get(0);
+                let cursor: Vec<String> = row.get(1);
+                Ok(TableRow { cursor: Cursor(cursor), data: serde_json::from_str(&data)? })
+            })
+            .collect::<Result<Vec<_>, QueryError>>()?;
+
+        if from_end {
+            rows.reverse();
+            Ok(TablePage {
+                rows,
+                has_next_page: self.before.is_some(),
+                has_previous_page: has_more,
+            })
+        } else {
+            Ok(TablePage {
+                rows,
+                has_next_page: has_more,
+                has_previous_page: self.after.is_some() || self.offset.unwrap_or(0) > 0,
+            })
+        }
+    }
+
+    pub async fn count(
+        &self,
+        client: &PostgresClient,
+        table: &EventTable,
+        timeout_ms: u64,
+    ) -> Result<i64, QueryError> {
+        let mut params = SqlParams::default();
+        let sql = format!(
+            "SELECT COUNT(*) FROM {} WHERE {}",
+            table.full_name(),
+            self.where_sql(table, &mut params)?
+        );
+        let rows = client.query_with_timeout(&sql, &params.as_refs(), timeout_ms).await?;
+
+        Ok(rows.first().map(|row| row.get(0)).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tables::transfer_table;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor(vec!["18600000".to_string(), "1".to_string()]);
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_page_sql() {
+        let table = transfer_table();
+        let query = TableQuery {
+            filters: vec![Filter::Or(vec![
+                Filter::Column {
+                    column: "from".to_string(),
+                    operator: FilterOperator::EqualTo,
+                    value: FilterValue::Single("0x1".to_string()),
+                },
+                Filter::Column {
+                    column: "block_number".to_string(),
+                    operator: FilterOperator::In,
+                    value: FilterValue::List(vec!["1".to_string(), "2".to_string()]),
+                },
+            ])],
+            order_by: vec![OrderBy { column: "block_number".to_string(), descending: true }],
+            first: Some(10),
+            after: Some(Cursor(vec!["5".to_string(), "7".to_string()])),
+            ..Default::default()
+        };
+
+        let (sql, params) = query.page_sql(&table, 1000).unwrap();
+        assert!(sql.contains(
+            "(\"from\" = CAST($1::TEXT AS CHAR(42)) OR \"block_number\" = ANY(ARRAY(SELECT CAST(v AS NUMERIC) FROM unnest($2::TEXT[]) AS v)))"
+        ));
+        assert!(sql.contains(
+            "((\"block_number\" < CAST($3::TEXT AS NUMERIC)) OR (\"block_number\" = CAST($3::TEXT AS NUMERIC) AND \"rindexer_id\" > CAST($4::TEXT AS INTEGER)))"
+        ));
+        assert!(
+            sql.contains("ORDER BY \"block_number\" DESC, \"rindexer_id\" ASC LIMIT 11 OFFSET 0")
+        );
+        assert_eq!(params.values.len(), 4);
+    }
+
+    #[test]
+    fn test_page_size() {
+        let query = TableQuery { first: Some(2000), ..Default::default() };
+        assert!(matches!(query.page_size(1000), Err(QueryError::PageLimitExceeded(1000))));
+
+        let query = TableQuery { last: Some(5), ..Default::default() };
+        assert_eq!(query.page_size(1000).unwrap(), (5, true));
+        assert_eq!(TableQuery::default().page_size(1000).unwrap(), (1000, false));
+    }
+}
@@ -0,0 +1,239 @@
+mod openapi;
+mod params;
+
+use std::{collections::HashMap, net::SocketAddr, path::Path, sync::Arc};
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tower_http::cors::CorsLayer;
+use tracing::{error, info};
+
+use crate::{
+    api::{
+        query::{QueryError, TablePage},
+        rest::{
+            openapi::openapi_document,
+            params::{parse_params, ResponseFormat},
+        },
+        tables::{event_tables, EventTable},
+    },
+    database::postgres::{
+        client::{PostgresClient, PostgresConnectionError},
+        generate::GenerateTablesForIndexerSqlError,
+    },
+    indexer::Indexer,
+    manifest::rest::RestSettings,
+    shutdown::shutdown_signal,
+};
+
+/// The REST API is off unless it is enabled
+#[derive(Default)]
+pub struct RestOverrideSettings {
+    pub enabled: bool,
+    pub override_port: Option<u16>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StartRestServerError {
+    #[error("Could not connect to postgres: {0}")]
+    PostgresConnectionError(#[from] PostgresConnectionError),
+
+    #[error("Could not read the event tables: {0}")]
+    EventTables(#[from] GenerateTablesForIndexerSqlError),
+
+    #[error("Could not start up REST server {0}")]
+    RestServerStartupError(String),
+}
+
+struct RestState {
+    client: PostgresClient,
+    /// The tables by the lowercase contract and event name
+    tables: HashMap<(String, String), EventTable>,
+    openapi: Value,
+    page_limit: u64,
+    timeout: u64,
+}
+
+fn table_key(contract_name: &str, event_name: &str) -> (String, String) {
+    (contract_name.to_lowercase(), event_name.to_lowercase())
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn csv_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn write_csv(table: &EventTable, page: &TablePage) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(table.columns.iter().map(|column| &column.field_name))?;
+    for row in &page.rows {
+        writer.write_record(
+            table.columns.iter().map(|column| csv_value(row.data.get(&column.field_name))),
+        )?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn csv_response(table: &EventTable, page: &TablePage) -> Response {
+    let body = match write_csv(table, page) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Could not write the events for {} as csv: {}", table.full_name(), e);
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not write the events as csv".to_string(),
+            );
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/csv"));
+    // csv has no room for the page info so the cursors are sent as headers
+    let cursors = [
+        ("x-next-cursor", page.has_next_page, page.rows.last()),
+        ("x-previous-cursor", page.has_previous_page, page.rows.first()),
+    ];
+    for (name, has_page, row) in cursors {
+        if let (true, Some(row)) = (has_page, row) {
+            if let Ok(value) = HeaderValue::from_str(&row.cursor.encode()) {
+                headers.insert(name, value);
+            }
+        }
+    }
+
+    (headers, body).into_response()
+}
+
+fn json_response(page: TablePage) -> Response {
+    let start_cursor = page.rows.first().map(|row| row.cursor.encode());
+    let end_cursor = page.rows.last().map(|row| row.cursor.encode());
+
+    Json(json!({
+        "data": page.rows.into_iter().map(|row| row.data).collect::<Vec<_>>(),
+        "pageInfo": {
+            "hasNextPage": page.has_next_page,
+            "hasPreviousPage": page.has_previous_page,
+            "startCursor": start_cursor,
+            "endCursor": end_cursor,
+        },
+    }))
+    .into_response()
+}
+
+async fn events(
+    State(state): State<Arc<RestState>>,
+    UrlPath((contract_name, event_name)): UrlPath<(String, String)>,
+    Query(params): Query<Vec<(String, String)>>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(table) = state.tables.get(&table_key(&contract_name, &event_name)) else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            format!("No events are indexed for {}/{}", contract_name, event_name),
+        );
+    };
+
+    let (query, mut format) = match parse_params(table, &params, state.page_limit) {
+        Ok(result) => result,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+
+    let accepts_csv = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/csv"));
+    if accepts_csv && !params.iter().any(|(key, _)| key == "format") {
+        format = ResponseFormat::Csv;
+    }
+
+    let page = match query.fetch(&state.client, table, state.page_limit, state.timeout).await {
+        Ok(page) => page,
+        Err(QueryError::Postgres(e)) => {
+            error!("Could not read the events for {}: {}", table.full_name(), e);
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not read the events".to_string(),
+            );
+        }
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+
+    match format {
+        ResponseFormat::Json => json_response(page),
+        ResponseFormat::Csv => csv_response(table, &page),
+    }
+}
+
+/// Runs the REST API until ctrl-c is pressed
+pub async fn start_rest_server(
+    project_path: &Path,
+    indexer: &Indexer,
+    settings: &RestSettings,
+) -> Result<(), StartRestServerError> {
+    info!("Starting REST server");
+
+    let client = PostgresClient::new().await?;
+    let tables = event_tables(project_path, indexer)?;
+
+    let state = Arc::new(RestState {
+        client,
+        openapi: openapi_document(&indexer.name, &tables),
+        tables: tables
+            .into_iter()
+            .map(|table| (table_key(&table.contract_name, &table.event_name), table))
+            .collect(),
+        page_limit: settings.page_limit,
+        timeout: settings.timeout,
+    });
+
+    let app = Router::new()
+        .route(
+            "/openapi.json",
+            get(|State(state): State<Arc<RestState>>| async move { Json(state.openapi.clone()) }),
+        )
+        .route("/{contract}/{event}", get(events))
+        .fallback(|| async {
+            error_response(
+                StatusCode::NOT_FOUND,
+                "Use /{contract}/{event} to read the events".to_string(),
+            )
+        })
+        .with_state(state)
+        .layer(CorsLayer::permissive());
+
+    let address = SocketAddr::from(([0, 0, 0, 0], settings.port));
+    let listener = TcpListener::bind(address).await.map_err(|e| {
+        StartRestServerError::RestServerStartupError(format!(
+            "Could not bind to port {}: {}",
+            settings.port, e
+        ))
+    })?;
+
+    info!(
+        "🦀 REST API ready at http://localhost:{} OpenAPI - http://localhost:{}/openapi.json 🦀",
+        settings.port, settings.port
+    );
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .map_err(|e| StartRestServerError::RestServerStartupError(e.to_string()))?;
+
+    info!("REST server stopped");
+
+    Ok(())
+}
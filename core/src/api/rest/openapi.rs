@@ -0,0 +1,239 @@
+use serde_json::{json, Map, Value};
+
+use crate::api::{
+    query::FilterOperator,
+    tables::{ColumnType, EventTable, EventTableColumn},
+};
+
+fn column_schema(column: &EventTableColumn) -> Value {
+    let schema = match column.column_type {
+        ColumnType::Int => json!({ "type": "integer" }),
+        ColumnType::Boolean => json!({ "type": "boolean" }),
+        ColumnType::String => json!({ "type": "string" }),
+        ColumnType::BigFloat => {
+            json!({ "type": "string", "description": "A number which can be larger than 64 bits" })
+        }
+        ColumnType::Bytes => json!({ "type": "string", "description": "0x prefixed hex bytes" }),
+    };
+
+    if column.is_array {
+        json!({ "type": "array", "items": schema, "nullable": !column.not_null })
+    } else if !column.not_null {
+        let mut schema = schema;
+        schema["nullable"] = json!(true);
+        schema
+    } else {
+        schema
+    }
+}
+
+fn query_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+fn table_parameters(table: &EventTable) -> Vec<Value> {
+    let string = json!({ "type": "string" });
+    let integer = json!({ "type": "integer", "minimum": 0 });
+    let mut parameters = vec![
+        query_parameter("network", "Only return events from this network", string.clone()),
+        query_parameter("address", "Only return events from this contract address", string.clone()),
+        query_parameter(
+            "from_block",
+            "Only return events from this block onwards",
+            integer.clone(),
+        ),
+        query_parameter(
+            "to_block",
+            "Only return events up to and including this block",
+            integer.clone(),
+        ),
+        query_parameter(
+            "order_by",
+            "Comma separated columns to order by, prefix a column with - to order descending",
+            string.clone(),
+        ),
+        query_parameter("limit", "The maximum number of events to return", integer),
+        query_parameter("after", "Return the events after this cursor", string.clone()),
+        query_parameter("before", "Return the events before this cursor", string),
+        query_parameter(
+            "format",
+            "The response format, csv can also be requested with an Accept: text/csv header",
+            json!({ "type": "string", "enum": ["json", "csv"], "default": "json" }),
+        ),
+    ];
+
+    for column in table.columns.iter().filter(|column| column.is_filterable()) {
+        let schema = column_schema(column);
+        parameters.push(query_parameter(
+            &column.field_name,
+            &format!("Only return events where {} is equal to the value", column.field_name),
+            schema.clone(),
+        ));
+        for operator in FilterOperator::ALL {
+            let (description, schema) = match operator {
+                FilterOperator::EqualTo => continue,
+                FilterOperator::IsNull => (
+                    "true to only return null values, false to only return set values",
+                    json!({ "type": "boolean" }),
+                ),
+                FilterOperator::In | FilterOperator::NotIn => {
+                    ("Comma separated values", json!({ "type": "string" }))
+                }
+                _ => ("", schema.clone()),
+            };
+            parameters.push(query_parameter(
+                &format!("{}.{}", column.field_name, operator.name()),
+                &format!("{} filter on {}. {}", operator.name(), column.field_name, description),
+                schema,
+            ));
+        }
+    }
+
+    parameters
+}
+
+/// The OpenAPI document of the REST API, built from the same event tables as the database
+pub fn openapi_document(name: &str, tables: &[EventTable]) -> Value {
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+
+    schemas.insert(
+        "PageInfo".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "hasNextPage": { "type": "boolean" },
+                "hasPreviousPage": { "type": "boolean" },
+                "startCursor": { "type": "string", "nullable": true },
+                "endCursor": { "type": "string", "nullable": true },
+            },
+            "required": ["hasNextPage", "hasPreviousPage"],
+        }),
+    );
+    schemas.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": { "error": { "type": "string" } },
+            "required": ["error"],
+        }),
+    );
+
+    for table in tables {
+        let properties: Map<String, Value> = table
+            .columns
+            .iter()
+            .map(|column| (column.field_name.clone(), column_schema(column)))
+            .collect();
+        let required: Vec<&String> = table
+            .columns
+            .iter()
+            .filter(|column| column.not_null)
+            .map(|column| &column.field_name)
+            .collect();
+        schemas.insert(
+            table.type_name.clone(),
+            json!({ "type": "object", "properties": properties, "required": required }),
+        );
+
+        let error_content = json!({
+            "application/json": { "schema": { "$ref": "#/components/schemas/Error" } }
+        });
+        paths.insert(
+            format!("/{}/{}", table.contract_name, table.event_name),
+            json!({
+                "get": {
+                    "operationId": format!("get{}{}", table.contract_name, table.event_name),
+                    "summary": format!("{} {} events", table.contract_name, table.event_name),
+                    "tags": [table.contract_name],
+                    "parameters": table_parameters(table),
+                    "responses": {
+                        "200": {
+                            "description": "A page of events",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "data": {
+                                                "type": "array",
+                                                "items": {
+                                                    "$ref": format!("#/components/schemas/{}", table.type_name)
+                                                }
+                                            },
+                                            "pageInfo": { "$ref": "#/components/schemas/PageInfo" },
+                                        },
+                                        "required": ["data", "pageInfo"],
+                                    }
+                                },
+                                "text/csv": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "400": {
+                            "description": "The query parameters are invalid",
+                            "content": error_content.clone(),
+                        },
+                        "500": {
+                            "description": "The events could not be read",
+                            "content": error_content,
+                        },
+                    },
+                }
+            }),
+        );
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": format!("{} REST API", name), "version": env!("CARGO_PKG_VERSION") },
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::ABIInput, api::tables::event_columns};
+
+    #[test]
+    fn test_openapi_document() {
+        let inputs: Vec<ABIInput> = serde_json::from_value(serde_json::json!([
+            { "indexed": true, "name": "from", "type": "address" },
+            { "indexed": false, "name": "ids", "type": "uint256[]" }
+        ]))
+        .unwrap();
+        let table = EventTable {
+            contract_name: "RocketPoolETH".to_string(),
+            event_name: "Transfer".to_string(),
+            schema_name: "indexer_rocket_pool_eth".to_string(),
+            table_name: "transfer".to_string(),
+            type_name: "Transfer".to_string(),
+            columns: event_columns(&inputs),
+        };
+
+        let document = openapi_document("RocketPoolETHIndexer", &[table]);
+        let path = &document["paths"]["/RocketPoolETH/Transfer"]["get"];
+        let parameters: Vec<&str> = path["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|parameter| parameter["name"].as_str().unwrap())
+            .collect();
+
+        assert!(parameters.contains(&"from"));
+        assert!(parameters.contains(&"from.in"));
+        assert!(parameters.contains(&"blockNumber.greaterThan"));
+        assert!(!parameters.contains(&"ids"));
+        assert_eq!(
+            document["components"]["schemas"]["Transfer"]["properties"]["ids"]["type"],
+            "array"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::api::{
+    query::{Cursor, Filter, FilterOperator, FilterValue, OrderBy, QueryError, TableQuery},
+    tables::{EventTable, EventTableColumn},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParamsError {
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+
+    #[error("Unknown filter operator: {0}")]
+    UnknownOperator(String),
+
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, String),
+
+    #[error("Unknown format: {0} - use json or csv")]
+    UnknownFormat(String),
+
+    #[error("Can not use after and before at the same time")]
+    AfterAndBefore,
+
+    #[error("{0}")]
+    Query(#[from] QueryError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+}
+
+/// Finds a column by its camelCase field name or its postgres name
+fn find_column<'a>(table: &'a EventTable, name: &str) -> Result<&'a EventTableColumn, ParamsError> {
+    table
+        .columns
+        .iter()
+        .find(|column| column.field_name == name || column.name == name)
+        .ok_or_else(|| ParamsError::UnknownColumn(name.to_string()))
+}
+
+fn parse_u64(name: &str, value: &str) -> Result<u64, ParamsError> {
+    value.parse().map_err(|_| ParamsError::InvalidValue(name.to_string(), value.to_string()))
+}
+
+fn column_filter(column: &str, operator: FilterOperator, value: &str) -> Filter {
+    Filter::Column {
+        column: column.to_string(),
+        operator,
+        value: FilterValue::Single(value.to_string()),
+    }
+}
+
+/// Parses a `{column}` or `{column}.{operator}` filter
+fn parse_filter(table: &EventTable, key: &str, value: &str) -> Result<Filter, ParamsError> {
+    let (name, operator) = match key.split_once('.') {
+        Some((name, operator)) => (
+            name,
+            FilterOperator::from_name(operator)
+                .ok_or_else(|| ParamsError::UnknownOperator(operator.to_string()))?,
+        ),
+        None => (key, FilterOperator::EqualTo),
+    };
+    let column = find_column(table, name)?;
+
+    let value = match operator {
+        FilterOperator::IsNull => FilterValue::Bool(
+            value
+                .parse()
+                .map_err(|_| ParamsError::InvalidValue(key.to_string(), value.to_string()))?,
+        ),
+        FilterOperator::In | FilterOperator::NotIn => {
+            FilterValue::List(value.split(',').map(|value| value.to_string()).collect())
+        }
+        _ => FilterValue::Single(value.to_string()),
+    };
+
+    Ok(Filter::Column { column: column.name.clone(), operator, value })
+}
+
+/// Turns the query parameters of a request into the query for the table
+pub fn parse_params(
+    table: &EventTable,
+    params: &[(String, String)],
+    page_limit: u64,
+) -> Result<(TableQuery, ResponseFormat), ParamsError> {
+    let mut query = TableQuery::default();
+    let mut format = ResponseFormat::Json;
+    let mut limit = None;
+
+    for (key, value) in params {
+        match key.as_str() {
+            "network" => {
+                query.filters.push(column_filter("network", FilterOperator::EqualTo, value))
+            }
+            "address" => query.filters.push(column_filter(
+                "contract_address",
+                FilterOperator::EqualTo,
+                &value.to_lowercase(),
+            )),
+            "from_block" => query.filters.push(column_filter(
+                "block_number",
+                FilterOperator::GreaterThanOrEqualTo,
+                &parse_u64(key, value)?.to_string(),
+            )),
+            "to_block" => query.filters.push(column_filter(
+                "block_number",
+                FilterOperator::LessThanOrEqualTo,
+                &parse_u64(key, value)?.to_string(),
+            )),
+            "order_by" => {
+                for name in value.split(',').filter(|name| !name.is_empty()) {
+                    let (name, descending) = match name.strip_prefix('-') {
+                        Some(name) => (name, true),
+                        None => (name, false),
+                    };
+                    let column = find_column(table, name)?;
+                    query.order_by.push(OrderBy { column: column.name.clone(), descending });
+                }
+            }
+            "limit" => limit = Some(parse_u64(key, value)?),
+            "after" => query.after = Some(Cursor::decode(value)?),
+            "before" => query.before = Some(Cursor::decode(value)?),
+            "format" => {
+                format = match value.to_lowercase().as_str() {
+                    "json" => ResponseFormat::Json,
+                    "csv" => ResponseFormat::Csv,
+                    _ => return Err(ParamsError::UnknownFormat(value.to_string())),
+                }
+            }
+            _ => query.filters.push(parse_filter(table, key, value)?),
+        }
+    }
+
+    if query.after.is_some() && query.before.is_some() {
+        return Err(ParamsError::AfterAndBefore);
+    }
+
+    // paging backwards takes the rows right before the cursor
+    let limit = limit.unwrap_or(page_limit);
+    if query.before.is_some() {
+        query.last = Some(limit);
+    } else {
+        query.first = Some(limit);
+    }
+    query.page_size(page_limit)?;
+
+    Ok((query, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tables::transfer_table;
+
+    fn params(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let (query, format) = parse_params(
+            &transfer_table(),
+            &params(&[
+                ("from_block", "100"),
+                ("address", "0xAE78736Cd615f374D3085123A210448E74Fc6393"),
+                ("value.greaterThan", "5"),
+                ("to.in", "0x1,0x2"),
+                ("order_by", "-blockNumber,log_index"),
+                ("limit", "10"),
+                ("format", "csv"),
+            ]),
+            100,
+        )
+        .unwrap();
+
+        assert_eq!(format, ResponseFormat::Csv);
+        assert_eq!(query.first, Some(10));
+        assert_eq!(query.filters.len(), 4);
+        assert!(matches!(
+            &query.filters[1],
+            Filter::Column { column, value: FilterValue::Single(value), .. }
+                if column == "contract_address" && value == "0xae78736cd615f374d3085123a210448e74fc6393"
+        ));
+        assert!(matches!(
+            &query.filters[3],
+            Filter::Column { operator: FilterOperator::In, value: FilterValue::List(values), .. }
+                if values.len() == 2
+        ));
+        assert_eq!(query.order_by[0].column, "block_number");
+        assert!(query.order_by[0].descending);
+        assert_eq!(query.order_by[1].column, "log_index");
+    }
+
+    #[test]
+    fn test_parse_params_errors() {
+        let table = transfer_table();
+        assert!(matches!(
+            parse_params(&table, &params(&[("nope", "1")]), 100),
+            Err(ParamsError::UnknownColumn(_))
+        ));
+        assert!(matches!(
+            parse_params(&table, &params(&[("value.between", "1")]), 100),
+            Err(ParamsError::UnknownOperator(_))
+        ));
+        assert!(matches!(
+            parse_params(&table, &params(&[("limit", "101")]), 100),
+            Err(ParamsError::Query(QueryError::PageLimitExceeded(100)))
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+use std::path::Path;
+
+use crate::{
+    abi::{ABIInput, ABIItem, GenerateAbiPropertiesType},
+    database::postgres::generate::{
+        find_clashing_event_names, generate_indexer_contract_schema_name, solidity_type_to_db_type,
+        GenerateTablesForIndexerSqlError,
+    },
+    helpers::camel_to_snake,
+    indexer::Indexer,
+};
+
+/// How a column is exposed over the APIs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Int,
+    Boolean,
+    String,
+    /// NUMERIC columns which do not fit in a GraphQL Int, exposed as a string
+    BigFloat,
+    /// BYTEA columns, exposed as a 0x hex string
+    Bytes,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventTableColumn {
+    /// The column name in postgres
+    pub name: String,
+    /// The camelCase name used by the APIs
+    pub field_name: String,
+    pub db_type: String,
+    pub column_type: ColumnType,
+    pub is_array: bool,
+    pub not_null: bool,
+}
+
+impl EventTableColumn {
+    fn new(name: &str, db_type: &str, not_null: bool) -> Self {
+        let is_array = db_type.ends_with("[]");
+        let base_type = db_type.trim_end_matches("[]");
+        let column_type = match base_type {
+            "SMALLINT" | "INTEGER" => ColumnType::Int,
+            "BOOLEAN" => ColumnType::Boolean,
+            "NUMERIC" => ColumnType::BigFloat,
+            "BYTEA" => ColumnType::Bytes,
+            _ => ColumnType::String,
+        };
+
+        Self {
+            name: name.to_string(),
+            field_name: snake_to_camel(name, false),
+            db_type: db_type.to_string(),
+            column_type,
+            is_array,
+            not_null,
+        }
+    }
+
+    /// The SQL expression to select the column in the format the APIs return it in
+    pub fn select_sql(&self) -> String {
+        let column = format!("\"{}\"", self.name);
+        match (self.column_type, self.is_array) {
+            (ColumnType::Int, _) | (ColumnType::Boolean, _) => column,
+            (ColumnType::Bytes, false) => format!("'0x' || encode({}, 'hex')", column),
+            (ColumnType::Bytes, true) => {
+                format!("ARRAY(SELECT '0x' || encode(v, 'hex') FROM unnest({}) AS v)", column)
+            }
+            (_, false) => format!("{}::TEXT", column),
+            (_, true) => format!("{}::TEXT[]", column),
+        }
+    }
+
+    /// The SQL expression to turn a text value in the format the APIs return it in back into
+    /// the column type
+    pub fn cast_sql(&self, value: &str) -> String {
+        let base_type = self.db_type.trim_end_matches("[]");
+        match self.column_type {
+            ColumnType::Bytes => format!("decode(substring({} from 3), 'hex')", value),
+            _ => format!("CAST({} AS {})", value, base_type),
+        }
+    }
+
+    /// Arrays can not be ordered or filtered on
+    pub fn is_filterable(&self) -> bool {
+        !self.is_array
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventTable {
+    pub contract_name: String,
+    pub event_name: String,
+    pub schema_name: String,
+    pub table_name: String,
+    /// The PascalCase name of a single row, prefixed with the contract name if other contracts
+    /// have an event with the same name
+    pub type_name: String,
+    pub columns: Vec<EventTableColumn>,
+}
+
+impl EventTable {
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.schema_name, self.table_name)
+    }
+
+    pub fn column(&self, name: &str) -> Option<&EventTableColumn> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+pub fn snake_to_camel(value: &str, upper_first: bool) -> String {
+    let mut result = String::new();
+    let mut upper_next = upper_first;
+    for c in value.chars() {
+        if c == '_' {
+            upper_next = !result.is_empty() || upper_first;
+            continue;
+        }
+        if upper_next {
+            result.push(c.to_ascii_uppercase());
+            upper_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+pub fn pluralize(value: &str) -> String {
+    let consonant_before_y = value.ends_with('y') &&
+        !value.ends_with("ay") &&
+        !value.ends_with("ey") &&
+        !value.ends_with("oy") &&
+        !value.ends_with("uy");
+
+    if consonant_before_y {
+        format!("{}ies", &value[..value.len() - 1])
+    } else if value.ends_with('s') ||
+        value.ends_with('x') ||
+        value.ends_with("ch") ||
+        value.ends_with("sh")
+    {
+        format!("{}es", value)
+    } else {
+        format!("{}s", value)
+    }
+}
+
+pub(crate) fn event_columns(inputs: &[ABIInput]) -> Vec<EventTableColumn> {
+    let mut columns = vec![
+        EventTableColumn::new("rindexer_id", "INTEGER", true),
+        EventTableColumn::new("contract_address", "CHAR(66)", true),
+    ];
+
+    columns.extend(
+        ABIInput::generate_abi_name_properties(
+            inputs,
+            &GenerateAbiPropertiesType::PostgresColumnsNamesOnly,
+            None,
+        )
+        .into_iter()
+        .map(|property| {
+            EventTableColumn::new(
+                &property.value,
+                &solidity_type_to_db_type(&property.abi_type),
+                false,
+            )
+        }),
+    );
+
+    columns.extend(vec![
+        EventTableColumn::new("tx_hash", "CHAR(66)", true),
+        EventTableColumn::new("block_number", "NUMERIC", true),
+        EventTableColumn::new("block_hash", "CHAR(66)", true),
+        EventTableColumn::new("network", "VARCHAR(50)", true),
+        EventTableColumn::new("tx_index", "NUMERIC", true),
+        EventTableColumn::new("log_index", "VARCHAR(78)", true),
+    ]);
+
+    columns
+}
+
+/// Describes the event tables created by `generate_tables_for_indexer_sql`
+pub fn event_tables(
+    project_path: &Path,
+    indexer: &Indexer,
+) -> Result<Vec<EventTable>, GenerateTablesForIndexerSqlError> {
+    let mut tables = vec![];
+
+    for contract in &indexer.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly();
+        let abi_items = ABIItem::read_abi_items(project_path, contract)?;
+        let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+        let schema_name = generate_indexer_contract_schema_name(&indexer.name, &contract_name);
+        let clashing_event_names =
+            find_clashing_event_names(project_path, contract, &indexer.contracts, &event_names)?;
+
+        for event_info in &event_names {
+            let table_name = camel_to_snake(&event_info.name);
+            let type_name = if clashing_event_names.contains(&event_info.name) {
+                snake_to_camel(
+                    &camel_to_snake(&format!("{}{}", contract.name, event_info.name)),
+                    true,
+                )
+            } else {
+                snake_to_camel(&table_name, true)
+            };
+
+            tables.push(EventTable {
+                contract_name: contract_name.to_string(),
+                event_name: event_info.name.clone(),
+                schema_name: schema_name.clone(),
+                table_name,
+                type_name,
+                columns: event_columns(&event_info.inputs),
+            });
+        }
+    }
+
+    Ok(tables)
+}
+
+/// The `RocketPoolETH` `Transfer` event table used by the API tests
+#[cfg(test)]
+pub(crate) fn transfer_table() -> EventTable {
+    let inputs: Vec<ABIInput> = serde_json::from_value(serde_json::json!([
+        { "indexed": true, "name": "from", "type": "address" },
+        { "indexed": true, "name": "to", "type": "address" },
+        { "indexed": false, "name": "value", "type": "uint256" }
+    ]))
+    .unwrap();
+
+    EventTable {
+        contract_name: "RocketPoolETH".to_string(),
+        event_name: "Transfer".to_string(),
+        schema_name: "indexer_rocket_pool_eth".to_string(),
+        table_name: "transfer".to_string(),
+        type_name: "Transfer".to_string(),
+        columns: event_columns(&inputs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(snake_to_camel("rindexer_id", false), "rindexerId");
+        assert_eq!(snake_to_camel("quote_params_profile_id", false), "quoteParamsProfileId");
+        assert_eq!(snake_to_camel("rocket_pool_eth_transfer", true), "RocketPoolEthTransfer");
+        assert_eq!(pluralize("Transfer"), "Transfers");
+        assert_eq!(pluralize("Approval"), "Approvals");
+        assert_eq!(pluralize("Policy"), "Policies");
+        assert_eq!(pluralize("Pass"), "Passes");
+    }
+
+    #[test]
+    fn test_column_sql() {
+        let column = EventTableColumn::new("data", "BYTEA", false);
+        assert_eq!(column.column_type, ColumnType::Bytes);
+        assert_eq!(column.select_sql(), "'0x' || encode(\"data\", 'hex')");
+
+        let column = EventTableColumn::new("value", "VARCHAR(78)", false);
+        assert_eq!(column.select_sql(), "\"value\"::TEXT");
+        assert_eq!(column.cast_sql("$1::TEXT"), "CAST($1::TEXT AS VARCHAR(78))");
+
+        let column = EventTableColumn::new("ids", "VARCHAR(78)[]", false);
+        assert!(column.is_array);
+        assert!(!column.is_filterable());
+    }
+}
@@ -1,32 +1,59 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Weak},
+};
 
-use ethers::types::U64;
 use futures::future::join_all;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use serenity::all::ChannelId;
 use teloxide::types::ChatId;
 use thiserror::Error;
-use tokio::{
-    task,
-    task::{JoinError, JoinHandle},
-};
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, info_span, warn, Instrument};
 
 use crate::{
     chat::{
+        delivery::{ChatDelivery, DigestMessage},
         discord::{DiscordBot, DiscordError},
+        email::{EmailClient, EmailError},
+        matrix::{MatrixBot, MatrixError},
         slack::{SlackBot, SlackError},
+        teams::{TeamsError, TeamsWebhook},
         telegram::{TelegramBot, TelegramError},
-        template::Template,
+        template::{Template, TemplateContext, TemplateError},
     },
-    event::{filter_event_data_by_conditions, EventMessage},
-    manifest::chat::{
-        ChatConfig, DiscordConfig, DiscordEvent, SlackConfig, SlackEvent, TelegramConfig,
-        TelegramEvent,
+    event::{ConditionError, Conditions, EventMessage},
+    manifest::{
+        chat::{
+            ChatConfig, DiscordConfig, DiscordEvent, EmailConfig, EmailEvent, MatrixConfig,
+            MatrixEvent, SlackConfig, SlackEvent, TeamsConfig, TeamsEvent, TelegramConfig,
+            TelegramEvent,
+        },
+        network::Network,
     },
+    metrics::record_chat_message,
+    shutdown::{on_shutdown, remove_shutdown_hook, spawn_in_flight},
 };
 
 type SendMessage = Vec<JoinHandle<Result<(), ChatError>>>;
 
+// the parsed conditions of the first message for each event, which is the message that is sent
+type MessageConditions = HashMap<String, Option<Conditions>>;
+
+// the parsed templates of the first message for each event so they are not parsed for every event
+type MessageTemplates = HashMap<String, Template>;
+
+/// A message of a chat target whose conditions or template could not be parsed
+#[derive(Error, Debug)]
+pub enum ChatMessageError {
+    #[error("Invalid chat conditions: {0}")]
+    Conditions(#[from] ConditionError),
+
+    #[error("Invalid chat template: {0}")]
+    Template(#[from] TemplateError),
+}
+
 #[derive(Error, Debug)]
 pub enum ChatError {
     #[error("Telegram error: {0}")]
@@ -38,87 +65,365 @@ pub enum ChatError {
     #[error("Slack error: {0}")]
     Slack(#[from] SlackError),
 
+    #[error("Teams error: {0}")]
+    Teams(#[from] TeamsError),
+
+    #[error("Matrix error: {0}")]
+    Matrix(#[from] MatrixError),
+
+    #[error("Email error: {0}")]
+    Email(#[from] EmailError),
+
+    #[error("{0}")]
+    Message(#[from] ChatMessageError),
+
     #[error("Task failed: {0}")]
     JoinError(JoinError),
 }
 
-#[derive(Debug, Clone)]
 struct TelegramInstance {
     config: TelegramConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
     client: Arc<TelegramBot>,
 }
 
-#[derive(Debug)]
 struct DiscordInstance {
     config: DiscordConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
     client: Arc<DiscordBot>,
 }
 
-#[derive(Debug)]
 struct SlackInstance {
     config: SlackConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
     client: Arc<SlackBot>,
 }
 
+struct TeamsInstance {
+    config: TeamsConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
+    client: Arc<TeamsWebhook>,
+}
+
+struct MatrixInstance {
+    config: MatrixConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
+    client: Arc<MatrixBot>,
+}
+
+struct EmailInstance {
+    config: EmailConfig,
+    conditions: MessageConditions,
+    templates: MessageTemplates,
+    subjects: MessageTemplates,
+    delivery: Arc<ChatDelivery>,
+    client: Arc<EmailClient>,
+}
+
 pub struct ChatClients {
+    send_historic: bool,
     telegram: Option<Vec<TelegramInstance>>,
     discord: Option<Vec<DiscordInstance>>,
     slack: Option<Vec<SlackInstance>>,
+    teams: Option<Vec<TeamsInstance>>,
+    matrix: Option<Vec<MatrixInstance>>,
+    email: Option<Vec<EmailInstance>>,
 }
 
 impl ChatClients {
-    pub async fn new(chat_config: ChatConfig) -> Self {
-        let telegram = chat_config.telegram.map(|config| {
-            config
-                .into_iter()
-                .map(|config| {
-                    let client = Arc::new(TelegramBot::new(&config.bot_token));
-                    TelegramInstance { config, client }
-                })
-                .collect()
-        });
+    pub async fn new(chat_config: ChatConfig, networks: &[Network]) -> Result<Self, ChatError> {
+        let template_context = Arc::new(TemplateContext::new(chat_config.token.clone(), networks));
+        let send_historic = chat_config.send_historic();
+
+        let telegram = chat_config
+            .telegram
+            .map(|config| {
+                config
+                    .into_iter()
+                    .map(|config| {
+                        let conditions = message_conditions(
+                            config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                        )?;
+                        let templates = message_templates(
+                            config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                            &template_context,
+                        )?;
+                        let client = Arc::new(TelegramBot::new(&config.bot_token));
+                        let delivery = Arc::new(ChatDelivery::new(
+                            &config.delivery,
+                            Arc::clone(&template_context),
+                        ));
+                        let chat_id = ChatId(config.chat_id);
+                        spawn_digest_flush("telegram", &delivery, {
+                            let client = Arc::clone(&client);
+                            move |digest| {
+                                let client = Arc::clone(&client);
+                                async move {
+                                    client.send_message(chat_id, &digest.message).await?;
+                                    Ok(())
+                                }
+                            }
+                        });
+                        Ok(TelegramInstance { config, conditions, templates, delivery, client })
+                    })
+                    .collect::<Result<Vec<_>, ChatMessageError>>()
+            })
+            .transpose()?;
+
+        let discord = chat_config
+            .discord
+            .map(|config| {
+                config
+                    .into_iter()
+                    .map(|config| {
+                        let conditions = message_conditions(
+                            config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                        )?;
+                        let templates = message_templates(
+                            config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                            &template_context,
+                        )?;
+                        let client = Arc::new(DiscordBot::new(&config.bot_token));
+                        let delivery = Arc::new(ChatDelivery::new(
+                            &config.delivery,
+                            Arc::clone(&template_context),
+                        ));
+                        let channel_id = ChannelId::new(config.channel_id);
+                        spawn_digest_flush("discord", &delivery, {
+                            let client = Arc::clone(&client);
+                            move |digest| {
+                                let client = Arc::clone(&client);
+                                async move {
+                                    client.send_message(channel_id, &digest.message).await?;
+                                    Ok(())
+                                }
+                            }
+                        });
+                        Ok(DiscordInstance { config, conditions, templates, delivery, client })
+                    })
+                    .collect::<Result<Vec<_>, ChatMessageError>>()
+            })
+            .transpose()?;
+
+        let slack = chat_config
+            .slack
+            .map(|config| {
+                config
+                    .into_iter()
+                    .map(|config| {
+                        let conditions = message_conditions(
+                            config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                        )?;
+                        let templates = message_templates(
+                            config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                            &template_context,
+                        )?;
+                        let client = Arc::new(SlackBot::new(config.bot_token.clone()));
+                        let delivery = Arc::new(ChatDelivery::new(
+                            &config.delivery,
+                            Arc::clone(&template_context),
+                        ));
+                        spawn_digest_flush("slack", &delivery, {
+                            let client = Arc::clone(&client);
+                            let channel = config.channel.clone();
+                            move |digest| {
+                                let client = Arc::clone(&client);
+                                let channel = channel.clone();
+                                async move {
+                                    client.send_message(&channel, &digest.message).await?;
+                                    Ok(())
+                                }
+                            }
+                        });
+                        Ok(SlackInstance { config, conditions, templates, delivery, client })
+                    })
+                    .collect::<Result<Vec<_>, ChatMessageError>>()
+            })
+            .transpose()?;
+
+        let teams = chat_config
+            .teams
+            .map(|config| {
+                config
+                    .into_iter()
+                    .map(|config| {
+                        let conditions = message_conditions(
+                            config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                        )?;
+                        let templates = message_templates(
+                            config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                            &template_context,
+                        )?;
+                        let client = Arc::new(TeamsWebhook::new(config.webhook_url.clone()));
+                        let delivery = Arc::new(ChatDelivery::new(
+                            &config.delivery,
+                            Arc::clone(&template_context),
+                        ));
+                        spawn_digest_flush("teams", &delivery, {
+                            let client = Arc::clone(&client);
+                            move |digest| {
+                                let client = Arc::clone(&client);
+                                async move {
+                                    client.send_message(&digest.message).await?;
+                                    Ok(())
+                                }
+                            }
+                        });
+                        Ok(TeamsInstance { config, conditions, templates, delivery, client })
+                    })
+                    .collect::<Result<Vec<_>, ChatMessageError>>()
+            })
+            .transpose()?;
+
+        let matrix = chat_config
+            .matrix
+            .map(|config| {
+                config
+                    .into_iter()
+                    .map(|config| {
+                        let conditions = message_conditions(
+                            config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                        )?;
+                        let templates = message_templates(
+                            config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                            &template_context,
+                        )?;
+                        let client = Arc::new(MatrixBot::new(
+                            &config.homeserver_url,
+                            config.access_token.clone(),
+                        ));
+                        let delivery = Arc::new(ChatDelivery::new(
+                            &config.delivery,
+                            Arc::clone(&template_context),
+                        ));
+                        spawn_digest_flush("matrix", &delivery, {
+                            let client = Arc::clone(&client);
+                            let room_id = config.room_id.clone();
+                            move |digest| {
+                                let client = Arc::clone(&client);
+                                let room_id = room_id.clone();
+                                async move {
+                                    client.send_message(&room_id, &digest.message).await?;
+                                    Ok(())
+                                }
+                            }
+                        });
+                        Ok(MatrixInstance { config, conditions, templates, delivery, client })
+                    })
+                    .collect::<Result<Vec<_>, ChatMessageError>>()
+            })
+            .transpose()?;
+
+        let email = match chat_config.email {
+            Some(config) => {
+                let mut instances = vec![];
+                for config in config {
+                    let conditions = message_conditions(
+                        config.messages.iter().map(|m| (&m.event_name, &m.conditions)),
+                    )?;
+                    let templates = message_templates(
+                        config.messages.iter().map(|m| (&m.event_name, &m.template_inline)),
+                        &template_context,
+                    )?;
+                    let subjects = message_templates(
+                        config.messages.iter().map(|m| (&m.event_name, &m.subject)),
+                        &template_context,
+                    )?;
+                    let client = Arc::new(EmailClient::new(&config)?);
+                    let delivery = Arc::new(ChatDelivery::new(
+                        &config.delivery,
+                        Arc::clone(&template_context),
+                    ));
+                    spawn_digest_flush("email", &delivery, {
+                        let client = Arc::clone(&client);
+                        move |digest| {
+                            let client = Arc::clone(&client);
+                            async move {
+                                client.send_message(&digest.subject, &digest.message).await?;
+                                Ok(())
+                            }
+                        }
+                    });
+                    instances.push(EmailInstance {
+                        config,
+                        conditions,
+                        templates,
+                        subjects,
+                        delivery,
+                        client,
+                    });
+                }
+                Some(instances)
+            }
+            None => None,
+        };
 
-        let discord = chat_config.discord.map(|config| {
-            config
-                .into_iter()
-                .map(|config| {
-                    let client = Arc::new(DiscordBot::new(&config.bot_token));
-                    DiscordInstance { config, client }
-                })
-                .collect()
-        });
+        Ok(Self { send_historic, telegram, discord, slack, teams, matrix, email })
+    }
 
-        let slack = chat_config.slack.map(|config| {
-            config
-                .into_iter()
-                .map(|config| {
-                    let client = Arc::new(SlackBot::new(config.bot_token.clone()));
-                    SlackInstance { config, client }
-                })
-                .collect()
-        });
+    /// Messages are only sent for live events unless `send_historic` is enabled
+    pub fn should_send(&self, live: bool) -> bool {
+        live || self.send_historic
+    }
 
-        Self { telegram, discord, slack }
+    fn has_any_chat(&self) -> bool {
+        self.telegram.is_some() ||
+            self.discord.is_some() ||
+            self.slack.is_some() ||
+            self.teams.is_some() ||
+            self.matrix.is_some() ||
+            self.email.is_some()
     }
 
-    fn find_accepted_block_range(&self, from_block: &U64, to_block: &U64) -> U64 {
-        if from_block > to_block {
-            panic!("Invalid range: from_block must be less than or equal to to_block");
-        }
+    /// Returns the events to send a message for now, events which fail the conditions, are
+    /// duplicates or go over the rate limit are dropped and events for a digest are held back
+    fn events_to_send<'a>(
+        &self,
+        delivery: &ChatDelivery,
+        event_name: &str,
+        conditions: Option<&Conditions>,
+        template: &Template,
+        events_data: &'a [Value],
+    ) -> Vec<&'a Value> {
+        let matched = events_data.iter().filter(|event_data| {
+            conditions.is_none_or(|conditions| conditions.matches(event_data)) &&
+                !delivery.is_duplicate(event_data)
+        });
 
-        match from_block.overflowing_add(to_block - from_block) {
-            (result, false) => result,
-            (_, true) => U64::max_value(),
+        if delivery.digest_window().is_some() {
+            for event_data in matched {
+                let message = template.parse_template_inline(event_data);
+                delivery.push_digest(event_name, event_data, message);
+            }
+            return vec![];
         }
-    }
 
-    pub fn is_in_block_range_to_send(&self, from_block: &U64, to_block: &U64) -> bool {
-        // only 10 blocks at a time else rate limits will kick in
-        U64::from(10) <= self.find_accepted_block_range(from_block, to_block)
-    }
+        let mut rate_limited = 0;
+        let events: Vec<_> = matched
+            .filter(|_| {
+                let acquired = delivery.try_acquire();
+                if !acquired {
+                    rate_limited += 1;
+                }
+                acquired
+            })
+            .collect();
 
-    fn has_any_chat(&self) -> bool {
-        self.telegram.is_some() || self.discord.is_some() || self.slack.is_some()
+        if rate_limited > 0 {
+            warn!("{} - {} chat messages dropped due to the rate limit", event_name, rate_limited);
+        }
+
+        events
     }
 
     fn telegram_send_message_tasks(
@@ -127,21 +432,21 @@ impl ChatClients {
         event_for: &TelegramEvent,
         events_data: &[Value],
     ) -> SendMessage {
-        let tasks: Vec<_> = events_data
-            .iter()
-            .filter(|event_data| {
-                if let Some(conditions) = &event_for.conditions {
-                    filter_event_data_by_conditions(event_data, conditions)
-                } else {
-                    true
-                }
-            })
+        let template = &instance.templates[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
             .map(|event_data| {
                 let client = Arc::clone(&instance.client);
                 let chat_id = ChatId(instance.config.chat_id);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
+                let message = template.parse_template_inline(event_data);
+                spawn_send("telegram", async move {
                     client.send_message(chat_id, &message).await?;
                     Ok(())
                 })
@@ -156,21 +461,21 @@ impl ChatClients {
         event_for: &DiscordEvent,
         events_data: &[Value],
     ) -> SendMessage {
-        let tasks: Vec<_> = events_data
-            .iter()
-            .filter(|event_data| {
-                if let Some(conditions) = &event_for.conditions {
-                    filter_event_data_by_conditions(event_data, conditions)
-                } else {
-                    true
-                }
-            })
+        let template = &instance.templates[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
             .map(|event_data| {
                 let client = Arc::clone(&instance.client);
                 let channel_id = ChannelId::new(instance.config.channel_id);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
+                let message = template.parse_template_inline(event_data);
+                spawn_send("discord", async move {
                     client.send_message(channel_id, &message).await?;
                     Ok(())
                 })
@@ -185,21 +490,21 @@ impl ChatClients {
         event_for: &SlackEvent,
         events_data: &[Value],
     ) -> SendMessage {
-        let tasks: Vec<_> = events_data
-            .iter()
-            .filter(|event_data| {
-                if let Some(conditions) = &event_for.conditions {
-                    filter_event_data_by_conditions(event_data, conditions)
-                } else {
-                    true
-                }
-            })
+        let template = &instance.templates[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
             .map(|event_data| {
                 let client = Arc::clone(&instance.client);
                 let channel = instance.config.channel.clone();
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
+                let message = template.parse_template_inline(event_data);
+                spawn_send("slack", async move {
                     client.send_message(&channel, &message).await?;
                     Ok(())
                 })
@@ -208,14 +513,99 @@ impl ChatClients {
         tasks
     }
 
+    fn teams_send_message_tasks(
+        &self,
+        instance: &TeamsInstance,
+        event_for: &TeamsEvent,
+        events_data: &[Value],
+    ) -> SendMessage {
+        let template = &instance.templates[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
+            .map(|event_data| {
+                let client = Arc::clone(&instance.client);
+                let message = template.parse_template_inline(event_data);
+                spawn_send("teams", async move {
+                    client.send_message(&message).await?;
+                    Ok(())
+                })
+            })
+            .collect();
+        tasks
+    }
+
+    fn matrix_send_message_tasks(
+        &self,
+        instance: &MatrixInstance,
+        event_for: &MatrixEvent,
+        events_data: &[Value],
+    ) -> SendMessage {
+        let template = &instance.templates[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
+            .map(|event_data| {
+                let client = Arc::clone(&instance.client);
+                let room_id = instance.config.room_id.clone();
+                let message = template.parse_template_inline(event_data);
+                spawn_send("matrix", async move {
+                    client.send_message(&room_id, &message).await?;
+                    Ok(())
+                })
+            })
+            .collect();
+        tasks
+    }
+
+    fn email_send_message_tasks(
+        &self,
+        instance: &EmailInstance,
+        event_for: &EmailEvent,
+        events_data: &[Value],
+    ) -> SendMessage {
+        let template = &instance.templates[&event_for.event_name];
+        let subject_template = &instance.subjects[&event_for.event_name];
+        let tasks: Vec<_> = self
+            .events_to_send(
+                &instance.delivery,
+                &event_for.event_name,
+                instance.conditions.get(&event_for.event_name).and_then(Option::as_ref),
+                template,
+                events_data,
+            )
+            .into_iter()
+            .map(|event_data| {
+                let client = Arc::clone(&instance.client);
+                let subject = subject_template.parse_template_inline(event_data);
+                let body = template.parse_template_inline(event_data);
+                spawn_send("email", async move {
+                    client.send_message(&subject, &body).await?;
+                    Ok(())
+                })
+            })
+            .collect();
+        tasks
+    }
+
     pub async fn send_message(
         &self,
         event_message: &EventMessage,
         index_event_in_order: bool,
-        from_block: &U64,
-        to_block: &U64,
     ) -> Result<usize, ChatError> {
-        if !self.has_any_chat() || !self.is_in_block_range_to_send(from_block, to_block) {
+        if !self.has_any_chat() {
             return Ok(0);
         }
 
@@ -283,6 +673,60 @@ impl ChatClients {
                 }
             }
 
+            if let Some(teams) = &self.teams {
+                for instance in teams {
+                    if instance.config.networks.contains(&event_message.network) {
+                        let teams_event = instance
+                            .config
+                            .messages
+                            .iter()
+                            .find(|e| e.event_name == event_message.event_name);
+
+                        if let Some(teams_event) = teams_event {
+                            let message =
+                                self.teams_send_message_tasks(instance, teams_event, data_array);
+                            messages.push(message);
+                        }
+                    }
+                }
+            }
+
+            if let Some(matrix) = &self.matrix {
+                for instance in matrix {
+                    if instance.config.networks.contains(&event_message.network) {
+                        let matrix_event = instance
+                            .config
+                            .messages
+                            .iter()
+                            .find(|e| e.event_name == event_message.event_name);
+
+                        if let Some(matrix_event) = matrix_event {
+                            let message =
+                                self.matrix_send_message_tasks(instance, matrix_event, data_array);
+                            messages.push(message);
+                        }
+                    }
+                }
+            }
+
+            if let Some(email) = &self.email {
+                for instance in email {
+                    if instance.config.networks.contains(&event_message.network) {
+                        let email_event = instance
+                            .config
+                            .messages
+                            .iter()
+                            .find(|e| e.event_name == event_message.event_name);
+
+                        if let Some(email_event) = email_event {
+                            let message =
+                                self.email_send_message_tasks(instance, email_event, data_array);
+                            messages.push(message);
+                        }
+                    }
+                }
+            }
+
             let mut messages_sent = 0;
 
             if index_event_in_order {
@@ -313,3 +757,122 @@ impl ChatClients {
         }
     }
 }
+
+fn message_templates<'a>(
+    messages: impl Iterator<Item = (&'a String, &'a String)>,
+    context: &Arc<TemplateContext>,
+) -> Result<MessageTemplates, ChatMessageError> {
+    let mut templates = HashMap::new();
+    for (event_name, template) in messages {
+        if !templates.contains_key(event_name) {
+            let parsed = Template::parse(template.clone())?.with_context(Arc::clone(context));
+            templates.insert(event_name.clone(), parsed);
+        }
+    }
+
+    Ok(templates)
+}
+
+fn message_conditions<'a>(
+    messages: impl Iterator<Item = (&'a String, &'a Option<Vec<Map<String, Value>>>)>,
+) -> Result<MessageConditions, ChatMessageError> {
+    let mut conditions = HashMap::new();
+    for (event_name, message_conditions) in messages {
+        if !conditions.contains_key(event_name) {
+            let parsed = message_conditions.as_deref().map(Conditions::parse).transpose()?;
+            conditions.insert(event_name.clone(), parsed);
+        }
+    }
+
+    Ok(conditions)
+}
+
+/// Spawns a chat send and records whether it succeeded
+fn spawn_send<F>(client: &'static str, send: F) -> JoinHandle<Result<(), ChatError>>
+where
+    F: Future<Output = Result<(), ChatError>> + Send + 'static,
+{
+    spawn_in_flight(
+        async move {
+            let result = send.await;
+            record_chat_message(client, result.is_ok());
+            result
+        }
+        .instrument(info_span!("chat_send", client)),
+    )
+}
+
+/// Sends the digest for a chat target every window, the digests still held are sent when the
+/// target is dropped on a reload or when rindexer shuts down
+fn spawn_digest_flush<F, Fut>(client: &'static str, delivery: &Arc<ChatDelivery>, send: F)
+where
+    F: Fn(DigestMessage) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), ChatError>> + Send,
+{
+    let window = match delivery.digest_window() {
+        Some(window) => window,
+        None => return,
+    };
+    let mut dropped_digests = delivery.digests_on_drop();
+    let delivery = Arc::downgrade(delivery);
+    let send = Arc::new(send);
+
+    let shutdown_hook = on_shutdown({
+        let delivery = Weak::clone(&delivery);
+        let send = Arc::clone(&send);
+        async move {
+            if let Some(delivery) = delivery.upgrade() {
+                send_digests(client, &*send, delivery.take_digests(), Some(&delivery)).await;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(window);
+        // the first tick completes straight away
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Some(delivery) = delivery.upgrade() {
+                        send_digests(client, &*send, delivery.take_digests(), Some(&delivery))
+                            .await;
+                    }
+                }
+                digests = &mut dropped_digests => {
+                    // the hook holds the clients of the target so it goes with the target
+                    remove_shutdown_hook(shutdown_hook);
+                    if let Ok(digests) = digests {
+                        send_digests(client, &*send, digests, None).await;
+                    }
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// The rate limit of the target is applied while it is still around
+async fn send_digests<F, Fut>(
+    client: &'static str,
+    send: &F,
+    digests: Vec<DigestMessage>,
+    delivery: Option<&ChatDelivery>,
+) where
+    F: Fn(DigestMessage) -> Fut,
+    Fut: Future<Output = Result<(), ChatError>>,
+{
+    for digest in digests {
+        if delivery.is_some_and(|delivery| !delivery.try_acquire()) {
+            warn!("Chat digest dropped due to the rate limit");
+            continue;
+        }
+
+        let result = send(digest).await;
+        record_chat_message(client, result.is_ok());
+        if let Err(e) = result {
+            error!("Error sending chat digest: {}", e);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::{
+    chat::template::{Template, TemplateContext},
+    manifest::chat::{ChatDedupeConfig, ChatDeliveryConfig, ChatDigestConfig, ChatRateLimitConfig},
+};
+
+struct RateLimiter {
+    max_messages: usize,
+    window: Duration,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    fn new(config: &ChatRateLimitConfig) -> Self {
+        Self {
+            max_messages: config.max_messages as usize,
+            window: Duration::from_secs(config.window_seconds),
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().expect("Failed to lock rate limiter");
+        while sent.front().is_some_and(|sent_at| now.duration_since(*sent_at) >= self.window) {
+            sent.pop_front();
+        }
+
+        if sent.len() >= self.max_messages {
+            return false;
+        }
+
+        sent.push_back(now);
+        true
+    }
+}
+
+struct Dedupe {
+    key: Template,
+    window: Duration,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl Dedupe {
+    fn new(config: &ChatDedupeConfig, context: Arc<TemplateContext>) -> Self {
+        Self {
+            key: Template::new(config.key.clone()).with_context(context),
+            window: Duration::from_secs(config.window_seconds),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if the key was already seen in the window, otherwise records it
+    fn is_duplicate(&self, event_data: &Value) -> bool {
+        let key = self.key.parse_template_inline(event_data);
+        let now = Instant::now();
+        let mut seen = self.seen.lock().expect("Failed to lock dedupe keys");
+        seen.retain(|_, seen_at| now.duration_since(*seen_at) < self.window);
+
+        if seen.contains_key(&key) {
+            return true;
+        }
+
+        seen.insert(key, now);
+        false
+    }
+}
+
+#[derive(Default)]
+struct PendingDigest {
+    events: Vec<Value>,
+    messages: Vec<String>,
+}
+
+struct Digest {
+    window: Duration,
+    template: Template,
+    subject: Option<Template>,
+    // keyed by event name and network in the order they were first seen
+    pending: Mutex<Vec<((String, String), PendingDigest)>>,
+}
+
+impl Digest {
+    fn new(config: &ChatDigestConfig, context: Arc<TemplateContext>) -> Self {
+        Self {
+            window: Duration::from_secs(config.window_seconds),
+            template: Template::new(config.template_inline.clone())
+                .with_context(Arc::clone(&context)),
+            subject: config
+                .subject
+                .as_ref()
+                .map(|subject| Template::new(subject.clone()).with_context(context)),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+pub struct DigestMessage {
+    pub subject: String,
+    pub message: String,
+}
+
+/// Applies the rate limit, dedupe and digest settings of a single chat target
+pub struct ChatDelivery {
+    rate_limit: Option<RateLimiter>,
+    dedupe: Option<Dedupe>,
+    digest: Option<Digest>,
+    // where the digests still held are handed over when the target is dropped, like on a reload
+    dropped_digests: Mutex<Option<oneshot::Sender<Vec<DigestMessage>>>>,
+}
+
+impl ChatDelivery {
+    pub fn new(config: &ChatDeliveryConfig, context: Arc<TemplateContext>) -> Self {
+        Self {
+            rate_limit: config.rate_limit.as_ref().map(RateLimiter::new),
+            dedupe: config.dedupe.as_ref().map(|dedupe| Dedupe::new(dedupe, Arc::clone(&context))),
+            digest: config.digest.as_ref().map(|digest| Digest::new(digest, context)),
+            dropped_digests: Mutex::new(None),
+        }
+    }
+
+    /// Resolves with the digests still held once the delivery is dropped so they can be sent
+    pub fn digests_on_drop(&self) -> oneshot::Receiver<Vec<DigestMessage>> {
+        let (sender, receiver) = oneshot::channel();
+        *self.dropped_digests.lock().expect("Failed to lock dropped digests") = Some(sender);
+        receiver
+    }
+
+    pub fn is_duplicate(&self, event_data: &Value) -> bool {
+        self.dedupe.as_ref().is_some_and(|dedupe| dedupe.is_duplicate(event_data))
+    }
+
+    /// Returns false when the rate limit has been hit for the current window
+    pub fn try_acquire(&self) -> bool {
+        self.rate_limit.as_ref().is_none_or(|rate_limit| rate_limit.try_acquire())
+    }
+
+    pub fn digest_window(&self) -> Option<Duration> {
+        self.digest.as_ref().map(|digest| digest.window)
+    }
+
+    /// Holds the event back to be sent in the next digest, returns false if digest is not enabled
+    pub fn push_digest(&self, event_name: &str, event_data: &Value, message: String) -> bool {
+        let digest = match &self.digest {
+            Some(digest) => digest,
+            None => return false,
+        };
+
+        let network = event_data
+            .get("transaction_information")
+            .and_then(|info| info.get("network"))
+            .and_then(|network| network.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let key = (event_name.to_string(), network);
+
+        let mut pending = digest.pending.lock().expect("Failed to lock pending digest");
+        let index = match pending.iter().position(|(pending_key, _)| *pending_key == key) {
+            Some(index) => index,
+            None => {
+                pending.push((key, PendingDigest::default()));
+                pending.len() - 1
+            }
+        };
+        pending[index].1.events.push(event_data.clone());
+        pending[index].1.messages.push(message);
+
+        true
+    }
+
+    /// Renders a digest message for each event and network seen since the last call
+    pub fn take_digests(&self) -> Vec<DigestMessage> {
+        let digest = match &self.digest {
+            Some(digest) => digest,
+            None => return vec![],
+        };
+
+        let pending =
+            std::mem::take(&mut *digest.pending.lock().expect("Failed to lock pending digest"));
+
+        pending
+            .into_iter()
+            .map(|((event_name, network), pending)| {
+                let data = json!({
+                    "event_name": event_name,
+                    "network": network,
+                    "count": pending.events.len(),
+                    "events": pending.events,
+                    "messages": pending.messages,
+                    "transaction_information": { "network": network },
+                });
+
+                let subject = match &digest.subject {
+                    Some(subject) => subject.parse_template_inline(&data),
+                    None => {
+                        format!("{} {} events on {}", pending.events.len(), event_name, network)
+                    }
+                };
+
+                DigestMessage { subject, message: digest.template.parse_template_inline(&data) }
+            })
+            .collect()
+    }
+}
+
+impl Drop for ChatDelivery {
+    fn drop(&mut self) {
+        let sender = self.dropped_digests.get_mut().ok().and_then(|sender| sender.take());
+        if let Some(sender) = sender {
+            let _ = sender.send(self.take_digests());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivery(config: Value) -> ChatDelivery {
+        let config: ChatDeliveryConfig = serde_json::from_value(config).unwrap();
+        ChatDelivery::new(&config, Arc::new(TemplateContext::default()))
+    }
+
+    #[test]
+    fn test_rate_limit_and_dedupe() {
+        let delivery = delivery(json!({
+            "rate_limit": { "max_messages": 2, "window_seconds": 60 },
+            "dedupe": { "key": "{{from}}", "window_seconds": 60 }
+        }));
+
+        assert!(!delivery.is_duplicate(&json!({ "from": "0x1" })));
+        assert!(delivery.is_duplicate(&json!({ "from": "0x1" })));
+        assert!(!delivery.is_duplicate(&json!({ "from": "0x2" })));
+
+        assert!(delivery.try_acquire());
+        assert!(delivery.try_acquire());
+        assert!(!delivery.try_acquire());
+    }
+
+    #[test]
+    fn test_digest() {
+        let delivery = delivery(json!({
+            "digest": {
+                "window_seconds": 60,
+                "template_inline": "{{count}} {{event_name}}:{{#each events}} {{this.value}}{{/each}}"
+            }
+        }));
+        let event = |value: u64| json!({ "value": value, "transaction_information": { "network": "ethereum" } });
+
+        assert!(delivery.push_digest("Transfer", &event(1), "1".to_string()));
+        assert!(delivery.push_digest("Transfer", &event(2), "2".to_string()));
+
+        let digests = delivery.take_digests();
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].message, "2 Transfer: 1 2");
+        assert_eq!(digests[0].subject, "2 Transfer events on ethereum");
+        assert!(delivery.take_digests().is_empty());
+    }
+
+    #[test]
+    fn test_digests_on_drop() {
+        let delivery = delivery(json!({
+            "digest": { "window_seconds": 60, "template_inline": "{{count}} {{event_name}}" }
+        }));
+        let mut dropped = delivery.digests_on_drop();
+
+        assert!(delivery.push_digest("Transfer", &json!({}), "1".to_string()));
+        assert!(dropped.try_recv().is_err());
+
+        drop(delivery);
+        let digests = dropped.try_recv().unwrap();
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].message, "1 Transfer");
+    }
+}
@@ -0,0 +1,63 @@
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use thiserror::Error;
+
+use crate::manifest::chat::{EmailConfig, SmtpTls};
+
+#[derive(Error, Debug)]
+pub enum EmailError {
+    #[error("Invalid email address: {0}")]
+    InvalidAddress(#[from] lettre::address::AddressError),
+
+    #[error("Could not build email: {0}")]
+    CouldNotBuildEmail(#[from] lettre::error::Error),
+
+    #[error("SMTP error: {0}")]
+    SmtpError(#[from] lettre::transport::smtp::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailClient {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailClient {
+    pub fn new(config: &EmailConfig) -> Result<Self, EmailError> {
+        let builder = match config.tls {
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpTls::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+
+        let mut builder = builder.port(config.port());
+        if let Some(username) = &config.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+
+        let to = config.to.iter().map(|to| to.parse()).collect::<Result<Vec<Mailbox>, _>>()?;
+
+        Ok(Self { transport: builder.build(), from: config.from.parse()?, to })
+    }
+
+    pub async fn send_message(&self, subject: &str, body: &str) -> Result<(), EmailError> {
+        let mut message = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+
+        let message = message.header(ContentType::TEXT_PLAIN).body(body.to_string())?;
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Client;
+use serde_json::json;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Error, Debug)]
+pub enum MatrixError {
+    #[error("HTTP request error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+
+    #[error("Matrix API error: {0} - {1}")]
+    ApiError(u16, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixBot {
+    client: Client,
+    homeserver_url: String,
+    access_token: String,
+}
+
+impl MatrixBot {
+    pub fn new(homeserver_url: &str, access_token: String) -> Self {
+        let client = Client::new();
+        Self {
+            client,
+            homeserver_url: homeserver_url.trim_end_matches('/').to_string(),
+            access_token,
+        }
+    }
+
+    pub async fn send_message(&self, room_id: &str, message: &str) -> Result<(), MatrixError> {
+        // the transaction id makes retries of the same request idempotent on the homeserver
+        let url = format!(
+            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+            self.homeserver_url,
+            utf8_percent_encode(room_id, NON_ALPHANUMERIC),
+            Uuid::new_v4()
+        );
+
+        let response = self
+            .client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "msgtype": "m.text",
+                "body": message
+            }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(MatrixError::ApiError(status.as_u16(), response.text().await.unwrap_or_default()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_message_to_room() {
+        let _mock = mock(
+            "PUT",
+            Matcher::Regex(
+                r"^/_matrix/client/v3/rooms/%21room%3Aserver/send/m.room.message/.+$".to_string(),
+            ),
+        )
+        .match_header("authorization", "Bearer token")
+        .match_body(Matcher::Json(json!({ "msgtype": "m.text", "body": "hello" })))
+        .with_status(200)
+        .with_body(r#"{"event_id": "$event"}"#)
+        .create();
+
+        let matrix = MatrixBot::new(&mockito::server_url(), "token".to_string());
+        assert!(matrix.send_message("!room:server", "hello").await.is_ok());
+    }
+}
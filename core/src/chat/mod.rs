@@ -1,7 +1,12 @@
 mod clients;
-pub use clients::ChatClients;
+pub use clients::{ChatClients, ChatError};
 
+mod delivery;
 mod discord;
+mod email;
+mod matrix;
 mod slack;
+mod teams;
 mod telegram;
 mod template;
+pub use template::{Template, TemplateContext, MAX_DECIMALS};
@@ -0,0 +1,85 @@
+use reqwest::Client;
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TeamsError {
+    #[error("HTTP request error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+
+    #[error("Teams webhook error: {0} - {1}")]
+    ApiError(u16, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamsWebhook {
+    client: Client,
+    webhook_url: String,
+}
+
+impl TeamsWebhook {
+    pub fn new(webhook_url: String) -> Self {
+        let client = Client::new();
+        Self { client, webhook_url }
+    }
+
+    pub async fn send_message(&self, message: &str) -> Result<(), TeamsError> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .header("Content-Type", "application/json")
+            .json(&json!({
+                "type": "message",
+                "attachments": [
+                    {
+                        "contentType": "application/vnd.microsoft.card.adaptive",
+                        "contentUrl": null,
+                        "content": {
+                            "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                            "type": "AdaptiveCard",
+                            "version": "1.4",
+                            "body": [
+                                {
+                                    "type": "TextBlock",
+                                    "text": message,
+                                    "wrap": true
+                                }
+                            ]
+                        }
+                    }
+                ]
+            }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(TeamsError::ApiError(status.as_u16(), response.text().await.unwrap_or_default()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{mock, Matcher};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_send_message_posts_adaptive_card() {
+        let _mock = mock("POST", "/teams")
+            .match_body(Matcher::PartialJson(json!({
+                "attachments": [{
+                    "contentType": "application/vnd.microsoft.card.adaptive",
+                    "content": { "body": [{ "type": "TextBlock", "text": "hello" }] }
+                }]
+            })))
+            .with_status(200)
+            .create();
+
+        let teams = TeamsWebhook::new(format!("{}/teams", mockito::server_url()));
+        assert!(teams.send_message("hello").await.is_ok());
+    }
+}
@@ -1,90 +1,613 @@
-use ethers::types::U64;
-use regex::Regex;
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use ethers::types::U256;
 use serde_json::Value;
 
+use crate::{
+    event::{evaluate_expression, validate_expression},
+    manifest::{chat::ChatTokenConfig, network::Network},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum TemplateError {
+    #[error("Template has an unclosed placeholder: {0}")]
+    UnclosedPlaceholder(String),
+
+    #[error("Template has {{{{{0}}}}} without a matching opening block")]
+    UnexpectedBlockTag(String),
+
+    #[error("Template block {{{{#{0}}}}} is never closed with {{{{/{0}}}}}")]
+    UnclosedBlock(String),
+
+    #[error("Template uses unknown helper {0}")]
+    UnknownHelper(String),
+
+    #[error("Template helper {0} expects {1} arguments")]
+    InvalidHelperArguments(String, &'static str),
+
+    #[error("Template helper {0} has an invalid number argument {1}")]
+    InvalidNumberArgument(String, String),
+
+    #[error(
+        "Template helper {0} has {1} decimals but the most a uint256 can have is {MAX_DECIMALS}"
+    )]
+    TooManyDecimals(String, u32),
+
+    #[error("Template uses {0} but no chat token config with decimals is defined")]
+    MissingTokenConfig(String),
+
+    #[error("Template uses {0} but network {1} has no block_explorer defined")]
+    MissingBlockExplorer(String, String),
+
+    #[error("Template has an invalid condition: {0}")]
+    InvalidCondition(String),
+}
+
+/// Config the template helpers need which is not part of the event data
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    token: Option<ChatTokenConfig>,
+    // network name to block explorer base url
+    block_explorers: HashMap<String, String>,
+}
+
+impl TemplateContext {
+    pub fn new(token: Option<ChatTokenConfig>, networks: &[Network]) -> Self {
+        let block_explorers = networks
+            .iter()
+            .filter_map(|network| {
+                network.block_explorer_url().map(|url| (network.name.clone(), url))
+            })
+            .collect();
+
+        Self { token, block_explorers }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct Template {
-    value: String,
+enum Node {
+    Text(String),
+    Placeholder(String),
+    Helper { name: String, args: Vec<String>, raw: String },
+    If { condition: String, then: Vec<Node>, otherwise: Vec<Node> },
+    Each { path: String, body: Vec<Node> },
 }
 
-impl Template {
-    pub fn new(value: String) -> Self {
-        Self { value }
+const HELPERS: [&str; 9] = [
+    "format_value",
+    "format_number",
+    "token_units",
+    "shorten_address",
+    "explorer_tx",
+    "explorer_address",
+    "explorer_block",
+    "format_time",
+    "relative_time",
+];
+
+/// 10^77 is the largest power of ten which fits in a uint256
+pub const MAX_DECIMALS: u32 = 77;
+
+// a block which is being parsed, the root of the template has no block
+struct Frame {
+    block: Option<Node>,
+    nodes: Vec<Node>,
+    // nodes parsed before the `else` of an `if` block
+    then: Option<Vec<Node>>,
+}
+
+impl Frame {
+    fn new(block: Option<Node>) -> Self {
+        Self { block, nodes: vec![], then: None }
     }
+}
 
-    pub fn parse_template_inline(&self, event_data: &Value) -> String {
-        let mut template = self.value.clone();
-        let placeholders = self.extract_placeholders(&template);
+fn parse_nodes(template: &str) -> Result<Vec<Node>, TemplateError> {
+    let mut stack: Vec<Frame> = vec![Frame::new(None)];
+    let mut rest = template;
 
-        for placeholder in placeholders {
-            if placeholder.contains('(') {
-                if let Some(value) = self.evaluate_function(&placeholder, event_data) {
-                    template = template.replace(&format!("{{{{{}}}}}", placeholder), &value);
+    while let Some(start) = rest.find("{{") {
+        let frame = stack.last_mut().expect("root frame is never popped");
+        if start > 0 {
+            frame.nodes.push(Node::Text(rest[..start].to_string()));
+        }
+
+        let end = rest[start + 2..]
+            .find("}}")
+            .ok_or_else(|| TemplateError::UnclosedPlaceholder(rest[start..].to_string()))?;
+        let tag = rest[start + 2..start + 2 + end].trim();
+        rest = &rest[start + 2 + end + 2..];
+
+        if let Some(condition) = tag.strip_prefix("#if ") {
+            let condition = condition.trim().to_string();
+            stack.push(Frame::new(Some(Node::If { condition, then: vec![], otherwise: vec![] })));
+        } else if let Some(path) = tag.strip_prefix("#each ") {
+            let path = path.trim().to_string();
+            stack.push(Frame::new(Some(Node::Each { path, body: vec![] })));
+        } else if tag == "else" {
+            if !matches!(frame.block, Some(Node::If { .. })) || frame.then.is_some() {
+                return Err(TemplateError::UnexpectedBlockTag(tag.to_string()));
+            }
+            frame.then = Some(std::mem::take(&mut frame.nodes));
+        } else if tag == "/if" || tag == "/each" {
+            if stack.len() == 1 {
+                return Err(TemplateError::UnexpectedBlockTag(tag.to_string()));
+            }
+
+            let frame = stack.pop().expect("checked there is an open block");
+            let node = match (frame.block, tag) {
+                (Some(Node::If { condition, .. }), "/if") => match frame.then {
+                    Some(then) => Node::If { condition, then, otherwise: frame.nodes },
+                    None => Node::If { condition, then: frame.nodes, otherwise: vec![] },
+                },
+                (Some(Node::Each { path, .. }), "/each") => Node::Each { path, body: frame.nodes },
+                _ => return Err(TemplateError::UnexpectedBlockTag(tag.to_string())),
+            };
+            stack.last_mut().expect("root frame is never popped").nodes.push(node);
+        } else if let Some(open) = tag.find('(') {
+            if !tag.ends_with(')') {
+                return Err(TemplateError::UnclosedPlaceholder(tag.to_string()));
+            }
+            let name = tag[..open].trim().to_string();
+            let args = tag[open + 1..tag.len() - 1]
+                .split(',')
+                .map(|arg| arg.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            frame.nodes.push(Node::Helper { name, args, raw: tag.to_string() });
+        } else {
+            frame.nodes.push(Node::Placeholder(tag.to_string()));
+        }
+    }
+
+    if !rest.is_empty() {
+        stack
+            .last_mut()
+            .expect("root frame is never popped")
+            .nodes
+            .push(Node::Text(rest.to_string()));
+    }
+
+    let frame = stack.pop().expect("root frame is never popped");
+    match frame.block {
+        None => Ok(frame.nodes),
+        Some(Node::If { .. }) => Err(TemplateError::UnclosedBlock("if".to_string())),
+        Some(_) => Err(TemplateError::UnclosedBlock("each".to_string())),
+    }
+}
+
+fn is_path(value: &str) -> bool {
+    !value.is_empty() &&
+        value.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '@')
+}
+
+fn validate_nodes(
+    nodes: &[Node],
+    context: &TemplateContext,
+    networks: &[String],
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(_) | Node::Placeholder(_) => {}
+            Node::If { condition, then, otherwise } => {
+                if !is_path(condition) {
+                    validate_expression(condition)
+                        .map_err(|e| TemplateError::InvalidCondition(e.to_string()))?;
                 }
-            } else if let Some(value) = self.get_nested_value(event_data, &placeholder) {
-                template = template.replace(&format!("{{{{{}}}}}", placeholder), &value);
+                validate_nodes(then, context, networks)?;
+                validate_nodes(otherwise, context, networks)?;
+            }
+            Node::Each { body, .. } => validate_nodes(body, context, networks)?,
+            Node::Helper { name, args, .. } => {
+                validate_helper(name, args, context, networks)?;
             }
         }
-        template
-    }
-
-    fn extract_placeholders(&self, template: &str) -> Vec<String> {
-        let mut placeholders = Vec::new();
-        let mut start = 0;
-        while let Some(start_index) = template[start..].find("{{") {
-            if let Some(end_index) = template[start + start_index + 2..].find("}}") {
-                let placeholder =
-                    &template[start + start_index + 2..start + start_index + 2 + end_index];
-                placeholders.push(placeholder.to_string());
-                start += start_index + 2 + end_index + 2;
-            } else {
-                break;
+    }
+
+    Ok(())
+}
+
+fn validate_helper(
+    name: &str,
+    args: &[String],
+    context: &TemplateContext,
+    networks: &[String],
+) -> Result<(), TemplateError> {
+    if !HELPERS.contains(&name) {
+        return Err(TemplateError::UnknownHelper(name.to_string()));
+    }
+
+    let (min, max, expected) = match name {
+        "format_value" => (2, 2, "2"),
+        "format_number" | "token_units" | "shorten_address" | "format_time" => (1, 2, "1 or 2"),
+        _ => (1, 1, "1"),
+    };
+    if args.len() < min || args.len() > max {
+        return Err(TemplateError::InvalidHelperArguments(name.to_string(), expected));
+    }
+
+    // the second argument is a number for all helpers apart from format_time
+    if name != "format_time" {
+        if let Some(arg) = args.get(1) {
+            match arg.parse::<u32>() {
+                Ok(decimals) if name == "format_value" && decimals > MAX_DECIMALS => {
+                    return Err(TemplateError::TooManyDecimals(name.to_string(), decimals));
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    return Err(TemplateError::InvalidNumberArgument(name.to_string(), arg.clone()));
+                }
             }
         }
-        placeholders
     }
 
-    fn get_nested_value(&self, data: &Value, path: &str) -> Option<String> {
-        let keys: Vec<&str> = path.split('.').collect();
-        let mut current = data;
-        for key in keys {
-            if let Some(value) = current.get(key) {
-                current = value;
-            } else {
-                return None;
+    if name == "token_units" && context.token.is_none() {
+        return Err(TemplateError::MissingTokenConfig(name.to_string()));
+    }
+
+    if name.starts_with("explorer_") {
+        if let Some(network) =
+            networks.iter().find(|network| !context.block_explorers.contains_key(*network))
+        {
+            return Err(TemplateError::MissingBlockExplorer(name.to_string(), network.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+fn get_nested_value<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = data;
+    for key in path.split('.') {
+        current = match current {
+            Value::Array(values) => values.get(key.parse::<usize>().ok()?)?,
+            _ => current.get(key)?,
+        };
+    }
+
+    Some(current)
+}
+
+fn value_to_string(value: &Value) -> String {
+    value.to_string().replace('"', "")
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(value)) => *value,
+        Some(Value::String(value)) => !value.is_empty() && value != "0",
+        Some(Value::Number(value)) => value.as_f64().is_some_and(|value| value != 0.0),
+        Some(Value::Array(values)) => !values.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+fn add_thousands_separators(integer: &str) -> String {
+    let (sign, digits) = match integer.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", integer),
+    };
+
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, c) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+
+    format!("{}{}", sign, formatted)
+}
+
+/// Formats a decimal string with thousands separators, if decimals are passed the fraction is
+/// truncated or padded to that length
+fn format_number(value: &str, decimals: Option<usize>) -> String {
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let integer = add_thousands_separators(integer);
+
+    match decimals {
+        Some(0) => integer,
+        Some(decimals) => {
+            let fraction: String =
+                fraction.chars().chain(std::iter::repeat('0')).take(decimals).collect();
+            format!("{}.{}", integer, fraction)
+        }
+        None if fraction.is_empty() => integer,
+        None => format!("{}.{}", integer, fraction),
+    }
+}
+
+/// Converts a raw integer value into a decimal string using the decimals
+fn format_units(value: &str, decimals: u32) -> String {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => ("-", value),
+        None => ("", value),
+    };
+
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    };
+
+    // decimals over 77 overflow a uint256 so the raw value is shown
+    match parsed.zip(U256::from(10).checked_pow(decimals.into())) {
+        Some((value, divisor)) => {
+            let integer_part = value / divisor;
+            let fractional_part = value % divisor;
+            if fractional_part.is_zero() {
+                return format!("{}{}", sign, integer_part);
             }
+
+            let fractional = format!("{:0>width$}", fractional_part, width = decimals as usize);
+            format!("{}{}.{}", sign, integer_part, fractional.trim_end_matches('0'))
         }
-        Some(current.to_string().replace('"', ""))
-    }
-
-    fn evaluate_function(&self, function_call: &str, event_data: &Value) -> Option<String> {
-        let re = Regex::new(r"(\w+)\(([^)]+)\)").unwrap();
-        if let Some(captures) = re.captures(function_call) {
-            let function_name = &captures[1];
-            let args: Vec<&str> = captures[2].split(',').map(|s| s.trim()).collect();
-            if function_name == "format_value" && args.len() == 2 {
-                if let Some(value_str) = self.get_nested_value(event_data, args[0]) {
-                    if let Ok(decimals) = args[1].parse::<u32>() {
-                        return Some(self.format_value(&value_str, decimals));
+        None => format!("{}{}", sign, value),
+    }
+}
+
+/// Counts chars rather than bytes so any string param can be shortened
+fn shorten_address(value: &str, chars: usize) -> String {
+    let prefix = if value.starts_with("0x") { 2 } else { 0 };
+    let length = value.chars().count();
+    if length <= prefix + chars * 2 {
+        return value.to_string();
+    }
+
+    let start: String = value.chars().take(prefix + chars).collect();
+    let end: String = value.chars().skip(length - chars).collect();
+    format!("{}...{}", start, end)
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let seconds = match value.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => value.parse::<i64>().ok()?,
+    };
+
+    DateTime::from_timestamp(seconds, 0)
+}
+
+fn relative_time(timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - timestamp).num_seconds();
+    let (amount, future) = (seconds.unsigned_abs(), seconds < 0);
+    if amount < 5 {
+        return "just now".to_string();
+    }
+
+    let (amount, unit) = match amount {
+        0..=59 => (amount, "second"),
+        60..=3_599 => (amount / 60, "minute"),
+        3_600..=86_399 => (amount / 3_600, "hour"),
+        _ => (amount / 86_400, "day"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+
+    if future {
+        format!("in {} {}{}", amount, unit, plural)
+    } else {
+        format!("{} {}{} ago", amount, unit, plural)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    value: String,
+    nodes: Option<Vec<Node>>,
+    context: Arc<TemplateContext>,
+}
+
+impl Template {
+    pub fn new(value: String) -> Self {
+        // invalid templates are rejected when the manifest is read so fallback to the raw text
+        let nodes = parse_nodes(&value).ok();
+        Self { value, nodes, context: Arc::new(TemplateContext::default()) }
+    }
+
+    /// Unlike `new` an invalid template is an error rather than sent as the raw text
+    pub fn parse(value: String) -> Result<Self, TemplateError> {
+        let nodes = parse_nodes(&value)?;
+        Ok(Self { value, nodes: Some(nodes), context: Arc::new(TemplateContext::default()) })
+    }
+
+    pub fn with_context(mut self, context: Arc<TemplateContext>) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Validates the template syntax and that the helpers used have the config they need
+    pub fn validate(
+        value: &str,
+        context: &TemplateContext,
+        networks: &[String],
+    ) -> Result<(), TemplateError> {
+        validate_nodes(&parse_nodes(value)?, context, networks)
+    }
+
+    pub fn parse_template_inline(&self, event_data: &Value) -> String {
+        match &self.nodes {
+            Some(nodes) => self.render(nodes, event_data),
+            None => self.value.clone(),
+        }
+    }
+
+    fn render(&self, nodes: &[Node], data: &Value) -> String {
+        let mut output = String::new();
+        for node in nodes {
+            match node {
+                Node::Text(text) => output.push_str(text),
+                Node::Placeholder(path) => match get_nested_value(data, path) {
+                    Some(value) => output.push_str(&value_to_string(value)),
+                    None => output.push_str(&format!("{{{{{}}}}}", path)),
+                },
+                Node::Helper { name, args, raw } => match self.evaluate_helper(name, args, data) {
+                    Some(value) => output.push_str(&value),
+                    None => output.push_str(&format!("{{{{{}}}}}", raw)),
+                },
+                Node::If { condition, then, otherwise } => {
+                    let matched = if is_path(condition) {
+                        is_truthy(get_nested_value(data, condition))
+                    } else {
+                        evaluate_expression(data, condition).unwrap_or(false)
+                    };
+                    output.push_str(&self.render(if matched { then } else { otherwise }, data));
+                }
+                Node::Each { path, body } => {
+                    if let Some(Value::Array(items)) = get_nested_value(data, path) {
+                        for (index, item) in items.iter().enumerate() {
+                            let mut scope = data.clone();
+                            if let Value::Object(map) = &mut scope {
+                                map.insert("this".to_string(), item.clone());
+                                map.insert("@index".to_string(), Value::from(index));
+                            }
+                            output.push_str(&self.render(body, &scope));
+                        }
                     }
                 }
             }
         }
-        None
+
+        output
+    }
+
+    fn network<'a>(&self, data: &'a Value) -> Option<&'a str> {
+        get_nested_value(data, "transaction_information.network").and_then(|value| value.as_str())
     }
 
-    fn format_value(&self, value: &str, decimals: u32) -> String {
-        match U64::from_dec_str(value) {
-            Ok(v) => {
-                let divisor = U64::from(10).pow(decimals.into());
-                let integer_part = v / divisor;
-                let fractional_part = v % divisor;
-                if fractional_part.is_zero() {
-                    return integer_part.to_string();
+    fn evaluate_helper(&self, name: &str, args: &[String], data: &Value) -> Option<String> {
+        let value = value_to_string(get_nested_value(data, args.first()?)?);
+        let number_arg = |index: usize| args.get(index).and_then(|arg| arg.parse::<u32>().ok());
+
+        match name {
+            "format_value" => Some(format_units(&value, number_arg(1)?)),
+            "format_number" => {
+                Some(format_number(&value, number_arg(1).map(|decimals| decimals as usize)))
+            }
+            "token_units" => {
+                let token = self.context.token.as_ref()?;
+                let units = format_units(&value, token.decimals);
+                let formatted = format_number(&units, number_arg(1).map(|value| value as usize));
+                match &token.symbol {
+                    Some(symbol) => Some(format!("{} {}", formatted, symbol)),
+                    None => Some(formatted),
                 }
-                format!("{}.{}", integer_part, fractional_part)
             }
-            Err(_) => value.to_string(),
+            "shorten_address" => Some(shorten_address(&value, number_arg(1).unwrap_or(4) as usize)),
+            "explorer_tx" | "explorer_address" | "explorer_block" => {
+                let explorer = self.context.block_explorers.get(self.network(data)?)?;
+                let path = match name {
+                    "explorer_tx" => "tx",
+                    "explorer_address" => "address",
+                    _ => "block",
+                };
+                Some(format!("{}/{}/{}", explorer, path, value))
+            }
+            "format_time" => {
+                let timestamp = parse_timestamp(&value)?;
+                let format = args.get(1).map_or("%Y-%m-%d %H:%M:%S UTC", |format| format);
+                Some(timestamp.format(format).to_string())
+            }
+            "relative_time" => Some(relative_time(parse_timestamp(&value)?, Utc::now())),
+            _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn context() -> Arc<TemplateContext> {
+        Arc::new(TemplateContext {
+            token: Some(ChatTokenConfig { decimals: 18, symbol: Some("RETH".to_string()) }),
+            block_explorers: HashMap::from([(
+                "ethereum".to_string(),
+                "https://etherscan.io".to_string(),
+            )]),
+        })
+    }
+
+    fn render(template: &str, data: &Value) -> String {
+        Template::new(template.to_string()).with_context(context()).parse_template_inline(data)
+    }
+
+    fn event_data() -> Value {
+        json!({
+            "from": "0x0338ce5020c447f7e668dc2ef778025ce3982662",
+            "value": "1234567050000000000000",
+            "ids": ["1", "2"],
+            "timestamp": "1700000000",
+            "transaction_information": {
+                "network": "ethereum",
+                "transaction_hash": "0xabc"
+            }
+        })
+    }
+
+    #[test]
+    fn test_placeholders_and_helpers() {
+        let data = event_data();
+        assert_eq!(render("{{format_value(value, 18)}}", &data), "1234.56705");
+        assert_eq!(render("{{token_units(value, 2)}}", &data), "1,234.56 RETH");
+        assert_eq!(render("{{format_number(value)}}", &data), "1,234,567,050,000,000,000,000");
+        assert_eq!(render("{{shorten_address(from)}}", &data), "0x0338...2662");
+        assert_eq!(shorten_address("héllo wörld ünïcode", 3), "hél...ode");
+        assert_eq!(shorten_address("ünïcödé", 4), "ünïcödé");
+        assert_eq!(
+            render("{{explorer_tx(transaction_information.transaction_hash)}}", &data),
+            "https://etherscan.io/tx/0xabc"
+        );
+        assert_eq!(render("{{format_time(timestamp)}}", &data), "2023-11-14 22:13:20 UTC");
+        assert_eq!(render("{{missing}}", &data), "{{missing}}");
+        assert_eq!(format_units("1234", 78), "1234");
+    }
+
+    #[test]
+    fn test_conditional_blocks_and_loops() {
+        let data = event_data();
+        assert_eq!(
+            render("{{#if value > 1000000000000000000000}}whale{{else}}fish{{/if}}", &data),
+            "whale"
+        );
+        assert_eq!(render("{{#if missing}}yes{{else}}no{{/if}}", &data), "no");
+        assert_eq!(render("{{#each ids}}[{{@index}}:{{this}}]{{/each}}", &data), "[0:1][1:2]");
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let earlier = DateTime::from_timestamp(1_699_999_880, 0).unwrap();
+        assert_eq!(relative_time(earlier, now), "2 minutes ago");
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        let networks = vec!["ethereum".to_string()];
+        let invalid = [
+            "{{#if value}}unclosed",
+            "{{/each}}",
+            "{{unknown_helper(value)}}",
+            "{{format_value(value)}}",
+            "{{#if value >}}x{{/if}}",
+            "{{explorer_tx(hash)}}",
+            "{{format_value(value, 78)}}",
+        ];
+        let context = TemplateContext::default();
+        for template in invalid {
+            assert!(
+                Template::validate(template, &context, &networks).is_err(),
+                "{} should be invalid",
+                template
+            );
+        }
+        assert!(Template::validate("{{#each ids}}{{this}}{{/each}}", &context, &networks).is_ok());
+
+        // parsed up front a syntax error is an error instead of falling back to the raw text
+        assert!(Template::parse("{{#if value}}unclosed".to_string()).is_err());
+        let template = Template::parse("{{value}}".to_string()).unwrap();
+        assert_eq!(template.parse_template_inline(&event_data()), "1234567050000000000000");
+    }
+}
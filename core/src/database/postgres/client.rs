@@ -1,4 +1,7 @@
-use std::{env, time::Duration};
+use std::{
+    env,
+    time::{Duration, Instant},
+};
 
 use bb8::{Pool, RunError};
 use bb8_postgres::PostgresConnectionManager;
@@ -15,10 +18,13 @@ use tokio_postgres::{
     Config, CopyInSink, Error as PgError, Row, Statement, ToStatement,
     Transaction as PgTransaction,
 };
-use tracing::{debug, error};
+use tracing::{debug, error, info_span, Instrument};
 
-use crate::database::postgres::{
-    generate::generate_event_table_columns_names_sql, sql_type_wrapper::EthereumSqlTypeWrapper,
+use crate::{
+    database::postgres::{
+        generate::generate_event_table_columns_names_sql, sql_type_wrapper::EthereumSqlTypeWrapper,
+    },
+    metrics::{record_postgres_insert, PostgresInsertMethod},
 };
 
 pub fn connection_string() -> Result<String, env::VarError> {
@@ -190,6 +196,21 @@ impl PostgresClient {
         Ok(rows)
     }
 
+    /// Runs a read query which is cancelled by postgres if it takes longer than the timeout
+    pub async fn query_with_timeout(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+        timeout_ms: u64,
+    ) -> Result<Vec<Row>, PostgresError> {
+        let mut conn = self.pool.get().await?;
+        let transaction = conn.transaction().await?;
+        transaction.batch_execute(&format!("SET LOCAL statement_timeout = {}", timeout_ms)).await?;
+        let rows = transaction.query(query, params).await?;
+        transaction.commit().await?;
+        Ok(rows)
+    }
+
     pub async fn query_one<T>(
         &self,
         query: &T,
@@ -269,18 +290,26 @@ impl PostgresClient {
 
         //debug!("Prepared data: {:?}", prepared_data);
 
-        let sink = self.copy_in(&stmt).await?;
+        let span =
+            info_span!("postgres_insert", method = "copy", table = table_name, rows = data.len());
+        async {
+            let started = Instant::now();
+            let sink = self.copy_in(&stmt).await?;
 
-        let writer = BinaryCopyInWriter::new(sink, column_types);
-        pin_mut!(writer);
+            let writer = BinaryCopyInWriter::new(sink, column_types);
+            pin_mut!(writer);
 
-        for row in prepared_data.iter() {
-            writer.as_mut().write(row).await?;
-        }
+            for row in prepared_data.iter() {
+                writer.as_mut().write(row).await?;
+            }
 
-        writer.finish().await?;
+            writer.finish().await?;
+            record_postgres_insert(PostgresInsertMethod::Copy, started.elapsed());
 
-        Ok(())
+            Ok(())
+        }
+        .instrument(span)
+        .await
     }
 
     pub async fn bulk_insert<'a>(
@@ -313,6 +342,19 @@ impl PostgresClient {
             }
         }
 
-        self.execute(&query, &params).await
+        let started = Instant::now();
+        let result = self
+            .execute(&query, &params)
+            .instrument(info_span!(
+                "postgres_insert",
+                method = "batch",
+                table = table_name,
+                rows = bulk_data.len()
+            ))
+            .await;
+        if result.is_ok() {
+            record_postgres_insert(PostgresInsertMethod::Batch, started.elapsed());
+        }
+        result
     }
 }
@@ -127,7 +127,7 @@ pub enum GenerateTablesForIndexerSqlError {
 
 /// If any event names match the whole table name should be exposed differently on graphql
 /// to avoid clashing of graphql namings
-fn find_clashing_event_names(
+pub(crate) fn find_clashing_event_names(
     project_path: &Path,
     current_contract: &Contract,
     other_contracts: &[Contract],
@@ -14,6 +14,7 @@ use tracing::{debug, error};
 use crate::{
     event::contract_setup::{ContractInformation, NetworkContract},
     indexer::start::ProcessedNetworkContract,
+    streams::StreamCheckpoints,
 };
 
 pub type Decoder = Arc<dyn Fn(Vec<H256>, Bytes) -> Arc<dyn Any + Send + Sync> + Send + Sync>;
@@ -39,6 +40,8 @@ pub struct TxInformation {
 pub struct LogFoundInRequest {
     pub from_block: U64,
     pub to_block: U64,
+    /// true when the log was found by live indexing instead of the historic sync
+    pub live: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -55,6 +58,7 @@ impl EventResult {
         log: Log,
         start_block: U64,
         end_block: U64,
+        live: bool,
     ) -> Self {
         let log_meta = LogMeta::from(&log);
         let log_address = log.address;
@@ -70,7 +74,11 @@ impl EventResult {
                 transaction_index: log_meta.transaction_index,
                 log_index: log_meta.log_index,
             },
-            found_in_request: LogFoundInRequest { from_block: start_block, to_block: end_block },
+            found_in_request: LogFoundInRequest {
+                from_block: start_block,
+                to_block: end_block,
+                live,
+            },
         }
     }
 }
@@ -87,6 +95,9 @@ pub struct EventCallbackRegistryInformation {
     pub index_event_in_order: bool,
     pub contract: ContractInformation,
     pub callback: EventCallbackType,
+    /// The checkpoints the stream clients of the callback skip sinks with, shared so the last
+    /// synced block is read and written through the same instance
+    pub stream_checkpoints: Option<Arc<StreamCheckpoints>>,
 }
 
 impl EventCallbackRegistryInformation {
@@ -105,6 +116,7 @@ impl Clone for EventCallbackRegistryInformation {
             index_event_in_order: self.index_event_in_order,
             contract: self.contract.clone(),
             callback: Arc::clone(&self.callback),
+            stream_checkpoints: self.stream_checkpoints.clone(),
         }
     }
 }
@@ -1,91 +1,633 @@
-use ethers::prelude::U64;
+use std::cmp::Ordering;
+
+use ethers::types::{I256, U256};
 use serde_json::{Map, Value};
 
-fn get_nested_value(data: &Value, path: &str) -> Option<Value> {
-    let keys: Vec<&str> = path.split('.').collect();
-    let mut current = data;
-    for key in keys {
-        match current.get(key) {
-            Some(value) => current = value,
-            None => return None,
-        }
-    }
-    Some(current.clone())
-}
-
-#[allow(clippy::manual_strip)]
-fn evaluate_condition(value: &Value, condition: &str) -> bool {
-    if condition.contains("||") ||
-        condition.contains("&&") ||
-        condition.contains('>') ||
-        condition.contains('<') ||
-        condition.contains('=')
-    {
-        let parts: Vec<&str> = condition.split("||").collect();
-        for part in parts {
-            let subparts: Vec<&str> = part.split("&&").collect();
-            let mut and_result = true;
-            for subpart in subparts {
-                let (op, comp) = if subpart.starts_with(">=") {
-                    (">=", &subpart[2..])
-                } else if subpart.starts_with("<=") {
-                    ("<=", &subpart[2..])
-                } else if subpart.starts_with(">") {
-                    (">", &subpart[1..])
-                } else if subpart.starts_with("<") {
-                    ("<", &subpart[1..])
-                } else if subpart.starts_with("=") {
-                    ("=", &subpart[1..])
-                } else {
-                    ("", subpart)
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ConditionError {
+    #[error("Condition for {0} must be a string, number or boolean")]
+    InvalidConditionValue(String),
+
+    #[error("Condition for {0} is empty")]
+    EmptyCondition(String),
+
+    #[error("Condition for {0} has an unterminated string: {1}")]
+    UnterminatedString(String, String),
+
+    #[error("Condition for {0} is invalid: {1} - expected {2}")]
+    UnexpectedToken(String, String, &'static str),
+
+    #[error("Condition for {0} is invalid: unexpected end of condition - expected {1}")]
+    UnexpectedEnd(String, &'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Field(String),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+    In,
+    NotIn,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Literal(String),
+    Field(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    // `None` as the left side means the field the condition is defined on
+    Compare { left: Option<String>, operator: Operator, right: Vec<Operand> },
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() &&
+        !matches!(c, '(' | ')' | '[' | ']' | ',' | '&' | '|' | '<' | '>' | '=' | '!')
+}
+
+fn tokenize(field: &str, condition: &str) -> Result<Vec<Token>, ConditionError> {
+    let chars: Vec<char> = condition.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::OpenParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::CloseParen);
+                i += 1;
+            }
+            '[' => {
+                tokens.push(Token::OpenBracket);
+                i += 1;
+            }
+            ']' => {
+                tokens.push(Token::CloseBracket);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '>' | '<' | '=' | '!' => {
+                let (token, length) = match (c, next) {
+                    ('>', Some('=')) => (Token::Operator(Operator::GreaterThanOrEqual), 2),
+                    ('<', Some('=')) => (Token::Operator(Operator::LessThanOrEqual), 2),
+                    ('=', Some('=')) => (Token::Operator(Operator::Equal), 2),
+                    ('!', Some('=')) => (Token::Operator(Operator::NotEqual), 2),
+                    ('>', _) => (Token::Operator(Operator::GreaterThan), 1),
+                    ('<', _) => (Token::Operator(Operator::LessThan), 1),
+                    ('=', _) => (Token::Operator(Operator::Equal), 1),
+                    _ => (Token::Not, 1),
                 };
+                tokens.push(token);
+                i += length;
+            }
+            '"' | '\'' => {
+                let end = chars[i + 1..].iter().position(|ch| *ch == c).ok_or_else(|| {
+                    ConditionError::UnterminatedString(field.to_string(), condition.to_string())
+                })?;
+                tokens.push(Token::Quoted(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                if start == i {
+                    return Err(ConditionError::UnexpectedToken(
+                        field.to_string(),
+                        c.to_string(),
+                        "a value",
+                    ));
+                }
 
-                and_result &= match op {
-                    ">=" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() >=
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
+                let word: String = chars[start..i].iter().collect();
+                let token = match word.as_str() {
+                    "in" => Token::Operator(Operator::In),
+                    "contains" => Token::Operator(Operator::Contains),
+                    _ if word.starts_with('$') && word.len() > 1 => {
+                        Token::Field(word[1..].to_string())
                     }
-                    "<=" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() <=
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    ">" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() >
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    "<" => {
-                        U64::from_str_radix(value.as_str().unwrap_or("0"), 10).unwrap_or_default() <
-                            U64::from_str_radix(comp, 10).unwrap_or_default()
-                    }
-                    "=" => value == &Value::String(comp.to_string()),
-                    "" => value == &Value::String(subpart.to_string()),
-                    _ => false,
+                    _ => Token::Word(word),
                 };
-            }
-            if and_result {
-                return true;
+                tokens.push(token);
             }
         }
-        false
-    } else {
-        value == &Value::String(condition.to_string())
     }
+
+    // `not in` is written as two words so join them up here
+    let mut joined: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if token == Token::Operator(Operator::In) &&
+            joined.last() == Some(&Token::Word("not".to_string()))
+        {
+            joined.pop();
+            joined.push(Token::Operator(Operator::NotIn));
+        } else {
+            joined.push(token);
+        }
+    }
+
+    Ok(joined)
+}
+
+struct Parser<'a> {
+    field: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
 }
 
-pub fn filter_event_data_by_conditions(
-    event_data: &Value,
-    conditions: &Vec<Map<String, Value>>,
-) -> bool {
-    for condition in conditions {
-        for (key, value) in condition {
-            if let Some(event_value) = get_nested_value(event_data, key) {
-                if !evaluate_condition(&event_value, value.as_str().unwrap_or("")) {
-                    return false;
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.position + offset)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<Token, ConditionError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| ConditionError::UnexpectedEnd(self.field.to_string(), expected))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self, token: &Token, expected: &'static str) -> ConditionError {
+        ConditionError::UnexpectedToken(self.field.to_string(), format!("{:?}", token), expected)
+    }
+
+    fn parse(mut self) -> Result<Expression, ConditionError> {
+        if self.tokens.is_empty() {
+            return Err(ConditionError::EmptyCondition(self.field.to_string()));
+        }
+
+        let expression = self.parse_or()?;
+        if let Some(token) = self.peek() {
+            return Err(self.unexpected(token, "`&&`, `||` or the end of the condition"));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, ConditionError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            let right = self.parse_and()?;
+            left = Expression::Or(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, ConditionError> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            let right = self.parse_unary()?;
+            left = Expression::And(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ConditionError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.position += 1;
+                Ok(Expression::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::OpenParen) => {
+                self.position += 1;
+                let expression = self.parse_or()?;
+                match self.next("`)`")? {
+                    Token::CloseParen => Ok(expression),
+                    token => Err(self.unexpected(&token, "`)`")),
                 }
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, ConditionError> {
+        // `field >= 10` - a field name is only the left side if an operator follows it
+        let left = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Word(word)), Some(Token::Operator(_))) |
+            (Some(Token::Field(word)), Some(Token::Operator(_))) => {
+                let word = word.clone();
+                self.position += 1;
+                Some(word)
+            }
+            _ => None,
+        };
+
+        let operator = match self.peek() {
+            Some(Token::Operator(operator)) => {
+                let operator = *operator;
+                self.position += 1;
+                operator
+            }
+            // a value on its own is an equals check
+            _ => Operator::Equal,
+        };
+
+        let right = if matches!(operator, Operator::In | Operator::NotIn) {
+            self.parse_list()?
+        } else {
+            vec![self.parse_operand()?]
+        };
+
+        Ok(Expression::Compare { left, operator, right })
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Operand>, ConditionError> {
+        match self.next("`[`")? {
+            Token::OpenBracket => {}
+            token => return Err(self.unexpected(&token, "`[`")),
+        }
+
+        let mut operands = vec![];
+        loop {
+            if self.peek() == Some(&Token::CloseBracket) && operands.is_empty() {
+                self.position += 1;
+                return Ok(operands);
+            }
+
+            operands.push(self.parse_operand()?);
+            match self.next("`,` or `]`")? {
+                Token::Comma => continue,
+                Token::CloseBracket => return Ok(operands),
+                token => return Err(self.unexpected(&token, "`,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ConditionError> {
+        match self.next("a value")? {
+            Token::Word(value) | Token::Quoted(value) => Ok(Operand::Literal(value)),
+            Token::Field(path) => Ok(Operand::Field(path)),
+            token => Err(self.unexpected(&token, "a value")),
+        }
+    }
+}
+
+fn condition_to_string(field: &str, condition: &Value) -> Result<String, ConditionError> {
+    match condition {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(ConditionError::InvalidConditionValue(field.to_string())),
+    }
+}
+
+fn parse_condition(field: &str, condition: &Value) -> Result<Expression, ConditionError> {
+    let condition = condition_to_string(field, condition)?;
+    let tokens = tokenize(field, &condition)?;
+
+    Parser { field, tokens, position: 0 }.parse()
+}
+
+/// Validates the conditions can be parsed, this is ran when the manifest is loaded
+pub fn validate_conditions(conditions: &[Map<String, Value>]) -> Result<(), ConditionError> {
+    Conditions::parse(conditions).map(|_| ())
+}
+
+/// Conditions parsed once when the stream or chat clients are built, events are then only
+/// evaluated against the parsed expressions
+#[derive(Debug, Clone)]
+pub struct Conditions {
+    expressions: Vec<(String, Expression)>,
+}
+
+impl Conditions {
+    pub fn parse(conditions: &[Map<String, Value>]) -> Result<Self, ConditionError> {
+        let mut expressions = vec![];
+        for condition in conditions {
+            for (field, value) in condition {
+                expressions.push((field.clone(), parse_condition(field, value)?));
+            }
+        }
+
+        Ok(Self { expressions })
+    }
+
+    /// If the event data matches every condition
+    pub fn matches(&self, event_data: &Value) -> bool {
+        self.expressions.iter().all(|(field, expression)| evaluate(event_data, field, expression))
+    }
+}
+
+fn parse_expression(expression: &str) -> Result<Expression, ConditionError> {
+    let tokens = tokenize(expression, expression)?;
+
+    Parser { field: expression, tokens, position: 0 }.parse()
+}
+
+/// Validates a standalone expression where every comparison names its field like `value > 10`
+pub fn validate_expression(expression: &str) -> Result<(), ConditionError> {
+    parse_expression(expression).map(|_| ())
+}
+
+/// Evaluates a standalone expression where every comparison names its field like `value > 10`
+pub fn evaluate_expression(event_data: &Value, expression: &str) -> Result<bool, ConditionError> {
+    Ok(evaluate(event_data, "", &parse_expression(expression)?))
+}
+
+fn get_nested_value<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = data;
+    for key in path.split('.') {
+        current = match current {
+            Value::Array(values) => values.get(key.parse::<usize>().ok()?)?,
+            _ => current.get(key)?,
+        };
+    }
+
+    Some(current)
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+enum Number {
+    Unsigned(U256),
+    Signed(I256),
+}
+
+fn parse_number(value: &str) -> Option<Number> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        return U256::from_str_radix(hex, 16).ok().map(Number::Unsigned);
+    }
+
+    if value.starts_with('-') {
+        return I256::from_dec_str(value).ok().map(Number::Signed);
+    }
+
+    U256::from_dec_str(value).ok().map(Number::Unsigned)
+}
+
+fn compare_numbers(left: &str, right: &str) -> Option<Ordering> {
+    match (parse_number(left)?, parse_number(right)?) {
+        (Number::Unsigned(left), Number::Unsigned(right)) => Some(left.cmp(&right)),
+        (Number::Signed(left), Number::Signed(right)) => Some(left.cmp(&right)),
+        (Number::Signed(left), Number::Unsigned(right)) => {
+            Some(I256::try_from(right).map_or(Ordering::Less, |right| left.cmp(&right)))
+        }
+        (Number::Unsigned(left), Number::Signed(right)) => {
+            Some(I256::try_from(left).map_or(Ordering::Greater, |left| left.cmp(&right)))
+        }
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value.starts_with("0x") || value.starts_with("0X")
+}
+
+/// 20 byte addresses and 32 byte hashes
+fn is_fixed_width_hex(value: &str) -> bool {
+    is_hex(value) && matches!(value.len(), 42 | 66)
+}
+
+fn values_equal(left: &str, right: &str) -> bool {
+    // addresses and hashes are compared case-insensitively, other hex values are quantities
+    // compared as numbers the same way `>` and `<` compare them
+    if is_fixed_width_hex(left) && is_fixed_width_hex(right) {
+        return left.eq_ignore_ascii_case(right);
+    }
+
+    if let Some(ordering) = compare_numbers(left, right) {
+        return ordering == Ordering::Equal;
+    }
+
+    if is_hex(left) && is_hex(right) {
+        return left.eq_ignore_ascii_case(right);
+    }
+
+    left == right
+}
+
+fn value_contains(value: &Value, needle: &str) -> bool {
+    match value {
+        Value::Array(values) => {
+            values.iter().filter_map(value_to_string).any(|value| values_equal(&value, needle))
+        }
+        _ => value_to_string(value).is_some_and(|value| {
+            if is_hex(needle) {
+                value.to_lowercase().contains(&needle.to_lowercase())
             } else {
+                value.contains(needle)
+            }
+        }),
+    }
+}
+
+fn resolve_operand(event_data: &Value, operand: &Operand) -> Option<String> {
+    match operand {
+        Operand::Literal(value) => Some(value.clone()),
+        Operand::Field(path) => get_nested_value(event_data, path).and_then(value_to_string),
+    }
+}
+
+fn evaluate(event_data: &Value, field: &str, expression: &Expression) -> bool {
+    match expression {
+        Expression::Or(left, right) => {
+            evaluate(event_data, field, left) || evaluate(event_data, field, right)
+        }
+        Expression::And(left, right) => {
+            evaluate(event_data, field, left) && evaluate(event_data, field, right)
+        }
+        Expression::Not(expression) => !evaluate(event_data, field, expression),
+        Expression::Compare { left, operator, right } => {
+            let path = left.as_deref().unwrap_or(field);
+            let Some(value) = get_nested_value(event_data, path) else {
                 return false;
+            };
+
+            let right: Vec<String> =
+                right.iter().filter_map(|operand| resolve_operand(event_data, operand)).collect();
+
+            if *operator == Operator::Contains {
+                return right.first().is_some_and(|needle| value_contains(value, needle));
+            }
+
+            let Some(value) = value_to_string(value) else {
+                return false;
+            };
+
+            match operator {
+                Operator::In => right.iter().any(|right| values_equal(&value, right)),
+                Operator::NotIn => !right.iter().any(|right| values_equal(&value, right)),
+                _ => {
+                    let Some(right) = right.first() else {
+                        return false;
+                    };
+
+                    match operator {
+                        Operator::Equal => values_equal(&value, right),
+                        Operator::NotEqual => !values_equal(&value, right),
+                        Operator::GreaterThan => {
+                            compare_numbers(&value, right) == Some(Ordering::Greater)
+                        }
+                        Operator::GreaterThanOrEqual => matches!(
+                            compare_numbers(&value, right),
+                            Some(Ordering::Greater | Ordering::Equal)
+                        ),
+                        Operator::LessThan => {
+                            compare_numbers(&value, right) == Some(Ordering::Less)
+                        }
+                        Operator::LessThanOrEqual => matches!(
+                            compare_numbers(&value, right),
+                            Some(Ordering::Less | Ordering::Equal)
+                        ),
+                        Operator::In | Operator::NotIn | Operator::Contains => false,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event_data() -> Value {
+        json!({
+            "from": "0x0338Ce5020c447f7e668DC2ef778025CE3982662",
+            "to": "0x0338ce5020c447f7e668dc2ef778025ce398266a",
+            "value": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+            "fee": "30000",
+            "delta": "-500",
+            "ids": ["1", "2", "3"],
+            "transaction_information": {
+                "block_number": "0x1122ff0",
+                "network": "ethereum"
             }
+        })
+    }
+
+    fn matches(field: &str, condition: &str) -> bool {
+        let mut map = Map::new();
+        map.insert(field.to_string(), Value::String(condition.to_string()));
+        Conditions::parse(&[map]).unwrap().matches(&event_data())
+    }
+
+    #[test]
+    fn test_existing_syntax() {
+        assert!(matches("fee", ">=20000&&<=40000"));
+        assert!(matches("fee", ">=20000 && fee <=40000"));
+        assert!(!matches("fee", ">30000"));
+        assert!(matches("fee", "10 || 30000"));
+        assert!(matches("fee", "=30000"));
+    }
+
+    #[test]
+    fn test_uint256_and_signed_comparison() {
+        assert!(matches(
+            "value",
+            ">115792089237316195423570985008687907853269984665640564039457584007913129639934"
+        ));
+        assert!(matches("delta", "< 0 && > -1000"));
+        assert!(matches("transaction_information.block_number", ">= 17000000"));
+    }
+
+    #[test]
+    fn test_hex_quantities_are_compared_as_numbers() {
+        let event_data = json!({ "value": "0xa", "padded": "0x000a" });
+        let matches = |field: &str, condition: &str| {
+            let mut map = Map::new();
+            map.insert(field.to_string(), Value::String(condition.to_string()));
+            Conditions::parse(&[map]).unwrap().matches(&event_data)
+        };
+
+        assert!(matches("value", "= 0x0a"));
+        assert!(matches("padded", "= 0x0A"));
+        assert!(matches("value", "= 10"));
+        assert!(matches("value", "= $padded"));
+        assert!(matches("value", ">= 0x0a && <= 0x0a"));
+        assert!(!matches("value", "= 0x0b"));
+    }
+
+    #[test]
+    fn test_addresses_are_case_insensitive() {
+        assert!(matches("from", "0x0338ce5020c447f7e668dc2ef778025ce3982662"));
+        assert!(matches("from", "!= $to"));
+        assert!(!matches("from", "= $to"));
+    }
+
+    #[test]
+    fn test_in_contains_and_parentheses() {
+        assert!(matches("fee", "in [100, 500, 3000, 30000]"));
+        assert!(matches("fee", "not in [100, 500]"));
+        assert!(matches("ids", "contains 2"));
+        assert!(matches("transaction_information.network", "in ['ethereum', 'base']"));
+        assert!(matches(
+            "fee",
+            "(>= 1 && <= 10) || (fee > 20000 && transaction_information.network = ethereum)"
+        ));
+        assert!(!matches("fee", "!(> 100)"));
+    }
+
+    #[test]
+    fn test_invalid_conditions_are_rejected() {
+        let invalid = ["", ">=", "(> 10", "in 1, 2", "> 10 10", "'unterminated"];
+        for condition in invalid {
+            let mut map = Map::new();
+            map.insert("fee".to_string(), Value::String(condition.to_string()));
+            assert!(validate_conditions(&vec![map]).is_err(), "{} should be invalid", condition);
         }
     }
-    true
 }
@@ -2,18 +2,26 @@ use std::{path::PathBuf, sync::Arc};
 
 use ethers::prelude::{H256, U64};
 use tokio::sync::{Mutex, Semaphore};
+use tracing::{info_span, Span};
 
 use crate::{
+    database::postgres::generate::generate_event_table_full_name,
     event::{
         callback_registry::{EventCallbackRegistry, EventResult},
         contract_setup::NetworkContract,
         BuildRindexerFilterError, RindexerEventFilter,
     },
-    indexer::IndexingEventsProgressState,
-    manifest::storage::CsvDetails,
+    indexer::{
+        indexed_events::{publish_indexed_events, IndexedEvents},
+        IndexingEventProgressStatus, IndexingEventsProgressState,
+    },
+    manifest::{network::Finality, storage::CsvDetails},
+    provider::RequestConcurrency,
+    streams::StreamCheckpoints,
     PostgresClient,
 };
 
+#[derive(Clone)]
 pub struct EventProcessingConfig {
     pub id: String,
     pub project_path: PathBuf,
@@ -21,33 +29,120 @@ pub struct EventProcessingConfig {
     pub contract_name: String,
     pub info_log_name: String,
     pub topic_id: H256,
+    /// The topics of the other events fetched in the same `eth_getLogs` as this one
+    pub combined_topic_ids: Vec<H256>,
     pub event_name: String,
     pub network_contract: Arc<NetworkContract>,
     pub start_block: U64,
     pub end_block: U64,
-    pub semaphore: Arc<Semaphore>,
+    pub request_concurrency: RequestConcurrency,
     pub registry: Arc<EventCallbackRegistry>,
     pub progress: Arc<Mutex<IndexingEventsProgressState>>,
     pub database: Option<Arc<PostgresClient>>,
     pub csv_details: Option<CsvDetails>,
-    pub stream_last_synced_block_file_path: Option<String>,
+    pub stream_checkpoints: Option<Arc<StreamCheckpoints>>,
     pub index_event_in_order: bool,
+    /// How many block ranges the historic sync is split into and fetched concurrently
+    pub historic_segments: u64,
+    /// How many fetched block ranges of logs wait to be processed before fetching pauses
+    pub log_buffer_size: usize,
+    /// Bounds the callbacks running at once for events not indexed in order, sized from
+    /// `log_buffer_size` so a slow callback pauses fetching rather than piling up tasks
+    pub callbacks_in_flight: Arc<Semaphore>,
     pub live_indexing: bool,
-    pub indexing_distance_from_head: U64,
+    /// The head of the chain indexing follows
+    pub finality: Finality,
+    /// How far behind the head live events are safe from reorgs, zero when the indexer already
+    /// follows a head behind the latest block
+    pub reorg_safe_distance: U64,
 }
 
 impl EventProcessingConfig {
+    /// The config the shared `eth_getLogs` of a contract runs under, starting from the event which
+    /// is furthest behind
+    pub fn combined(configs: &[Arc<EventProcessingConfig>]) -> Self {
+        let mut combined = (*configs[0]).clone();
+        combined.event_name =
+            configs.iter().map(|config| config.event_name.as_str()).collect::<Vec<_>>().join(",");
+        combined.info_log_name = format!("{}::{}", combined.contract_name, combined.event_name);
+        combined.combined_topic_ids = configs[1..].iter().map(|config| config.topic_id).collect();
+        combined.start_block =
+            configs.iter().map(|config| config.start_block).min().unwrap_or(combined.start_block);
+        combined.index_event_in_order = configs.iter().any(|config| config.index_event_in_order);
+        combined
+    }
+
+    pub fn topic_ids(&self) -> Vec<H256> {
+        let mut topic_ids = vec![self.topic_id];
+        topic_ids.extend(&self.combined_topic_ids);
+        topic_ids
+    }
+
     pub fn to_event_filter(&self) -> Result<RindexerEventFilter, BuildRindexerFilterError> {
-        RindexerEventFilter::new(
+        let filter = RindexerEventFilter::new(
             &self.topic_id,
             &self.event_name,
             &self.network_contract.indexing_contract_setup,
             self.start_block,
             self.end_block,
-        )
+        )?;
+
+        if self.combined_topic_ids.is_empty() {
+            Ok(filter)
+        } else {
+            Ok(filter.set_topic0s(self.topic_ids()))
+        }
     }
 
     pub async fn trigger_event(&self, fn_data: Vec<EventResult>) {
         self.registry.trigger_event(&self.id, fn_data).await;
     }
+
+    /// The span the whole indexing of the event runs under, `status` is recorded as it moves on
+    pub fn indexing_span(&self) -> Span {
+        info_span!(
+            "indexing",
+            contract = %self.contract_name,
+            event = %self.event_name,
+            network = %self.network_contract.network,
+            status = IndexingEventProgressStatus::Syncing.as_str(),
+        )
+    }
+
+    /// The span which covers fetching a block range of logs through to them being processed
+    pub fn logs_span(&self, from_block: U64, to_block: U64, live: bool) -> Span {
+        let status = if live {
+            IndexingEventProgressStatus::Live
+        } else {
+            IndexingEventProgressStatus::Syncing
+        };
+
+        // each block range is its own trace rather than part of the long running indexing span
+        info_span!(
+            parent: None,
+            "index_logs",
+            contract = %self.contract_name,
+            event = %self.event_name,
+            network = %self.network_contract.network,
+            from_block = from_block.as_u64(),
+            to_block = to_block.as_u64(),
+            live,
+            status = status.as_str(),
+        )
+    }
+
+    pub fn publish_indexed_events(&self, from_block: U64, to_block: U64, has_events: bool) {
+        publish_indexed_events(IndexedEvents {
+            table_name: generate_event_table_full_name(
+                &self.indexer_name,
+                &self.contract_name,
+                &self.event_name,
+            ),
+            network: self.network_contract.network.clone(),
+            from_block,
+            to_block,
+            has_events,
+            reorg_safe_distance: self.reorg_safe_distance,
+        });
+    }
 }
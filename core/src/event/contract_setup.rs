@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     event::callback_registry::Decoder,
     generate_random_id,
-    manifest::contract::{Contract, EventInputIndexedFilters},
+    manifest::contract::{AddressSource, Contract, EventInputIndexedFilters, HistoricSource},
     provider::{CreateNetworkProvider, JsonRpcCachedProvider},
 };
 
@@ -22,6 +22,7 @@ pub struct NetworkContract {
     pub decoder: Decoder,
     pub start_block: Option<U64>,
     pub end_block: Option<U64>,
+    pub historic_source: Option<HistoricSource>,
     pub disable_logs_bloom_checks: bool,
 }
 
@@ -41,6 +42,9 @@ pub struct ContractInformation {
     pub details: Vec<NetworkContract>,
     pub abi: String,
     pub reorg_safe_distance: bool,
+    pub historic_segments: u64,
+    pub combine_get_logs: bool,
+    pub log_buffer_size: usize,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -74,6 +78,7 @@ impl ContractInformation {
                         indexing_contract_setup: c.indexing_contract_setup(),
                         start_block: c.start_block,
                         end_block: c.end_block,
+                        historic_source: c.historic_source.clone(),
                         disable_logs_bloom_checks: provider.disable_logs_bloom_checks,
                     });
                 }
@@ -85,6 +90,9 @@ impl ContractInformation {
             details,
             abi: contract.abi.clone(),
             reorg_safe_distance: contract.reorg_safe_distance.unwrap_or_default(),
+            historic_segments: contract.historic_segments.unwrap_or(1),
+            combine_get_logs: contract.combine_get_logs.unwrap_or_default(),
+            log_buffer_size: contract.get_log_buffer_size(),
         })
     }
 }
@@ -100,6 +108,9 @@ pub struct AddressDetails {
     pub address: ValueOrArray<Address>,
 
     pub indexed_filters: Option<Vec<EventInputIndexedFilters>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_source: Option<AddressSource>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -133,4 +144,15 @@ impl IndexingContractSetup {
     pub fn is_filter(&self) -> bool {
         matches!(self, IndexingContractSetup::Filter(_))
     }
+
+    /// Indexed filters are per event so only plain addresses can fetch every event in one request
+    pub fn can_combine_get_logs(&self) -> bool {
+        match self {
+            IndexingContractSetup::Address(details) => {
+                details.indexed_filters.as_ref().is_none_or(|filters| filters.is_empty())
+            }
+            IndexingContractSetup::Factory(_) => true,
+            IndexingContractSetup::Filter(_) => false,
+        }
+    }
 }
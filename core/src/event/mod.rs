@@ -10,4 +10,6 @@ mod message;
 pub use message::EventMessage;
 
 mod conditions;
-pub use conditions::filter_event_data_by_conditions;
+pub use conditions::{
+    evaluate_expression, validate_conditions, validate_expression, ConditionError, Conditions,
+};
@@ -123,6 +123,26 @@ impl RindexerEventFilter {
         self
     }
 
+    /// Matches any of the topics so the logs of several events come back in one request
+    pub fn set_topic0s(mut self, topic_ids: Vec<H256>) -> Self {
+        self.filter = self.filter.topic0(topic_ids);
+        self
+    }
+
+    /// The filter split so each has at most `max_addresses` addresses, providers limit how big a
+    /// filter can be
+    pub fn split_addresses(&self, max_addresses: usize) -> Vec<RindexerEventFilter> {
+        match &self.filter.address {
+            Some(ValueOrArray::Array(addresses)) if addresses.len() > max_addresses => addresses
+                .chunks(max_addresses)
+                .map(|chunk| RindexerEventFilter {
+                    filter: self.filter.clone().address(chunk.to_vec()),
+                })
+                .collect(),
+            _ => vec![self.clone()],
+        }
+    }
+
     pub fn contract_address(&self) -> Option<ValueOrArray<Address>> {
         self.filter.address.clone()
     }
@@ -378,7 +378,8 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
 
             use self::rindexer_lib::indexers::all_handlers::register_all_handlers;
             use rindexer::{
-                start_rindexer, GraphqlOverrideSettings, IndexingDetails, StartDetails,
+                start_rindexer, GraphqlOverrideSettings, IndexUntil, IndexingDetails,
+                RestOverrideSettings, StartDetails,
             };
 
             mod rindexer_lib;
@@ -389,8 +390,11 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
 
                 let mut enable_graphql = false;
                 let mut enable_indexer = false;
+                let mut enable_rest = false;
                 
                 let mut port: Option<u16> = None;
+                let mut rest_port: Option<u16> = None;
+                let mut until: Option<IndexUntil> = None;
 
                 let args = args.iter();
                 if args.len() == 0 {
@@ -402,6 +406,26 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
                     match arg.as_str() {
                         "--graphql" => enable_graphql = true,
                         "--indexer" => enable_indexer = true,
+                        "--rest" => enable_rest = true,
+                        "--no-live" => until = Some(IndexUntil::Latest),
+                        _ if arg.starts_with("--until=") => {
+                            match arg.trim_start_matches("--until=").parse::<IndexUntil>() {
+                                Ok(value) => until = Some(value),
+                                Err(e) => {
+                                    println!("{}", e);
+                                    return;
+                                }
+                            }
+                        },
+                        _ if arg.starts_with("--rest-port=") => {
+                            match arg.trim_start_matches("--rest-port=").parse::<u16>() {
+                                Ok(value) => rest_port = Some(value),
+                                Err(_) => {
+                                    println!("Invalid rest port number");
+                                    return;
+                                }
+                            }
+                        },
                         _ if arg.starts_with("--port=") || arg.starts_with("--p") => {
                             if let Some(value) = arg.split('=').nth(1) {
                                 let overridden_port = value.parse::<u16>();
@@ -422,26 +446,34 @@ serde = {{ version = "1.0.194", features = ["derive"] }}
                 match path {
                     Ok(path) => {
                         let manifest_path = path.join("rindexer.yaml");
-                        let result = start_rindexer(StartDetails {
-                            manifest_path: &manifest_path,
-                            indexing_details: if enable_indexer {
-                                Some(IndexingDetails {
-                                    registry: register_all_handlers(&manifest_path).await,
-                                })
-                            } else {
-                                None
-                            },
-                            graphql_details: GraphqlOverrideSettings {
-                                enabled: enable_graphql,
-                                override_port: port,
-                            }
-                        })
+                        let indexing_details = if enable_indexer {
+                            Some(
+                                IndexingDetails::new(register_all_handlers(&manifest_path).await)
+                                    .with_until(until),
+                            )
+                        } else {
+                            None
+                        };
+                        let graphql_details = GraphqlOverrideSettings {
+                            enabled: enable_graphql,
+                            override_port: if enable_graphql { port } else { None },
+                        };
+                        let rest_details = RestOverrideSettings {
+                            enabled: enable_rest,
+                            // --port is for REST when it runs without GraphQL
+                            override_port: rest_port.or(if enable_graphql { None } else { port }),
+                        };
+                        let result = start_rindexer(
+                            StartDetails::new(&manifest_path, indexing_details, graphql_details)
+                                .with_rest_details(rest_details),
+                        )
                         .await;
                         
                         match result {
                             Ok(_) => {}
                             Err(e) => {
                                 println!("Error starting rindexer: {:?}", e);
+                                std::process::exit(1);
                             }
                         }
                     }
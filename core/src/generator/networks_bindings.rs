@@ -15,9 +15,10 @@ pub fn network_provider_fn_name(network: &Network) -> String {
 fn generate_network_lazy_provider_code(network: &Network) -> Code {
     Code::new(format!(
         r#"
-            static ref {network_name}: Arc<JsonRpcCachedProvider> = {client_fn}(&public_read_env_value("{network_url}").unwrap_or("{network_url}".to_string()), {compute_units_per_second}, {max_block_range} {placeholder_headers}).expect("Error creating provider");
+            static ref {network_name}: Arc<JsonRpcCachedProvider> = {client_fn}("{network}", &public_read_env_value("{network_url}").unwrap_or("{network_url}".to_string()), {compute_units_per_second}, {max_block_range} {placeholder_headers}).expect("Error creating provider");
         "#,
         network_name = network_provider_name(network),
+        network = network.name,
         network_url = network.rpc,
         compute_units_per_second =
             if let Some(compute_units_per_second) = network.compute_units_per_second {
@@ -99,6 +100,7 @@ pub fn generate_networks_code(networks: &[Network]) -> Code {
             
             #[allow(dead_code)]
             fn create_shadow_client(
+                network: &str,
                 rpc_url: &str,
                 compute_units_per_second: Option<u64>,
                 max_block_range: Option<U64>,
@@ -108,7 +110,7 @@ pub fn generate_networks_code(networks: &[Network]) -> Code {
                     "X-SHADOW-API-KEY",
                     public_read_env_value("RINDEXER_PHANTOM_API_KEY").unwrap().parse().unwrap(),
                 );
-                create_client(rpc_url, compute_units_per_second, max_block_range, header)
+                create_client(network, rpc_url, compute_units_per_second, max_block_range, header)
             }
 
             lazy_static! {
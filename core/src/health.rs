@@ -0,0 +1,178 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tracing::info;
+
+use crate::{
+    indexer::{IndexingEventProgress, IndexingEventProgressStatus, IndexingEventsProgressState},
+    manifest::health::HealthSettings,
+    metrics,
+    shutdown::shutdown_signal,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum StartHealthServerError {
+    #[error("Could not start up health server {0}")]
+    HealthServerStartupError(String),
+}
+
+fn blocks_behind(event: &IndexingEventProgress) -> u64 {
+    event.syncing_to_block.saturating_sub(event.last_synced_block).as_u64()
+}
+
+/// Ready once every historic sync is complete or within the allowed blocks of where it is
+/// syncing to, and every live network is within the allowed blocks of the chain head
+fn is_ready(
+    events: &[IndexingEventProgress],
+    settings: &HealthSettings,
+    head_lags: &HashMap<String, u64>,
+) -> bool {
+    events.iter().all(|event| match event.status {
+        IndexingEventProgressStatus::Live => {
+            settings.ready_max_head_lag.is_none_or(|max_head_lag| {
+                head_lags.get(&event.network).is_none_or(|head_lag| *head_lag <= max_head_lag)
+            })
+        }
+        IndexingEventProgressStatus::Completed => true,
+        _ => blocks_behind(event) <= settings.ready_max_blocks_behind,
+    })
+}
+
+/// The head lag of each network measured from its live events, completed events stop at their
+/// end block so they would always look behind
+fn live_head_lags(events: &[IndexingEventProgress]) -> HashMap<String, u64> {
+    let live: Vec<IndexingEventProgress> = events
+        .iter()
+        .filter(|event| matches!(event.status, IndexingEventProgressStatus::Live))
+        .cloned()
+        .collect();
+    metrics::head_lags(&live)
+}
+
+fn events_ready(events: &[IndexingEventProgress], settings: &HealthSettings) -> bool {
+    is_ready(events, settings, &live_head_lags(events))
+}
+
+async fn current_events() -> Option<Vec<IndexingEventProgress>> {
+    let state = IndexingEventsProgressState::current()?;
+    let events = state.lock().await.events.clone();
+    Some(events)
+}
+
+async fn ready(settings: HealthSettings) -> impl IntoResponse {
+    let ready = current_events().await.is_some_and(|events| events_ready(&events, &settings));
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (status, Json(json!({ "ready": ready })))
+}
+
+async fn status(settings: HealthSettings) -> Json<Value> {
+    let Some(events) = current_events().await else {
+        return Json(json!({ "ready": false, "indexing": false, "events": [] }));
+    };
+
+    Json(json!({
+        "ready": events_ready(&events, &settings),
+        "indexing": true,
+        "events": events
+            .iter()
+            .map(|event| {
+                json!({
+                    "contract_name": event.contract_name,
+                    "event_name": event.event_name,
+                    "network": event.network,
+                    "status": event.status.as_str(),
+                    "live_indexing": event.live_indexing,
+                    "finality": event.finality.to_string(),
+                    "starting_block": event.starting_block.as_u64(),
+                    "last_synced_block": event.last_synced_block.as_u64(),
+                    "syncing_to_block": event.syncing_to_block.as_u64(),
+                    "blocks_behind": blocks_behind(event),
+                    "progress": (event.progress * 10_000.0).round() / 100.0,
+                })
+            })
+            .collect::<Vec<_>>(),
+    }))
+}
+
+async fn metrics() -> impl IntoResponse {
+    let events = current_events().await.unwrap_or_default();
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], metrics::render(&events))
+}
+
+/// Runs the health server until ctrl-c is pressed
+pub async fn start_health_server(settings: &HealthSettings) -> Result<(), StartHealthServerError> {
+    let ready_settings = settings.clone();
+    let status_settings = settings.clone();
+    let app = Router::new()
+        .route("/health", get(|| async { Json(json!({ "status": "ok" })) }))
+        .route("/ready", get(move || ready(ready_settings.clone())))
+        .route("/status", get(move || status(status_settings.clone())))
+        .route("/metrics", get(metrics));
+
+    let address = SocketAddr::from(([0, 0, 0, 0], settings.port));
+    let listener = TcpListener::bind(address).await.map_err(|e| {
+        StartHealthServerError::HealthServerStartupError(format!(
+            "Could not bind to port {}: {}",
+            settings.port, e
+        ))
+    })?;
+
+    info!("Health server ready at http://localhost:{}/status", settings.port);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .map_err(|e| StartHealthServerError::HealthServerStartupError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(status: IndexingEventProgressStatus, last_synced_block: u64) -> IndexingEventProgress {
+        IndexingEventProgress::for_test(
+            "RocketPoolETH",
+            "Transfer",
+            "ethereum",
+            status,
+            last_synced_block,
+        )
+    }
+
+    fn settings(ready_max_blocks_behind: u64, ready_max_head_lag: Option<u64>) -> HealthSettings {
+        HealthSettings { ready_max_blocks_behind, ready_max_head_lag, ..Default::default() }
+    }
+
+    #[test]
+    fn test_is_ready() {
+        let live = event(IndexingEventProgressStatus::Live, 100);
+        let syncing = event(IndexingEventProgressStatus::Syncing, 95);
+        let no_lags = HashMap::new();
+
+        assert!(is_ready(std::slice::from_ref(&live), &settings(0, None), &no_lags));
+        assert!(!is_ready(&[live.clone(), syncing.clone()], &settings(0, None), &no_lags));
+        assert!(is_ready(&[live, syncing], &settings(5, None), &no_lags));
+        assert!(is_ready(&[], &settings(0, None), &no_lags));
+    }
+
+    #[test]
+    fn test_is_ready_within_head_lag() {
+        let live = event(IndexingEventProgressStatus::Live, 100);
+        let completed = event(IndexingEventProgressStatus::Completed, 100);
+        let head_lags = HashMap::from([("ethereum".to_string(), 20)]);
+
+        assert!(is_ready(std::slice::from_ref(&live), &settings(0, None), &head_lags));
+        assert!(is_ready(std::slice::from_ref(&live), &settings(0, Some(20)), &head_lags));
+        assert!(!is_ready(&[live], &settings(0, Some(10)), &head_lags));
+        assert!(is_ready(&[completed], &settings(0, Some(10)), &head_lags));
+    }
+}
@@ -1,15 +1,9 @@
-mod thread;
-
-pub use thread::set_thread_no_logging;
-
 mod file;
 
 use std::{
     env,
     env::VarError,
     path::{Path, PathBuf},
-    process::Command,
-    str,
 };
 
 use dotenv::dotenv;
@@ -80,25 +74,6 @@ pub fn get_full_path(project_path: &Path, file_path: &str) -> Result<PathBuf, st
     }
 }
 
-pub fn kill_process_on_port(port: u16) -> Result<(), String> {
-    // Use lsof to find the process using the port
-    let output = Command::new("lsof")
-        .arg(format!("-i:{}", port))
-        .arg("-t")
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    let pids =
-        str::from_utf8(&output.stdout).map_err(|e| e.to_string())?.lines().collect::<Vec<&str>>();
-
-    for pid in pids {
-        // Kill each process using the port
-        Command::new("kill").arg("-9").arg(pid).output().map_err(|e| e.to_string())?;
-    }
-
-    Ok(())
-}
-
 pub fn public_read_env_value(var_name: &str) -> Result<String, VarError> {
     dotenv().ok();
     env::var(var_name)
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use ethers::types::Address;
+use tokio::fs;
+
+use crate::{
+    database::postgres::client::{PostgresClient, PostgresError},
+    manifest::contract::AddressSource,
+};
+
+const DEFAULT_CSV_COLUMN: &str = "address";
+
+#[derive(thiserror::Error, Debug)]
+pub enum LoadAddressSourceError {
+    #[error("Could not read the addresses file {0}: {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error("Could not read the addresses CSV {0}: {1}")]
+    Csv(PathBuf, csv::Error),
+
+    #[error("The addresses CSV {0} has no {1} column")]
+    MissingCsvColumn(PathBuf, String),
+
+    #[error("Could not parse the addresses JSON in {0}: {1}")]
+    Json(PathBuf, serde_json::Error),
+
+    #[error("{0} is not a valid address - from the address source {1}")]
+    InvalidAddress(String, AddressSource),
+
+    #[error("The address source {0} needs postgres storage enabled")]
+    PostgresNotEnabled(AddressSource),
+
+    #[error("The address source {0} failed: {1}")]
+    Query(AddressSource, PostgresError),
+
+    #[error(
+        "The address source {0} must return the addresses in the first column as text or bytea"
+    )]
+    InvalidSqlColumn(AddressSource),
+
+    #[error(
+        "The address source {0} has no addresses, an empty address list would index every contract"
+    )]
+    NoAddresses(AddressSource),
+}
+
+fn parse_address(value: &str, source: &AddressSource) -> Result<Address, LoadAddressSourceError> {
+    Address::from_str(value.trim())
+        .map_err(|_| LoadAddressSourceError::InvalidAddress(value.to_string(), source.clone()))
+}
+
+fn parse_lines(
+    content: &str,
+    source: &AddressSource,
+) -> Result<Vec<Address>, LoadAddressSourceError> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| parse_address(line, source))
+        .collect()
+}
+
+fn parse_csv(
+    path: &Path,
+    content: &str,
+    column: Option<&str>,
+    source: &AddressSource,
+) -> Result<Vec<Address>, LoadAddressSourceError> {
+    let csv_error = |e| LoadAddressSourceError::Csv(path.into(), e);
+    let column = column.unwrap_or(DEFAULT_CSV_COLUMN);
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let index = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(column))
+        .ok_or_else(|| LoadAddressSourceError::MissingCsvColumn(path.into(), column.to_string()))?;
+
+    let mut addresses = vec![];
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        match record.get(index).map(str::trim) {
+            Some(value) if !value.is_empty() => addresses.push(parse_address(value, source)?),
+            _ => {}
+        }
+    }
+
+    Ok(addresses)
+}
+
+async fn query_addresses(
+    query: &str,
+    database: Option<&Arc<PostgresClient>>,
+    source: &AddressSource,
+) -> Result<Vec<Address>, LoadAddressSourceError> {
+    let database =
+        database.ok_or_else(|| LoadAddressSourceError::PostgresNotEnabled(source.clone()))?;
+    let rows = database
+        .query(query, &[])
+        .await
+        .map_err(|e| LoadAddressSourceError::Query(source.clone(), e))?;
+
+    rows.iter()
+        .map(|row| {
+            // rindexer stores addresses as text but tables of other tools often use bytea
+            if let Ok(value) = row.try_get::<_, String>(0) {
+                return parse_address(&value, source);
+            }
+            match row.try_get::<_, Vec<u8>>(0) {
+                Ok(bytes) if bytes.len() == 20 => Ok(Address::from_slice(&bytes)),
+                _ => Err(LoadAddressSourceError::InvalidSqlColumn(source.clone())),
+            }
+        })
+        .collect()
+}
+
+/// Loads the addresses of the source without duplicates, relative paths are from the project
+pub async fn load_address_source(
+    source: &AddressSource,
+    project_path: &Path,
+    database: Option<&Arc<PostgresClient>>,
+) -> Result<Vec<Address>, LoadAddressSourceError> {
+    let addresses = match source {
+        AddressSource::File { file } => {
+            let path = project_path.join(file);
+            let content = fs::read_to_string(&path)
+                .await
+                .map_err(|e| LoadAddressSourceError::Io(path.clone(), e))?;
+            if content.trim_start().starts_with('[') {
+                serde_json::from_str::<Vec<String>>(&content)
+                    .map_err(|e| LoadAddressSourceError::Json(path, e))?
+                    .iter()
+                    .map(|address| parse_address(address, source))
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                parse_lines(&content, source)?
+            }
+        }
+        AddressSource::Csv { csv, column } => {
+            let path = project_path.join(csv);
+            let content = fs::read_to_string(&path)
+                .await
+                .map_err(|e| LoadAddressSourceError::Io(path.clone(), e))?;
+            parse_csv(&path, &content, column.as_deref(), source)?
+        }
+        AddressSource::Sql { sql } => query_addresses(sql, database, source).await?,
+    };
+
+    let mut seen = HashSet::new();
+    let addresses: Vec<Address> =
+        addresses.into_iter().filter(|address| seen.insert(*address)).collect();
+    if addresses.is_empty() {
+        return Err(LoadAddressSourceError::NoAddresses(source.clone()));
+    }
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::ethereum_types::BloomInput,
+        types::{Block, Bloom, ValueOrArray, H256},
+    };
+
+    use super::*;
+    use crate::{
+        event::{
+            contract_setup::{AddressDetails, IndexingContractSetup},
+            RindexerEventFilter,
+        },
+        indexer::log_helpers::{is_relevant_block, AddressBlooms},
+        manifest::contract::ContractDetails,
+    };
+
+    #[tokio::test]
+    async fn test_load_address_source() {
+        let project = tempfile::tempdir().unwrap();
+        let first = Address::repeat_byte(1);
+        let second = Address::repeat_byte(2);
+
+        std::fs::write(
+            project.path().join("vaults.txt"),
+            format!("# vaults\n{:?}\n\n{:?}\n{:?}\n", first, second, first),
+        )
+        .unwrap();
+        std::fs::write(project.path().join("vaults.json"), format!(r#"["{:?}"]"#, second)).unwrap();
+        std::fs::write(
+            project.path().join("vaults.csv"),
+            format!("name,Vault\none,{:?}\ntwo,{:?}\nthree,\n", first, second),
+        )
+        .unwrap();
+        std::fs::write(project.path().join("empty.txt"), "\n").unwrap();
+
+        let project_path = project.path();
+        let load = |source| async move { load_address_source(&source, project_path, None).await };
+
+        let addresses = load(AddressSource::File { file: "vaults.txt".to_string() }).await.unwrap();
+        assert_eq!(addresses, vec![first, second]);
+        let addresses =
+            load(AddressSource::File { file: "vaults.json".to_string() }).await.unwrap();
+        assert_eq!(addresses, vec![second]);
+        let csv =
+            AddressSource::Csv { csv: "vaults.csv".to_string(), column: Some("vault".into()) };
+        assert_eq!(load(csv).await.unwrap(), vec![first, second]);
+
+        // the default column is `address` and an empty list would match every contract
+        let csv = AddressSource::Csv { csv: "vaults.csv".to_string(), column: None };
+        assert!(matches!(load(csv).await, Err(LoadAddressSourceError::MissingCsvColumn(..))));
+        assert!(matches!(
+            load(AddressSource::File { file: "empty.txt".to_string() }).await,
+            Err(LoadAddressSourceError::NoAddresses(_))
+        ));
+        assert!(matches!(
+            load(AddressSource::Sql { sql: "SELECT 1".to_string() }).await,
+            Err(LoadAddressSourceError::PostgresNotEnabled(_))
+        ));
+
+        let details: ContractDetails = serde_yaml::from_str(
+            "network: ethereum\naddress_source:\n  csv: vaults.csv\n  column: vault",
+        )
+        .unwrap();
+        let csv =
+            AddressSource::Csv { csv: "vaults.csv".to_string(), column: Some("vault".into()) };
+        assert_eq!(details.address_source, Some(csv));
+    }
+
+    #[test]
+    fn test_many_addresses() {
+        let addresses: Vec<Address> = (0..2_500u64).map(Address::from_low_u64_be).collect();
+        let topic_id = H256::repeat_byte(1);
+        let setup = IndexingContractSetup::Address(AddressDetails {
+            address: ValueOrArray::Array(addresses.clone()),
+            indexed_filters: None,
+            address_source: None,
+        });
+        let filter =
+            RindexerEventFilter::new(&topic_id, "Transfer", &setup, 1.into(), 2.into()).unwrap();
+
+        let chunks = filter.split_addresses(1_000);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].contract_address(), Some(addresses[2_000..].to_vec().into()));
+        assert_eq!(chunks[2].get_to_block(), 2.into());
+
+        let mut logs_bloom = Bloom::zero();
+        logs_bloom.accrue(BloomInput::Raw(addresses[2_499].as_bytes()));
+        logs_bloom.accrue(BloomInput::Raw(topic_id.as_bytes()));
+        let block = Block { logs_bloom: Some(logs_bloom), ..Default::default() };
+        let address_blooms = AddressBlooms::new(&filter.raw_filter().address);
+        assert!(is_relevant_block(&address_blooms, &topic_id, &block));
+        let address_blooms = AddressBlooms::new(&chunks[0].raw_filter().address);
+        assert!(!is_relevant_block(&address_blooms, &topic_id, &block));
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use ethers::{types::U64, utils::keccak256};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{debug, error, warn};
+
+use crate::{lazy_static, metrics::record_get_logs_block_range, shutdown::spawn_in_flight};
+
+/// The logs each `eth_getLogs` request aims for, big enough to keep batches large without the
+/// responses getting slow to download and decode
+const TARGET_LOGS_PER_REQUEST: usize = 5_000;
+
+/// Responses faster than this with few logs mean the provider has room for a wider range
+const FAST_RESPONSE: Duration = Duration::from_secs(2);
+
+/// Responses slower than this shrink the range even when the provider did not error
+const SLOW_RESPONSE: Duration = Duration::from_secs(20);
+
+/// How long a single `eth_getLogs` request gets before the range is treated as too wide
+pub const GET_LOGS_TIMEOUT: Duration = Duration::from_secs(90);
+
+const BLOCK_RANGES_FILE: &str = ".rindexer/block-ranges.json";
+
+/// The learned `eth_getLogs` block range of a network
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct NetworkBlockRange {
+    /// None requests every block up to the snapshot head and lets the provider say what it allows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    range: Option<u64>,
+
+    /// The most blocks the provider said it accepts, the range never grows past it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider_limit: Option<u64>,
+
+    /// A hash of the RPC url the range was learned from so a new provider learns its own range
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+}
+
+impl NetworkBlockRange {
+    fn ceiling(&self, configured: Option<u64>) -> Option<u64> {
+        match (self.provider_limit, configured) {
+            (Some(provider_limit), Some(configured)) => Some(provider_limit.min(configured)),
+            (provider_limit, configured) => provider_limit.or(configured),
+        }
+    }
+
+    fn current(&self, configured: Option<u64>) -> Option<u64> {
+        match (self.range, self.ceiling(configured)) {
+            (Some(range), Some(ceiling)) => Some(range.min(ceiling)),
+            (range, ceiling) => range.or(ceiling),
+        }
+    }
+
+    /// Adjusts the range after a successful request over `requested` blocks, returns true when it
+    /// changed
+    fn on_success(
+        &mut self,
+        requested: u64,
+        logs: usize,
+        elapsed: Duration,
+        configured: Option<u64>,
+    ) -> bool {
+        let current = self.current(configured);
+
+        let next = if logs > TARGET_LOGS_PER_REQUEST * 2 || elapsed > SLOW_RESPONSE {
+            let towards_target =
+                requested.saturating_mul(TARGET_LOGS_PER_REQUEST as u64) / logs.max(1) as u64;
+            Some(towards_target.min(requested / 2))
+        } else if logs < TARGET_LOGS_PER_REQUEST / 2 && elapsed < FAST_RESPONSE {
+            // only a full window says anything about the range, the last window before the head
+            // is cut short
+            match current {
+                Some(current) if requested >= current => Some(current.saturating_mul(2).max(1)),
+                _ => current,
+            }
+        } else {
+            current
+        };
+
+        let next = match (next, self.ceiling(configured)) {
+            (Some(next), Some(ceiling)) => Some(next.min(ceiling)),
+            (next, _) => next,
+        };
+
+        self.set(next, current)
+    }
+
+    /// Adjusts the range after the provider rejected or timed out a request over `requested`
+    /// blocks, `suggested` is the range the provider asked for if it said so
+    fn on_error(
+        &mut self,
+        requested: u64,
+        suggested: Option<u64>,
+        provider_limit: Option<u64>,
+        configured: Option<u64>,
+    ) -> bool {
+        let current = self.current(configured);
+        if provider_limit.is_some() {
+            self.provider_limit = provider_limit;
+        }
+        let next = suggested.unwrap_or(requested / 2);
+        self.set(Some(next), current)
+    }
+
+    fn set(&mut self, next: Option<u64>, current: Option<u64>) -> bool {
+        self.range = next;
+        next != current
+    }
+}
+
+lazy_static! {
+    static ref BLOCK_RANGES: Mutex<HashMap<String, NetworkBlockRange>> = Mutex::new(HashMap::new());
+    static ref WRITE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+fn block_ranges_path(project_path: &Path) -> PathBuf {
+    project_path.join(BLOCK_RANGES_FILE)
+}
+
+/// Loads the block ranges learned on previous runs, networks already running keep their range
+pub async fn load_block_ranges(project_path: &Path) {
+    let path = block_ranges_path(project_path);
+    let content = match fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    let stored: HashMap<String, NetworkBlockRange> = match serde_json::from_str(&content) {
+        Ok(stored) => stored,
+        Err(e) => {
+            warn!("Could not read the learned block ranges in {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    if let Ok(mut ranges) = BLOCK_RANGES.lock() {
+        for (network, range) in stored {
+            ranges.entry(network).or_insert(range);
+        }
+    }
+}
+
+fn save_block_ranges(project_path: &Path) {
+    let path = block_ranges_path(project_path);
+    spawn_in_flight(async move {
+        let _lock = WRITE_LOCK.lock().await;
+        let content = match BLOCK_RANGES.lock().map(|ranges| serde_json::to_string_pretty(&*ranges))
+        {
+            Ok(Ok(content)) => content,
+            _ => return,
+        };
+
+        let temp_path = path.with_extension("json.tmp");
+        let result = async {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::write(&temp_path, content).await?;
+            fs::rename(&temp_path, &path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!("Could not save the learned block ranges to {}: {}", path.display(), e);
+        }
+    });
+}
+
+// the url is hashed so api keys in it are not written to the block ranges file
+fn provider_id(rpc_url: &str) -> String {
+    hex::encode(&keccak256(rpc_url.as_bytes())[..8])
+}
+
+/// Adapts the `eth_getLogs` block range of a network to what the provider handles, growing it
+/// after fast small responses and shrinking it on errors, timeouts and very large responses
+pub struct AdaptiveBlockRange {
+    project_path: PathBuf,
+    network: String,
+    provider: String,
+    configured: Option<u64>,
+}
+
+impl AdaptiveBlockRange {
+    pub fn new(project_path: &Path, network: &str, rpc_url: &str, configured: Option<U64>) -> Self {
+        let provider = provider_id(rpc_url);
+
+        // a range and provider limit learned from another RPC do not apply to this one
+        if let Ok(mut ranges) = BLOCK_RANGES.lock() {
+            if let Some(range) = ranges.get_mut(network) {
+                if range.provider.as_ref() != Some(&provider) {
+                    debug!(
+                        "{} - RPC changed, the eth_getLogs block range is learned again",
+                        network
+                    );
+                    *range = NetworkBlockRange {
+                        provider: Some(provider.clone()),
+                        ..Default::default()
+                    };
+                }
+            }
+        }
+
+        AdaptiveBlockRange {
+            project_path: project_path.to_path_buf(),
+            network: network.to_string(),
+            provider,
+            configured: configured.map(|configured| configured.as_u64()),
+        }
+    }
+
+    fn update(&self, adjust: impl FnOnce(&mut NetworkBlockRange) -> bool) {
+        let (changed, range, provider_limit) = match BLOCK_RANGES.lock() {
+            Ok(mut ranges) => {
+                let entry =
+                    ranges.entry(self.network.clone()).or_insert_with(|| NetworkBlockRange {
+                        provider: Some(self.provider.clone()),
+                        ..Default::default()
+                    });
+                let provider_limit = entry.provider_limit;
+                let changed = adjust(entry);
+                let provider_limit_changed = entry.provider_limit != provider_limit;
+                (
+                    changed || provider_limit_changed,
+                    entry.current(self.configured),
+                    entry.provider_limit.filter(|_| provider_limit_changed),
+                )
+            }
+            Err(_) => return,
+        };
+
+        if let Some(provider_limit) = provider_limit {
+            warn!(
+                "{} - RPC provider limits eth_getLogs to {} blocks - block range indexing will be slower then RPC providers supplying the optimal ranges - https://rindexer.xyz/docs/references/rpc-node-providers#rpc-node-providers",
+                self.network, provider_limit
+            );
+        }
+
+        if changed {
+            if let Some(range) = range {
+                debug!("{} - eth_getLogs block range is now {} blocks", self.network, range);
+                record_get_logs_block_range(&self.network, range);
+            }
+            save_block_ranges(&self.project_path);
+        }
+    }
+
+    /// The block range to request, None means up to the snapshot head
+    pub fn current(&self) -> Option<U64> {
+        BLOCK_RANGES
+            .lock()
+            .ok()
+            .and_then(|ranges| ranges.get(&self.network).cloned())
+            .unwrap_or_default()
+            .current(self.configured)
+            .map(U64::from)
+    }
+
+    pub fn on_success(&self, from_block: U64, to_block: U64, logs: usize, elapsed: Duration) {
+        let requested = to_block.saturating_sub(from_block).as_u64();
+        self.update(|range| range.on_success(requested, logs, elapsed, self.configured));
+    }
+
+    pub fn on_error(
+        &self,
+        from_block: U64,
+        to_block: U64,
+        suggested: Option<U64>,
+        provider_limit: Option<U64>,
+    ) {
+        let requested = to_block.saturating_sub(from_block).as_u64();
+        self.update(|range| {
+            range.on_error(
+                requested,
+                suggested.map(|suggested| suggested.as_u64()),
+                provider_limit.map(|provider_limit| provider_limit.as_u64()),
+                self.configured,
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_network_block_range() {
+        let mut range = NetworkBlockRange::default();
+
+        // a full range request which worked leaves the range to the provider
+        assert!(!range.on_success(1_000_000, 10, FAST, None));
+        assert_eq!(range.current(None), None);
+
+        // a rejected request learns the suggested range then grows after fast small responses
+        assert!(range.on_error(1_000_000, Some(2_000), None, None));
+        assert_eq!(range.current(None), Some(2_000));
+        assert!(range.on_success(2_000, 10, FAST, None));
+        assert_eq!(range.current(None), Some(4_000));
+
+        // the last window before the head is cut short so it does not grow the range
+        assert!(!range.on_success(100, 10, FAST, None));
+        assert_eq!(range.current(None), Some(4_000));
+
+        // very large responses shrink towards the target logs
+        assert!(range.on_success(4_000, TARGET_LOGS_PER_REQUEST * 4, FAST, None));
+        assert_eq!(range.current(None), Some(1_000));
+
+        // slow responses and timeouts halve the range
+        assert!(range.on_success(1_000, 10, SLOW_RESPONSE * 2, None));
+        assert_eq!(range.current(None), Some(500));
+        assert!(range.on_error(500, None, None, None));
+        assert_eq!(range.current(None), Some(250));
+
+        // a stated provider limit and the configured max block range cap the growth
+        assert!(range.on_error(250, Some(200), Some(300), None));
+        assert!(range.on_success(200, 10, FAST, None));
+        assert!(!range.on_success(300, 10, FAST, None));
+        assert_eq!(range.current(None), Some(300));
+        assert_eq!(range.current(Some(100)), Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_provider_change_forgets_the_provider_limit() {
+        let project = tempfile::tempdir().unwrap();
+        let network = "block-range-provider-change";
+
+        let block_range = AdaptiveBlockRange::new(project.path(), network, "http://a", None);
+        block_range.on_error(0.into(), 10_000.into(), Some(500.into()), Some(1_000.into()));
+        assert_eq!(block_range.current(), Some(500.into()));
+
+        let block_range = AdaptiveBlockRange::new(project.path(), network, "http://a", None);
+        assert_eq!(block_range.current(), Some(500.into()));
+
+        let block_range = AdaptiveBlockRange::new(project.path(), network, "http://b", None);
+        assert_eq!(block_range.current(), None);
+    }
+}
@@ -1,25 +1,44 @@
-use std::{error::Error, str::FromStr, sync::Arc};
+use std::{error::Error, str::FromStr, sync::Arc, time::Instant};
 
 use ethers::{
-    addressbook::Address,
     middleware::MiddlewareError,
-    prelude::{BlockNumber, JsonRpcError, Log, ValueOrArray, H256, U64},
+    prelude::{BlockNumber, JsonRpcError, Log, U64},
+    providers::ProviderError,
 };
+use futures::future::join_all;
 use regex::Regex;
-use tokio::sync::{mpsc, Semaphore};
-use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{debug, error, info, warn};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{debug, error, info, warn, Instrument, Span};
 
 use crate::{
     event::{config::EventProcessingConfig, RindexerEventFilter},
-    indexer::{log_helpers::is_relevant_block, IndexingEventProgressStatus},
-    provider::JsonRpcCachedProvider,
+    indexer::{
+        block_range::{AdaptiveBlockRange, GET_LOGS_TIMEOUT},
+        historic_source::{
+            historic_source_files, historic_source_logs, read_blocking, HistoricSourceError,
+        },
+        log_helpers::{is_relevant_block, AddressBlooms},
+        reorg::finality_head,
+        IndexingEventProgressStatus,
+    },
+    manifest::contract::HistoricSource,
+    metrics::{record_fetch_backpressure, record_get_logs_retry_block_range},
+    provider::RequestPermit,
+    shutdown::is_shutting_down,
 };
 
+/// Providers limit how big a filter can be so longer address lists are requested in chunks
+const MAX_ADDRESSES_PER_FILTER: usize = 1_000;
+
 pub struct FetchLogsResult {
     pub logs: Vec<Log>,
     pub from_block: U64,
     pub to_block: U64,
+    /// true when the logs were fetched by live indexing instead of the historic sync
+    pub live: bool,
+    /// The span the logs are processed under
+    pub span: Span,
 }
 
 pub fn fetch_logs_stream(
@@ -27,79 +46,46 @@ pub fn fetch_logs_stream(
     force_no_live_indexing: bool,
 ) -> impl tokio_stream::Stream<Item = Result<FetchLogsResult, Box<dyn Error + Send>>> + Send + Unpin
 {
-    let (tx, rx) = mpsc::unbounded_channel();
+    let (tx, rx) = mpsc::channel(config.log_buffer_size.max(1));
 
     let initial_filter = config.to_event_filter().unwrap();
-    let contract_address = initial_filter.contract_address();
+    let address_blooms = AddressBlooms::new(&initial_filter.raw_filter().address);
+    let indexing_span = config.indexing_span();
 
     tokio::spawn(async move {
+        let initial_filter = match &config.network_contract.historic_source {
+            Some(source) => match fetch_historic_source(&config, &tx, initial_filter, source).await
+            {
+                Some(filter) => filter,
+                None => return,
+            },
+            None => initial_filter,
+        };
+
         let snapshot_to_block = initial_filter.get_to_block();
         let from_block = initial_filter.get_from_block();
-        let mut current_filter = initial_filter;
-
-        // add any max block range limitation before we start processing
-        let mut max_block_range_limitation =
-            config.network_contract.cached_provider.max_block_range;
-        if max_block_range_limitation.is_some() {
-            current_filter = current_filter.set_to_block(calculate_process_historic_log_to_block(
-                &from_block,
-                &snapshot_to_block,
-                &max_block_range_limitation,
-            ));
+
+        if let Some(max_block_range) = config.network_contract.cached_provider.max_block_range {
             warn!(
                 "{} - {} - max block range limitation of {} blocks applied - block range indexing will be slower then RPC providers supplying the optimal ranges - https://rindexer.xyz/docs/references/rpc-node-providers#rpc-node-providers",
                 config.info_log_name,
                 IndexingEventProgressStatus::Syncing.log(),
-                max_block_range_limitation.unwrap()
+                max_block_range
             );
         }
-        while current_filter.get_from_block() <= snapshot_to_block {
-            let semaphore_client = Arc::clone(&config.semaphore);
-            let permit = semaphore_client.acquire_owned().await;
-
-            match permit {
-                Ok(permit) => {
-                    let result = fetch_historic_logs_stream(
-                        &config.network_contract.cached_provider,
-                        &tx,
-                        &config.topic_id,
-                        current_filter.clone(),
-                        max_block_range_limitation,
-                        snapshot_to_block,
-                        &config.info_log_name,
-                    )
-                    .await;
-
-                    drop(permit);
-
-                    // slow indexing warn user
-                    if let Some(range) = max_block_range_limitation {
-                        warn!(
-                            "{} - RPC PROVIDER IS SLOW - Slow indexing mode enabled, max block range limitation: {} blocks - we advise using a faster provider who can predict the next block ranges.",
-                            &config.info_log_name,
-                            range
-                        );
-                    }
 
-                    if let Some(result) = result {
-                        current_filter = result.next;
-                        max_block_range_limitation = result.max_block_range_limitation;
-                    } else {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    error!(
-                        "{} - {} - Semaphore error: {}",
-                        &config.info_log_name,
-                        IndexingEventProgressStatus::Syncing.log(),
-                        e
-                    );
-                    continue;
-                }
-            }
+        let segments = split_block_range(from_block, snapshot_to_block, config.historic_segments);
+        let current_filter = if segments.len() > 1 {
+            fetch_historic_segments(&config, &tx, initial_filter, segments).await
+        } else {
+            fetch_historic_range(&config, &tx, initial_filter).await
+        };
+
+        if is_shutting_down() {
+            return;
         }
 
+        Span::current().record("status", IndexingEventProgressStatus::Completed.as_str());
         info!(
             "{} - {} - Finished indexing historic events",
             &config.info_log_name,
@@ -108,38 +94,302 @@ pub fn fetch_logs_stream(
 
         // Live indexing mode
         if config.live_indexing && !force_no_live_indexing {
-            live_indexing_stream(
-                &config.network_contract.cached_provider,
-                &tx,
-                &contract_address,
-                &config.topic_id,
-                &config.indexing_distance_from_head,
-                current_filter,
-                &config.info_log_name,
-                &config.semaphore,
-                config.network_contract.disable_logs_bloom_checks,
-            )
-            .await;
+            Span::current().record("status", IndexingEventProgressStatus::Live.as_str());
+            live_indexing_stream(&config, &tx, &address_blooms, current_filter).await;
         }
-    });
+    }.instrument(indexing_span));
+
+    ReceiverStream::new(rx)
+}
 
-    UnboundedReceiverStream::new(rx)
+/// Fetches the logs of the filter under the request slot the caller holds, long address lists are
+/// split into several requests which run one after another under that same slot, waiting on
+/// another slot while holding one could deadlock once every slot is held by a fetch
+pub(crate) async fn get_logs_in_address_chunks(
+    config: &EventProcessingConfig,
+    filter: &RindexerEventFilter,
+) -> Result<Vec<Log>, ProviderError> {
+    let cached_provider = &config.network_contract.cached_provider;
+    let chunks = filter.split_addresses(MAX_ADDRESSES_PER_FILTER);
+    if chunks.len() == 1 {
+        return cached_provider.get_logs(filter).await;
+    }
+
+    debug!(
+        "{} - Fetching the logs in {} requests of up to {} addresses",
+        config.info_log_name,
+        chunks.len(),
+        MAX_ADDRESSES_PER_FILTER
+    );
+    let mut logs = vec![];
+    for chunk in &chunks {
+        logs.extend(cached_provider.get_logs(chunk).await?);
+    }
+    logs.sort_by_key(|log| (log.block_number, log.log_index));
+    Ok(logs)
+}
+
+/// Splits the blocks into segments of about the same size, never more segments than blocks
+fn split_block_range(from_block: U64, to_block: U64, segments: u64) -> Vec<(U64, U64)> {
+    if from_block > to_block {
+        return vec![(from_block, to_block)];
+    }
+
+    let blocks = (to_block - from_block).as_u64() + 1;
+    let segments = segments.clamp(1, blocks);
+    let segment_size = blocks / segments;
+
+    (0..segments)
+        .map(|segment| {
+            let start = from_block + segment * segment_size;
+            let end = if segment == segments - 1 { to_block } else { start + segment_size - 1 };
+            (start, end)
+        })
+        .collect()
+}
+
+/// Sends the logs to the consumer, waiting while its buffer is full so fetching does not run ahead
+/// of the callbacks, returns false once the consumer has stopped
+async fn send_logs(
+    config: &EventProcessingConfig,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    result: Result<FetchLogsResult, Box<dyn Error + Send>>,
+) -> bool {
+    let result = match tx.try_send(result) {
+        Ok(()) => return true,
+        Err(TrySendError::Closed(_)) => return false,
+        Err(TrySendError::Full(result)) => result,
+    };
+
+    let started = Instant::now();
+    let sent = tx.send(result).await.is_ok();
+    let waited = started.elapsed();
+    record_fetch_backpressure(
+        &config.network_contract.network,
+        &config.contract_name,
+        &config.event_name,
+        waited,
+    );
+    debug!(
+        "{} - Waited {:?} for the fetched logs to be processed before fetching more",
+        config.info_log_name, waited
+    );
+    sent
+}
+
+/// Reads the logs of the historic source files up to its end block, one batch per file, returns the
+/// filter RPC carries on from or None when the source could not be read
+async fn fetch_historic_source(
+    config: &Arc<EventProcessingConfig>,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    filter: RindexerEventFilter,
+    source: &HistoricSource,
+) -> Option<RindexerEventFilter> {
+    let first_block = filter.get_from_block();
+    let end_block = source.end_block.min(filter.get_to_block());
+    if first_block > end_block {
+        return Some(filter);
+    }
+
+    info!(
+        "{} - {} - Reading historic events from {} up to block {}",
+        config.info_log_name,
+        IndexingEventProgressStatus::Syncing.log(),
+        source.path,
+        end_block
+    );
+
+    let path = config.project_path.join(&source.path);
+    let mut from_block = first_block;
+    let result: Result<bool, HistoricSourceError> = async {
+        let source_path = path.clone();
+        let files = read_blocking(&path, move || historic_source_files(&source_path)).await?;
+        for file in files {
+            if is_shutting_down() {
+                return Ok(false);
+            }
+
+            let raw_filter = filter.raw_filter().clone();
+            let path = file.clone();
+            let logs = read_blocking(&file, move || {
+                historic_source_logs(&path, &raw_filter, first_block, from_block, end_block)
+            })
+            .await?;
+
+            let Some(to_block) = logs.last().and_then(|log| log.block_number) else {
+                continue;
+            };
+            debug!(
+                "{} - {} - Read {} event logs from {} - blocks: {} - {}",
+                config.info_log_name,
+                IndexingEventProgressStatus::Syncing.log(),
+                logs.len(),
+                file.display(),
+                from_block,
+                to_block
+            );
+            let span = config.logs_span(from_block, to_block, false);
+            let result = FetchLogsResult { logs, from_block, to_block, live: false, span };
+            if !send_logs(config, tx, Ok(result)).await {
+                return Ok(false);
+            }
+            from_block = to_block + 1;
+        }
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => {
+            error!("{} - Could not read the historic source: {}", config.info_log_name, e);
+            send_logs(config, tx, Err(Box::new(e))).await;
+            return None;
+        }
+    }
+
+    // the source has every log up to its end block so the blocks after the last log are synced too
+    if from_block <= end_block {
+        let span = config.logs_span(from_block, end_block, false);
+        let result =
+            FetchLogsResult { logs: vec![], from_block, to_block: end_block, live: false, span };
+        if !send_logs(config, tx, Ok(result)).await {
+            return None;
+        }
+    }
+
+    Some(filter.set_from_block(end_block + 1))
+}
+
+/// Fetches the historic segments concurrently under the shared request limits, when the event is
+/// indexed in order the logs of later segments are held back until the earlier segments are done
+async fn fetch_historic_segments(
+    config: &Arc<EventProcessingConfig>,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    initial_filter: RindexerEventFilter,
+    segments: Vec<(U64, U64)>,
+) -> RindexerEventFilter {
+    info!(
+        "{} - {} - Fetching historic events in {} segments",
+        config.info_log_name,
+        IndexingEventProgressStatus::Syncing.log(),
+        segments.len()
+    );
+
+    let mut fetches = vec![];
+    let mut receivers = vec![];
+    for (from_block, to_block) in segments {
+        let segment_tx = if config.index_event_in_order {
+            let (segment_tx, segment_rx) = mpsc::channel(config.log_buffer_size.max(1));
+            receivers.push(segment_rx);
+            segment_tx
+        } else {
+            tx.clone()
+        };
+        let filter = initial_filter.clone().set_from_block(from_block).set_to_block(to_block);
+        fetches.push(async move { fetch_historic_range(config, &segment_tx, filter).await });
+    }
+
+    let forward_in_order = async {
+        for mut receiver in receivers {
+            while let Some(result) = receiver.recv().await {
+                if tx.send(result).await.is_err() {
+                    return;
+                }
+            }
+        }
+    };
+
+    let (filters, _) = tokio::join!(join_all(fetches), forward_in_order);
+    filters.into_iter().last().unwrap_or(initial_filter)
+}
+
+/// Walks the filter block range fetching the logs, returns the last filter used
+async fn fetch_historic_range(
+    config: &Arc<EventProcessingConfig>,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    mut current_filter: RindexerEventFilter,
+) -> RindexerEventFilter {
+    let snapshot_to_block = current_filter.get_to_block();
+    let from_block = current_filter.get_from_block();
+
+    // apply the learned or configured block range before we start processing
+    let block_range = AdaptiveBlockRange::new(
+        &config.project_path,
+        &config.network_contract.network,
+        config.network_contract.cached_provider.rpc_url(),
+        config.network_contract.cached_provider.max_block_range,
+    );
+    if block_range.current().is_some() {
+        current_filter = current_filter.set_to_block(calculate_process_historic_log_to_block(
+            &from_block,
+            &snapshot_to_block,
+            &block_range.current(),
+        ));
+    }
+
+    // no new block ranges are fetched once rindexer is shutting down
+    while current_filter.get_from_block() <= snapshot_to_block && !is_shutting_down() {
+        let permit = config.request_concurrency.acquire().await;
+
+        match permit {
+            Ok(permit) => {
+                let span = config.logs_span(
+                    current_filter.get_from_block(),
+                    current_filter.get_to_block(),
+                    false,
+                );
+                let result = fetch_historic_logs_stream(
+                    config,
+                    tx,
+                    permit,
+                    current_filter.clone(),
+                    &block_range,
+                    snapshot_to_block,
+                    span,
+                )
+                .await;
+
+                if let Some(result) = result {
+                    current_filter = result.next;
+                } else {
+                    break;
+                }
+            }
+            Err(e) => {
+                error!(
+                    "{} - {} - Semaphore error: {}",
+                    &config.info_log_name,
+                    IndexingEventProgressStatus::Syncing.log(),
+                    e
+                );
+                continue;
+            }
+        }
+    }
+
+    current_filter
 }
 
 struct ProcessHistoricLogsStreamResult {
     pub next: RindexerEventFilter,
-    pub max_block_range_limitation: Option<U64>,
 }
 
+/// Fetches the logs of the filter block range, the permit is given back once the request is done
+/// so waiting on a full buffer does not hold up the other events
 async fn fetch_historic_logs_stream(
-    cached_provider: &Arc<JsonRpcCachedProvider>,
-    tx: &mpsc::UnboundedSender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
-    topic_id: &H256,
+    config: &EventProcessingConfig,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    permit: RequestPermit,
     current_filter: RindexerEventFilter,
-    max_block_range_limitation: Option<U64>,
+    block_range: &AdaptiveBlockRange,
     snapshot_to_block: U64,
-    info_log_name: &str,
+    span: Span,
 ) -> Option<ProcessHistoricLogsStreamResult> {
+    let cached_provider = &config.network_contract.cached_provider;
+    let topic_id = &config.topic_id;
+    let info_log_name = &config.info_log_name;
     let from_block = current_filter.get_from_block();
     let to_block = current_filter.get_to_block();
     debug!(
@@ -161,7 +411,6 @@ async fn fetch_historic_logs_stream(
 
         return Some(ProcessHistoricLogsStreamResult {
             next: current_filter.set_from_block(to_block),
-            max_block_range_limitation,
         });
     }
 
@@ -172,8 +421,41 @@ async fn fetch_historic_logs_stream(
         current_filter
     );
 
-    match cached_provider.get_logs(&current_filter).await {
+    let started = Instant::now();
+    let logs = match tokio::time::timeout(
+        GET_LOGS_TIMEOUT,
+        get_logs_in_address_chunks(config, &current_filter),
+    )
+    .instrument(span.clone())
+    .await
+    {
+        Ok(logs) => logs,
+        Err(_) => {
+            warn!(
+                "{} - {} - eth_getLogs timed out after {:?} - blocks: {} - {} - retrying with a smaller block range",
+                info_log_name,
+                IndexingEventProgressStatus::Syncing.log(),
+                GET_LOGS_TIMEOUT,
+                from_block,
+                to_block
+            );
+            block_range.on_error(from_block, to_block, None, None);
+            return Some(ProcessHistoricLogsStreamResult {
+                next: current_filter.set_to_block(calculate_process_historic_log_to_block(
+                    &from_block,
+                    &snapshot_to_block,
+                    &block_range.current(),
+                )),
+            });
+        }
+    };
+
+    drop(permit);
+
+    match logs {
         Ok(logs) => {
+            block_range.on_success(from_block, to_block, logs.len(), started.elapsed());
+
             debug!(
                 "{} - {} - topic_id {}, Logs: {} from {} to {}",
                 info_log_name,
@@ -197,12 +479,21 @@ async fn fetch_historic_logs_stream(
             // clone here over the full logs way less overhead
             let last_log = logs.last().cloned();
 
-            if tx.send(Ok(FetchLogsResult { logs, from_block, to_block })).is_err() {
-                error!(
-                    "{} - {} - Failed to send logs to stream consumer!",
-                    IndexingEventProgressStatus::Syncing.log(),
-                    info_log_name
-                );
+            if !send_logs(
+                config,
+                tx,
+                Ok(FetchLogsResult { logs, from_block, to_block, live: false, span }),
+            )
+            .await
+            {
+                // the consumer stops taking logs once rindexer is shutting down
+                if !is_shutting_down() {
+                    error!(
+                        "{} - {} - Failed to send logs to stream consumer!",
+                        IndexingEventProgressStatus::Syncing.log(),
+                        info_log_name
+                    );
+                }
                 return None;
             }
 
@@ -218,7 +509,7 @@ async fn fetch_historic_logs_stream(
                     let new_to_block = calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.current(),
                     );
 
                     debug!(
@@ -233,7 +524,6 @@ async fn fetch_historic_logs_stream(
                         next: current_filter
                             .set_from_block(next_from_block)
                             .set_to_block(new_to_block),
-                        max_block_range_limitation,
                     })
                 };
             }
@@ -255,7 +545,7 @@ async fn fetch_historic_logs_stream(
                     let new_to_block = calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.current(),
                     );
 
                     debug!(
@@ -270,7 +560,6 @@ async fn fetch_historic_logs_stream(
                         next: current_filter
                             .set_from_block(next_from_block)
                             .set_to_block(new_to_block),
-                        max_block_range_limitation,
                     })
                 };
             }
@@ -286,11 +575,27 @@ async fn fetch_historic_logs_stream(
                         IndexingEventProgressStatus::Syncing.log(),
                         retry_result
                     );
+                    let suggested_range =
+                        match (retry_result.from.as_number(), retry_result.to.as_number()) {
+                            (Some(from), Some(to)) => Some(to.saturating_sub(from)),
+                            _ => None,
+                        };
+                    if let Some(suggested_range) = suggested_range {
+                        record_get_logs_retry_block_range(
+                            &cached_provider.network,
+                            suggested_range.as_u64(),
+                        );
+                    }
+                    block_range.on_error(
+                        from_block,
+                        to_block,
+                        suggested_range,
+                        retry_result.max_block_range,
+                    );
                     return Some(ProcessHistoricLogsStreamResult {
                         next: current_filter
                             .set_from_block(retry_result.from)
                             .set_to_block(retry_result.to),
-                        max_block_range_limitation: retry_result.max_block_range,
                     });
                 }
             }
@@ -302,7 +607,7 @@ async fn fetch_historic_logs_stream(
                 err
             );
 
-            let _ = tx.send(Err(Box::new(err)));
+            send_logs(config, tx, Err(Box::new(err))).await;
             return None;
         }
     }
@@ -312,22 +617,25 @@ async fn fetch_historic_logs_stream(
 
 /// Handles live indexing mode, continuously checking for new blocks, ensuring they are
 /// within a safe range, updating the filter, and sending the logs to the provided channel.
-#[allow(clippy::too_many_arguments)]
 async fn live_indexing_stream(
-    cached_provider: &Arc<JsonRpcCachedProvider>,
-    tx: &mpsc::UnboundedSender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
-    contract_address: &Option<ValueOrArray<Address>>,
-    topic_id: &H256,
-    reorg_safe_distance: &U64,
+    config: &EventProcessingConfig,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    address_blooms: &AddressBlooms,
     mut current_filter: RindexerEventFilter,
-    info_log_name: &str,
-    semaphore: &Arc<Semaphore>,
-    disable_logs_bloom_checks: bool,
 ) {
+    let cached_provider = &config.network_contract.cached_provider;
+    let topic_ids = config.topic_ids();
+    let info_log_name = &config.info_log_name;
+    let disable_logs_bloom_checks = config.network_contract.disable_logs_bloom_checks;
     let mut last_seen_block_number = U64::from(0);
     loop {
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
 
+        // the consumer stops when the event stops being indexed
+        if tx.is_closed() || is_shutting_down() {
+            break;
+        }
+
         let latest_block = cached_provider.get_latest_block().await;
         match latest_block {
             Ok(latest_block) => {
@@ -349,24 +657,47 @@ async fn live_indexing_stream(
                             last_seen_block_number
                         );
 
-                        let safe_block_number = latest_block_number - reorg_safe_distance;
+                        let safe_block_number = match finality_head(
+                            cached_provider,
+                            config.finality,
+                            latest_block_number,
+                        )
+                        .await
+                        {
+                            Ok(safe_block_number) => safe_block_number,
+                            Err(e) => {
+                                error!(
+                                    "{} - {} - Error getting the {}: {}",
+                                    info_log_name,
+                                    IndexingEventProgressStatus::Live.log(),
+                                    config.finality.describe(),
+                                    e
+                                );
+                                continue;
+                            }
+                        };
                         let from_block = current_filter.get_from_block();
                         // check reorg distance and skip if not safe
                         if from_block > safe_block_number {
                             info!(
-                                "{} - {} - not in safe reorg block range yet block: {} > range: {}",
+                                "{} - {} - not in safe reorg block range yet block: {} > range: {} - following the {}",
                                 info_log_name,
                                 IndexingEventProgressStatus::Live.log(),
                                 from_block,
-                                safe_block_number
+                                safe_block_number,
+                                config.finality.describe()
                             );
                             continue;
                         }
 
                         let to_block = safe_block_number;
+                        // the logs bloom is only of the latest block
                         if from_block == to_block &&
+                            to_block == latest_block_number &&
                             !disable_logs_bloom_checks &&
-                            !is_relevant_block(contract_address, topic_id, &latest_block)
+                            !topic_ids.iter().any(|topic_id| {
+                                is_relevant_block(address_blooms, topic_id, &latest_block)
+                            })
                         {
                             debug!(
                                 "{} - {} - Skipping block {} as it's not relevant",
@@ -394,17 +725,20 @@ async fn live_indexing_stream(
                             current_filter
                         );
 
-                        let semaphore_client = Arc::clone(semaphore);
-                        let permit = semaphore_client.acquire_owned().await;
+                        let permit = config.request_concurrency.acquire().await;
 
                         if let Ok(permit) = permit {
-                            match cached_provider.get_logs(&current_filter).await {
+                            let span = config.logs_span(from_block, to_block, true);
+                            match get_logs_in_address_chunks(config, &current_filter)
+                                .instrument(span.clone())
+                                .await
+                            {
                                 Ok(logs) => {
                                     debug!(
-                                        "{} - {} - Live topic_id {}, Logs: {} from {} to {}",
+                                        "{} - {} - Live topic_ids {:?}, Logs: {} from {} to {}",
                                         info_log_name,
                                         IndexingEventProgressStatus::Live.log(),
-                                        topic_id,
+                                        topic_ids,
                                         logs.len(),
                                         from_block,
                                         to_block
@@ -420,21 +754,32 @@ async fn live_indexing_stream(
                                     );
 
                                     last_seen_block_number = to_block;
+                                    drop(permit);
 
                                     let logs_empty = logs.is_empty();
                                     // clone here over the full logs way less overhead
                                     let last_log = logs.last().cloned();
 
-                                    if tx
-                                        .send(Ok(FetchLogsResult { logs, from_block, to_block }))
-                                        .is_err()
+                                    if !send_logs(
+                                        config,
+                                        tx,
+                                        Ok(FetchLogsResult {
+                                            logs,
+                                            from_block,
+                                            to_block,
+                                            live: true,
+                                            span,
+                                        }),
+                                    )
+                                    .await
                                     {
-                                        error!(
-                                            "{} - {} - Failed to send logs to stream consumer!",
-                                            info_log_name,
-                                            IndexingEventProgressStatus::Live.log()
-                                        );
-                                        drop(permit);
+                                        if !is_shutting_down() {
+                                            error!(
+                                                "{} - {} - Failed to send logs to stream consumer!",
+                                                info_log_name,
+                                                IndexingEventProgressStatus::Live.log()
+                                            );
+                                        }
                                         break;
                                     }
 
@@ -457,8 +802,6 @@ async fn live_indexing_stream(
                                             error!("Failed to get last log block number the provider returned null (should never happen) - try again in 200ms");
                                         }
                                     }
-
-                                    drop(permit);
                                 }
                                 Err(err) => {
                                     error!(
@@ -598,7 +941,7 @@ fn retry_with_block_range(
         return Some(RetryWithBlockRangeResult {
             from: BlockNumber::from(from_block),
             to: BlockNumber::from(from_block + fallback_range),
-            max_block_range: Some(fallback_range),
+            max_block_range: None,
         });
     }
 
@@ -621,3 +964,25 @@ fn calculate_process_historic_log_to_block(
         *snapshot_to_block
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_block_range() {
+        let segments: Vec<(u64, u64)> = split_block_range(U64::from(100), U64::from(199), 4)
+            .into_iter()
+            .map(|(from, to)| (from.as_u64(), to.as_u64()))
+            .collect();
+        assert_eq!(segments, vec![(100, 124), (125, 149), (150, 174), (175, 199)]);
+
+        // the last segment takes the remainder and there are never more segments than blocks
+        assert_eq!(
+            split_block_range(U64::from(0), U64::from(10), 3).last(),
+            Some(&(U64::from(6), U64::from(10)))
+        );
+        assert_eq!(split_block_range(U64::from(5), U64::from(6), 8).len(), 2);
+        assert_eq!(split_block_range(U64::from(5), U64::from(6), 0).len(), 1);
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use ethers::{
+    prelude::{Address, Bytes, Filter, Log, ValueOrArray, H256, U256, U64},
+    utils::hex,
+};
+use parquet::{
+    errors::ParquetError,
+    file::reader::{FileReader, SerializedFileReader},
+    record::Field,
+};
+use serde_json::Value;
+
+/// The file extensions read from a historic source directory
+const SOURCE_EXTENSIONS: [&str; 4] = ["parquet", "jsonl", "ndjson", "json"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum HistoricSourceError {
+    #[error("Could not read {0}: {1}")]
+    Io(PathBuf, io::Error),
+
+    #[error("Could not read the parquet file {0}: {1}")]
+    Parquet(PathBuf, ParquetError),
+
+    #[error("Could not parse line {1} of {0}: {2}")]
+    Json(PathBuf, usize, String),
+
+    #[error("{0} has a log without the {1} column")]
+    MissingColumn(PathBuf, &'static str),
+
+    #[error("{0} has a log without a valid {1} column")]
+    InvalidColumn(PathBuf, &'static str),
+
+    #[error("{0} is not a .parquet, .jsonl, .ndjson or .json file")]
+    UnsupportedFile(PathBuf),
+
+    #[error("{0} has logs from block {1} which an earlier file already covered, the files must be in block order without overlaps")]
+    NotInBlockOrder(PathBuf, U64),
+
+    #[error("Reading {0} did not finish: {1}")]
+    ReadTask(PathBuf, String),
+}
+
+/// Runs the read of `path` on the blocking pool, a read that panics or is cancelled is an error
+/// instead of taking the fetch task down with it
+pub async fn read_blocking<T, F>(path: &Path, read: F) -> Result<T, HistoricSourceError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, HistoricSourceError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(read)
+        .await
+        .map_err(|e| HistoricSourceError::ReadTask(path.into(), e.to_string()))?
+}
+
+/// The files of the source in the order they are read, a directory is read in file name order
+pub fn historic_source_files(path: &Path) -> Result<Vec<PathBuf>, HistoricSourceError> {
+    let metadata = std::fs::metadata(path).map_err(|e| HistoricSourceError::Io(path.into(), e))?;
+    if metadata.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = vec![];
+    for entry in std::fs::read_dir(path).map_err(|e| HistoricSourceError::Io(path.into(), e))? {
+        let entry = entry.map_err(|e| HistoricSourceError::Io(path.into(), e))?;
+        let file = entry.path();
+        let supported = file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension));
+        if supported && file.is_file() {
+            files.push(file);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Reads every log of the file, a parquet file or JSON logs in the `eth_getLogs` or cryo format
+pub fn read_historic_source_file(path: &Path) -> Result<Vec<Log>, HistoricSourceError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("parquet") => read_parquet_logs(path),
+        Some("jsonl" | "ndjson" | "json") => read_json_logs(path),
+        _ => Err(HistoricSourceError::UnsupportedFile(path.into())),
+    }
+}
+
+/// A column of an exported log, binary columns can also be hex strings
+enum ColumnValue {
+    Bytes(Vec<u8>),
+    Number(u64),
+    Null,
+}
+
+impl ColumnValue {
+    fn from_parquet(field: &Field) -> Option<Self> {
+        let value = match field {
+            Field::Null => ColumnValue::Null,
+            Field::Bytes(bytes) => ColumnValue::Bytes(bytes.data().to_vec()),
+            Field::Str(value) => ColumnValue::from_hex(value)?,
+            Field::UByte(value) => ColumnValue::Number(*value as u64),
+            Field::UShort(value) => ColumnValue::Number(*value as u64),
+            Field::UInt(value) => ColumnValue::Number(*value as u64),
+            Field::ULong(value) => ColumnValue::Number(*value),
+            Field::Byte(value) => ColumnValue::Number(u64::try_from(*value).ok()?),
+            Field::Short(value) => ColumnValue::Number(u64::try_from(*value).ok()?),
+            Field::Int(value) => ColumnValue::Number(u64::try_from(*value).ok()?),
+            Field::Long(value) => ColumnValue::Number(u64::try_from(*value).ok()?),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(ColumnValue::Null),
+            Value::Number(number) => number.as_u64().map(ColumnValue::Number),
+            Value::String(value) => ColumnValue::from_hex(value),
+            _ => None,
+        }
+    }
+
+    fn from_hex(value: &str) -> Option<Self> {
+        match value.strip_prefix("0x") {
+            // quantities like `0x1` leave out the leading zero
+            Some(value) if value.len() % 2 == 1 => {
+                hex::decode(format!("0{}", value)).ok().map(ColumnValue::Bytes)
+            }
+            Some(value) => hex::decode(value).ok().map(ColumnValue::Bytes),
+            None => value.parse().ok().map(ColumnValue::Number),
+        }
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            ColumnValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn number(&self) -> Option<u64> {
+        match self {
+            ColumnValue::Number(number) => Some(*number),
+            // hex quantities like `0x1b4`
+            ColumnValue::Bytes(bytes) if bytes.len() <= 8 => {
+                Some(bytes.iter().fold(0, |number, byte| (number << 8) | *byte as u64))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Builds a log from the columns cryo exports for logs
+#[derive(Default)]
+struct LogColumns {
+    address: Option<Address>,
+    topics: [Option<H256>; 4],
+    data: Option<Bytes>,
+    block_hash: Option<H256>,
+    block_number: Option<u64>,
+    transaction_hash: Option<H256>,
+    transaction_index: Option<u64>,
+    log_index: Option<u64>,
+}
+
+impl LogColumns {
+    fn set(
+        &mut self,
+        path: &Path,
+        name: &str,
+        value: ColumnValue,
+    ) -> Result<(), HistoricSourceError> {
+        fn hash(
+            path: &Path,
+            column: &'static str,
+            value: &ColumnValue,
+        ) -> Result<Option<H256>, HistoricSourceError> {
+            match value {
+                ColumnValue::Null => Ok(None),
+                value => value
+                    .bytes()
+                    .filter(|bytes| bytes.len() == 32)
+                    .map(|bytes| Some(H256::from_slice(bytes)))
+                    .ok_or(HistoricSourceError::InvalidColumn(path.into(), column)),
+            }
+        }
+
+        fn number(
+            path: &Path,
+            column: &'static str,
+            value: &ColumnValue,
+        ) -> Result<Option<u64>, HistoricSourceError> {
+            match value {
+                ColumnValue::Null => Ok(None),
+                value => value
+                    .number()
+                    .map(Some)
+                    .ok_or(HistoricSourceError::InvalidColumn(path.into(), column)),
+            }
+        }
+
+        match name {
+            "address" | "contract_address" => {
+                self.address = match value.bytes() {
+                    Some(bytes) if bytes.len() == 20 => Some(Address::from_slice(bytes)),
+                    _ => return Err(HistoricSourceError::InvalidColumn(path.into(), "address")),
+                }
+            }
+            "topic0" => self.topics[0] = hash(path, "topic0", &value)?,
+            "topic1" => self.topics[1] = hash(path, "topic1", &value)?,
+            "topic2" => self.topics[2] = hash(path, "topic2", &value)?,
+            "topic3" => self.topics[3] = hash(path, "topic3", &value)?,
+            "data" => self.data = value.bytes().map(|bytes| Bytes::from(bytes.to_vec())),
+            "block_hash" => self.block_hash = hash(path, "block_hash", &value)?,
+            "transaction_hash" => self.transaction_hash = hash(path, "transaction_hash", &value)?,
+            "block_number" => self.block_number = number(path, "block_number", &value)?,
+            "transaction_index" => {
+                self.transaction_index = number(path, "transaction_index", &value)?
+            }
+            "log_index" => self.log_index = number(path, "log_index", &value)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn into_log(self, path: &Path) -> Result<Log, HistoricSourceError> {
+        let missing = |column| HistoricSourceError::MissingColumn(path.into(), column);
+        Ok(Log {
+            address: self.address.ok_or_else(|| missing("address"))?,
+            // cryo leaves the unused topics empty, the topics of a log never have gaps
+            topics: self.topics.into_iter().map_while(|topic| topic).collect(),
+            data: self.data.unwrap_or_default(),
+            block_hash: self.block_hash,
+            block_number: Some(self.block_number.ok_or_else(|| missing("block_number"))?.into()),
+            transaction_hash: self.transaction_hash,
+            transaction_index: self.transaction_index.map(U64::from),
+            log_index: Some(self.log_index.ok_or_else(|| missing("log_index"))?.into()),
+            removed: Some(false),
+            ..Default::default()
+        })
+    }
+}
+
+fn read_parquet_logs(path: &Path) -> Result<Vec<Log>, HistoricSourceError> {
+    let parquet_error = |e| HistoricSourceError::Parquet(path.into(), e);
+    let file = File::open(path).map_err(|e| HistoricSourceError::Io(path.into(), e))?;
+    let reader = SerializedFileReader::new(file).map_err(parquet_error)?;
+
+    let mut logs = vec![];
+    for row in reader.get_row_iter(None).map_err(parquet_error)? {
+        let mut columns = LogColumns::default();
+        for (name, field) in row.map_err(parquet_error)?.get_column_iter() {
+            // columns the log does not need can have any type
+            if let Some(value) = ColumnValue::from_parquet(field) {
+                columns.set(path, name, value)?;
+            }
+        }
+        logs.push(columns.into_log(path)?);
+    }
+
+    Ok(logs)
+}
+
+fn json_log(path: &Path, value: Value) -> Result<Log, String> {
+    // logs as `eth_getLogs` returns them
+    if value.get("topics").is_some() {
+        return serde_json::from_value(value).map_err(|e| e.to_string());
+    }
+
+    let Value::Object(object) = value else {
+        return Err("expected an object".to_string());
+    };
+    let mut columns = LogColumns::default();
+    for (name, value) in &object {
+        if let Some(value) = ColumnValue::from_json(value) {
+            columns.set(path, name, value).map_err(|e| e.to_string())?;
+        }
+    }
+    columns.into_log(path).map_err(|e| e.to_string())
+}
+
+/// Reads JSON lines or a JSON array of logs
+fn read_json_logs(path: &Path) -> Result<Vec<Log>, HistoricSourceError> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| HistoricSourceError::Io(path.into(), e))?;
+
+    if content.trim_start().starts_with('[') {
+        let values: Vec<Value> = serde_json::from_str(&content)
+            .map_err(|e| HistoricSourceError::Json(path.into(), e.line(), e.to_string()))?;
+        return values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                json_log(path, value).map_err(|e| HistoricSourceError::Json(path.into(), index, e))
+            })
+            .collect();
+    }
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let value = serde_json::from_str(line)
+                .map_err(|e| HistoricSourceError::Json(path.into(), line_number, e.to_string()))?;
+            json_log(path, value)
+                .map_err(|e| HistoricSourceError::Json(path.into(), line_number, e))
+        })
+        .collect()
+}
+
+/// Whether `eth_getLogs` would have returned the log for the filter, the block range is left to the
+/// caller
+pub fn filter_matches_log(filter: &Filter, log: &Log) -> bool {
+    let address_matches = match &filter.address {
+        None => true,
+        Some(ValueOrArray::Value(address)) => *address == log.address,
+        Some(ValueOrArray::Array(addresses)) => addresses.contains(&log.address),
+    };
+    if !address_matches {
+        return false;
+    }
+
+    filter.topics.iter().enumerate().all(|(index, topic)| {
+        let log_topic = log.topics.get(index);
+        match topic {
+            None | Some(ValueOrArray::Value(None)) => true,
+            Some(ValueOrArray::Value(Some(topic))) => log_topic == Some(topic),
+            Some(ValueOrArray::Array(topics)) => {
+                topics.is_empty() ||
+                    topics.iter().any(|topic| topic.is_none() || topic.as_ref() == log_topic)
+            }
+        }
+    })
+}
+
+/// The logs of the file the filter matches from `from_block` up to `to_block` in block order,
+/// errors when the file has matching logs before `from_block` that an earlier file should have had
+pub fn historic_source_logs(
+    path: &Path,
+    filter: &Filter,
+    first_block: U64,
+    from_block: U64,
+    to_block: U64,
+) -> Result<Vec<Log>, HistoricSourceError> {
+    let mut logs = vec![];
+    for log in read_historic_source_file(path)? {
+        let Some(block_number) = log.block_number else {
+            continue;
+        };
+        if block_number < first_block ||
+            block_number > to_block ||
+            !filter_matches_log(filter, &log)
+        {
+            continue;
+        }
+        if block_number < from_block {
+            return Err(HistoricSourceError::NotInBlockOrder(path.into(), block_number));
+        }
+        logs.push(log);
+    }
+
+    logs.sort_by_key(|log| (log.block_number, log.log_index.unwrap_or(U256::zero())));
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_historic_source_logs() {
+        let directory = tempfile::tempdir().unwrap();
+        let address = Address::repeat_byte(1);
+        let topic = H256::repeat_byte(2);
+
+        // cryo columns with the unused topics empty and a log as `eth_getLogs` returns it
+        let cryo_log = serde_json::json!({
+            "block_number": 12,
+            "transaction_index": 0,
+            "log_index": 3,
+            "transaction_hash": format!("{:?}", H256::repeat_byte(3)),
+            "address": format!("{:?}", address),
+            "topic0": format!("{:?}", topic),
+            "topic1": null,
+            "topic2": null,
+            "topic3": null,
+            "data": "0x01",
+            "chain_id": 1,
+        });
+        let rpc_log = Log {
+            address,
+            topics: vec![topic],
+            block_number: Some(U64::from(10)),
+            log_index: Some(U256::from(1)),
+            ..Default::default()
+        };
+        let other_contract = Log { address: Address::repeat_byte(9), ..rpc_log.clone() };
+        let content = [
+            cryo_log.to_string(),
+            serde_json::to_string(&rpc_log).unwrap(),
+            serde_json::to_string(&other_contract).unwrap(),
+        ]
+        .join("\n");
+        let first_file = directory.path().join("0001.jsonl");
+        std::fs::write(&first_file, content).unwrap();
+        std::fs::write(directory.path().join("0000.json"), "[]").unwrap();
+        std::fs::write(directory.path().join("notes.txt"), "").unwrap();
+
+        let files = historic_source_files(directory.path()).unwrap();
+        assert_eq!(files, vec![directory.path().join("0000.json"), first_file.clone()]);
+
+        let filter = Filter::new().address(address).topic0(topic);
+        let logs = historic_source_logs(&first_file, &filter, 0.into(), 0.into(), 20.into());
+        let logs = logs.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0], rpc_log);
+        assert_eq!(logs[1].block_number, Some(U64::from(12)));
+        assert_eq!(logs[1].topics, vec![topic]);
+        assert_eq!(logs[1].data, Bytes::from(vec![1]));
+
+        // the block range is applied and logs an earlier file covered are an error
+        let logs = historic_source_logs(&first_file, &filter, 11.into(), 11.into(), 20.into());
+        assert_eq!(logs.unwrap().len(), 1);
+        assert!(historic_source_logs(&first_file, &filter, 0.into(), 11.into(), 20.into()).is_err());
+        let logs = historic_source_logs(&first_file, &filter, 0.into(), 0.into(), 11.into());
+        assert_eq!(logs.unwrap(), vec![rpc_log]);
+    }
+
+    #[test]
+    fn test_odd_length_hex_quantities() {
+        let directory = tempfile::tempdir().unwrap();
+        let address = Address::repeat_byte(1);
+        let cryo_log = serde_json::json!({
+            "block_number": "0x1b4",
+            "log_index": "0x1",
+            "transaction_index": "0x0",
+            "address": format!("{:?}", address),
+            "topic0": format!("{:?}", H256::repeat_byte(2)),
+            "data": "0x",
+        });
+        let file = directory.path().join("0000.jsonl");
+        std::fs::write(&file, cryo_log.to_string()).unwrap();
+
+        let logs = read_historic_source_file(&file).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, Some(U64::from(0x1b4)));
+        assert_eq!(logs[0].log_index, Some(U256::from(1)));
+        assert_eq!(logs[0].transaction_index, Some(U64::zero()));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_historic_source_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let filter = Filter::new();
+
+        // a truncated parquet file is an error, as is a read that panics on it
+        let file = directory.path().join("0000.parquet");
+        std::fs::write(&file, b"PAR1\x00\x01").unwrap();
+        let path = file.clone();
+        let logs = read_blocking(&file, move || {
+            historic_source_logs(&path, &filter, 0.into(), 0.into(), 20.into())
+        })
+        .await;
+        assert!(matches!(logs, Err(HistoricSourceError::Parquet(..))));
+
+        let logs: Result<Vec<Log>, _> =
+            read_blocking(&file, || panic!("the parquet reader panicked")).await;
+        assert!(matches!(logs, Err(HistoricSourceError::ReadTask(path, _)) if path == file));
+    }
+}
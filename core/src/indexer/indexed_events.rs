@@ -0,0 +1,33 @@
+use ethers::types::U64;
+use tokio::sync::broadcast;
+
+use crate::lazy_static;
+
+/// Published once the callback for live logs has completed, so any storage write has been done
+#[derive(Debug, Clone)]
+pub struct IndexedEvents {
+    /// The full `schema.table` name the events are stored in
+    pub table_name: String,
+    pub network: String,
+    pub from_block: U64,
+    pub to_block: U64,
+    /// false when the request found no logs, these are still published so listeners know how
+    /// far the network has been indexed
+    pub has_events: bool,
+    /// How many blocks past `to_block` the network has to be indexed before the events are safe
+    /// from reorgs, zero when the indexer already waits for the reorg safe distance
+    pub reorg_safe_distance: U64,
+}
+
+lazy_static! {
+    static ref INDEXED_EVENTS: broadcast::Sender<IndexedEvents> = broadcast::channel(1024).0;
+}
+
+pub fn publish_indexed_events(events: IndexedEvents) {
+    // an error only means nothing is listening
+    let _ = INDEXED_EVENTS.send(events);
+}
+
+pub fn subscribe_indexed_events() -> broadcast::Receiver<IndexedEvents> {
+    INDEXED_EVENTS.subscribe()
+}
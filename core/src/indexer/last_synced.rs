@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::BTreeMap, path::Path, sync::Arc};
 
 use ethers::prelude::U64;
 use rust_decimal::Decimal;
@@ -12,11 +12,13 @@ use tracing::error;
 use crate::{
     event::config::EventProcessingConfig,
     helpers::{camel_to_snake, get_full_path},
-    manifest::{storage::CsvDetails, stream::StreamsConfig},
+    manifest::storage::CsvDetails,
+    shutdown::spawn_in_flight,
+    streams::StreamCheckpoints,
     EthereumSqlTypeWrapper, PostgresClient,
 };
 
-async fn get_last_synced_block_number_file(
+pub(crate) async fn get_last_synced_block_number_file(
     full_path: &Path,
     contract_name: &str,
     network: &str,
@@ -69,7 +71,7 @@ pub struct SyncConfig<'a> {
     pub project_path: &'a Path,
     pub database: &'a Option<Arc<PostgresClient>>,
     pub csv_details: &'a Option<CsvDetails>,
-    pub stream_details: &'a Option<&'a StreamsConfig>,
+    pub stream_checkpoints: &'a Option<Arc<StreamCheckpoints>>,
     pub contract_csv_enabled: bool,
     pub indexer_name: &'a str,
     pub contract_name: &'a str,
@@ -105,38 +107,19 @@ pub async fn get_last_synced_block_number(config: SyncConfig<'_>) -> Option<U64>
         }
     }
 
-    // Then check streams if no csv or database to find out last synced block
-    if config.database.is_none() && !config.contract_csv_enabled && config.stream_details.is_some()
-    {
-        let stream_details = config.stream_details.as_ref().unwrap();
-
-        // create the path if it does not exist
-        stream_details
-            .create_full_streams_last_synced_block_path(config.project_path, config.contract_name)
-            .await;
-
-        return if let Ok(result) = get_last_synced_block_number_file(
-            &config
-                .project_path
-                .join(stream_details.get_streams_last_synced_block_path())
-                .canonicalize()
-                .expect("Failed to canonicalize path"),
-            config.contract_name,
-            config.network,
-            config.event_name,
-        )
-        .await
-        {
-            if let Some(value) = result {
-                if value.is_zero() {
-                    return None;
+    // Then check the stream checkpoints if no csv or database to find out last synced block
+    if config.database.is_none() && !config.contract_csv_enabled {
+        if let Some(stream_checkpoints) = config.stream_checkpoints {
+            return match stream_checkpoints
+                .last_synced_block(config.event_name, config.network)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Error fetching last synced block from stream checkpoints: {:?}", e);
+                    None
                 }
-            }
-
-            result
-        } else {
-            error!("Error fetching last synced block from stream");
-            None
+            };
         }
     }
 
@@ -184,20 +167,28 @@ async fn update_last_synced_block_number_for_file(
     full_path: &Path,
     to_block: U64,
 ) -> Result<(), UpdateLastSyncedBlockNumberFile> {
-    let file_path = build_last_synced_block_number_file(
-        full_path,
-        &config.contract_name,
-        &config.network_contract.network,
-        &config.event_name,
-    );
-
-    let last_block = get_last_synced_block_number_file(
+    write_last_synced_block_number_file(
         full_path,
         &config.contract_name,
         &config.network_contract.network,
         &config.event_name,
+        to_block,
     )
-    .await?;
+    .await
+}
+
+pub(crate) async fn write_last_synced_block_number_file(
+    full_path: &Path,
+    contract_name: &str,
+    network: &str,
+    event_name: &str,
+    to_block: U64,
+) -> Result<(), UpdateLastSyncedBlockNumberFile> {
+    let file_path =
+        build_last_synced_block_number_file(full_path, contract_name, network, event_name);
+
+    let last_block =
+        get_last_synced_block_number_file(full_path, contract_name, network, event_name).await?;
 
     let to_block_higher_then_last_block =
         if let Some(last_block_value) = last_block { to_block > last_block_value } else { true };
@@ -215,8 +206,22 @@ async fn update_last_synced_block_number_for_file(
     Ok(())
 }
 
+/// Moves the progress on without storing the last synced block, used when no logs were found
+pub fn update_progress(config: Arc<EventProcessingConfig>, to_block: U64) {
+    spawn_in_flight(async move {
+        if let Err(e) = config
+            .progress
+            .lock()
+            .await
+            .update_last_synced_block(&config.network_contract.id, to_block)
+        {
+            error!("Error updating last synced block: {:?}", e);
+        }
+    });
+}
+
 pub fn update_progress_and_last_synced(config: Arc<EventProcessingConfig>, to_block: U64) {
-    tokio::spawn(async move {
+    spawn_in_flight(async move {
         let update_last_synced_block_result = config
             .progress
             .lock()
@@ -261,25 +266,71 @@ pub fn update_progress_and_last_synced(config: Arc<EventProcessingConfig>, to_bl
                     csv_details.path, e
                 );
             }
-        } else if let Some(stream_last_synced_block_file_path) =
-            &config.stream_last_synced_block_file_path
-        {
-            if let Err(e) = update_last_synced_block_number_for_file(
-                &config,
-                &config
-                    .project_path
-                    .join(stream_last_synced_block_file_path)
-                    .canonicalize()
-                    .expect("Failed to canonicalize path"),
-                to_block,
-            )
-            .await
+        } else if let Some(stream_checkpoints) = &config.stream_checkpoints {
+            if let Err(e) = stream_checkpoints
+                .update(&config.event_name, &config.network_contract.network, to_block)
+                .await
             {
-                error!(
-                    "Error updating last synced block to stream - path - {} error - {:?}",
-                    stream_last_synced_block_file_path, e
-                );
+                error!("Error updating last synced block to stream checkpoints - error - {:?}", e);
             }
         }
     });
 }
+
+/// Tracks the block ranges which finished processing so the last synced block only moves to the
+/// end of the contiguous blocks, logs handled out of order can then not leave a gap on a restart
+#[derive(Debug)]
+pub struct SyncedBlockRanges {
+    next_block: U64,
+    completed: BTreeMap<U64, U64>,
+}
+
+impl SyncedBlockRanges {
+    pub fn new(from_block: U64) -> Self {
+        SyncedBlockRanges { next_block: from_block, completed: BTreeMap::new() }
+    }
+
+    /// Marks the blocks as processed, returns the new last synced block when it moved on
+    pub fn complete(&mut self, from_block: U64, to_block: U64) -> Option<U64> {
+        if to_block < self.next_block {
+            return None;
+        }
+
+        let completed_to = self.completed.entry(from_block).or_insert(to_block);
+        *completed_to = (*completed_to).max(to_block);
+
+        let mut last_synced_block = None;
+        while let Some((&from_block, &to_block)) = self.completed.first_key_value() {
+            if from_block > self.next_block {
+                break;
+            }
+            self.completed.pop_first();
+            if to_block >= self.next_block {
+                self.next_block = to_block + 1;
+                last_synced_block = Some(to_block);
+            }
+        }
+
+        last_synced_block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synced_block_ranges() {
+        let mut synced = SyncedBlockRanges::new(U64::from(100));
+
+        // later segments finishing first do not move the last synced block past the gap
+        assert_eq!(synced.complete(U64::from(200), U64::from(299)), None);
+        assert_eq!(synced.complete(U64::from(300), U64::from(399)), None);
+        assert_eq!(synced.complete(U64::from(100), U64::from(149)), Some(U64::from(149)));
+        assert_eq!(synced.complete(U64::from(150), U64::from(199)), Some(U64::from(399)));
+
+        // ranges which overlap what is already synced still move it on
+        assert_eq!(synced.complete(U64::from(390), U64::from(450)), Some(U64::from(450)));
+        assert_eq!(synced.complete(U64::from(400), U64::from(420)), None);
+    }
+}
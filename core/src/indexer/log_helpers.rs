@@ -1,7 +1,7 @@
 use std::str::FromStr;
 
 use ethers::{
-    abi::{Event, Log as ParsedLog, LogParam, RawLog, Token},
+    abi::{ethereum_types::BloomInput, Event, Log as ParsedLog, LogParam, RawLog, Token},
     addressbook::Address,
     prelude::{Block, Bloom, FilteredParams, ValueOrArray, H256, U256},
     types::{BigEndianHash, Log},
@@ -74,10 +74,28 @@ pub fn parse_topic(input: &str) -> H256 {
     }
 }
 
-pub fn contract_in_bloom(contract_address: Address, logs_bloom: Bloom) -> bool {
-    let address_filter =
-        FilteredParams::address_filter(&Some(ValueOrArray::Value(contract_address)));
-    FilteredParams::matches_address(logs_bloom, &address_filter)
+/// The bloom bits of each contract address worked out once, so checking every block against
+/// thousands of addresses does not hash all of them again
+pub struct AddressBlooms(Option<Vec<Bloom>>);
+
+impl AddressBlooms {
+    pub fn new(contract_address: &Option<ValueOrArray<Address>>) -> Self {
+        let addresses = match contract_address {
+            None => return AddressBlooms(None),
+            Some(ValueOrArray::Value(address)) => std::slice::from_ref(address),
+            Some(ValueOrArray::Array(addresses)) => addresses.as_slice(),
+        };
+        AddressBlooms(Some(
+            addresses.iter().map(|address| BloomInput::Raw(address.as_bytes()).into()).collect(),
+        ))
+    }
+
+    /// Whether any of the addresses can have logs in the block, always true without addresses
+    fn any_in(&self, logs_bloom: &Bloom) -> bool {
+        self.0
+            .as_ref()
+            .is_none_or(|blooms| blooms.iter().any(|bloom| logs_bloom.contains_bloom(bloom)))
+    }
 }
 
 pub fn topic_in_bloom(topic_id: H256, logs_bloom: Bloom) -> bool {
@@ -87,26 +105,15 @@ pub fn topic_in_bloom(topic_id: H256, logs_bloom: Bloom) -> bool {
 }
 
 pub fn is_relevant_block(
-    contract_address: &Option<ValueOrArray<Address>>,
+    address_blooms: &AddressBlooms,
     topic_id: &H256,
     latest_block: &Block<H256>,
 ) -> bool {
     match latest_block.logs_bloom {
         None => false,
         Some(logs_bloom) => {
-            if let Some(contract_address) = contract_address {
-                match contract_address {
-                    ValueOrArray::Value(address) => {
-                        if !contract_in_bloom(*address, logs_bloom) {
-                            return false;
-                        }
-                    }
-                    ValueOrArray::Array(addresses) => {
-                        if addresses.iter().all(|addr| !contract_in_bloom(*addr, logs_bloom)) {
-                            return false;
-                        }
-                    }
-                }
+            if !address_blooms.any_in(&logs_bloom) {
+                return false;
             }
 
             if !topic_in_bloom(*topic_id, logs_bloom) {
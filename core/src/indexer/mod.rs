@@ -1,17 +1,24 @@
 mod process;
 mod progress;
 
-pub use progress::{IndexingEventProgressStatus, IndexingEventsProgressState};
+pub use progress::{
+    IndexingEventProgress, IndexingEventProgressStatus, IndexingEventsProgressState,
+};
 use serde::{Deserialize, Serialize};
 
+mod address_source;
+mod block_range;
 mod log_helpers;
 pub use log_helpers::parse_topic;
 mod dependency;
 pub use dependency::ContractEventDependenciesMapFromRelationshipsError;
 mod fetch_logs;
-mod last_synced;
+mod historic_source;
+pub mod indexed_events;
+pub(crate) mod last_synced;
 pub mod no_code;
-mod reorg;
+pub(crate) mod reload;
+pub(crate) mod reorg;
 pub mod start;
 pub use dependency::{ContractEventDependencies, EventDependencies, EventsDependencyTree};
 
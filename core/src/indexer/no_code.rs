@@ -4,11 +4,11 @@ use colored::Colorize;
 use ethers::abi::{Abi, Contract as EthersContract, Event};
 use serde_json::Value;
 use tokio_postgres::types::Type as PgType;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, Instrument};
 
 use crate::{
     abi::{ABIItem, CreateCsvFileForEvent, EventInfo, ParamTypeError, ReadAbiError},
-    chat::ChatClients,
+    chat::{ChatClients, ChatError},
     database::postgres::{
         client::PostgresClient,
         generate::{
@@ -31,13 +31,13 @@ use crate::{
     generate_random_id,
     helpers::get_full_path,
     indexer::log_helpers::{map_log_params_to_raw_values, parse_log},
+    logger::setup_info_logger_with_telemetry,
     manifest::{
         core::Manifest,
         yaml::{read_manifest, ReadManifestError},
     },
     provider::{CreateNetworkProvider, RetryClientError},
-    setup_info_logger,
-    streams::StreamsClients,
+    streams::{StreamCheckpointError, StreamCheckpoints, StreamsClients, StreamsClientsError},
     AsyncCsvAppender, FutureExt, IndexingDetails, StartDetails, StartNoCodeDetails,
 };
 
@@ -58,21 +58,24 @@ pub enum SetupNoCodeError {
     #[error("Could not process indexers: {0}")]
     ProcessIndexersError(#[from] ProcessIndexersError),
 
-    #[error("You have graphql disabled as well as indexer so nothing can startup")]
+    #[error("You have graphql and rest disabled as well as indexer so nothing can startup")]
     NothingToStartNoCode,
 }
 
 pub async fn setup_no_code(
     details: StartNoCodeDetails<'_>,
 ) -> Result<StartDetails<'_>, SetupNoCodeError> {
-    if !details.indexing_details.enabled && !details.graphql_details.enabled {
+    if !details.indexing_details.enabled &&
+        !details.graphql_details.enabled &&
+        !details.rest_details.enabled
+    {
         return Err(SetupNoCodeError::NothingToStartNoCode);
     }
     let project_path = details.manifest_path.parent();
     match project_path {
         Some(project_path) => {
             let mut manifest = read_manifest(details.manifest_path)?;
-            setup_info_logger();
+            setup_info_logger_with_telemetry(&manifest);
 
             info!("Starting rindexer no code");
 
@@ -86,6 +89,7 @@ pub async fn setup_no_code(
                     manifest_path: details.manifest_path,
                     indexing_details: None,
                     graphql_details: details.graphql_details,
+                    rest_details: details.rest_details,
                 });
             }
 
@@ -115,8 +119,12 @@ pub async fn setup_no_code(
 
             Ok(StartDetails {
                 manifest_path: details.manifest_path,
-                indexing_details: Some(IndexingDetails { registry }),
+                indexing_details: Some(IndexingDetails {
+                    registry,
+                    until: details.indexing_details.until,
+                }),
                 graphql_details: details.graphql_details,
+                rest_details: details.rest_details,
             })
         }
         None => Err(SetupNoCodeError::NoProjectPathFoundUsingParentOfManifestPath),
@@ -155,8 +163,12 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                 return Ok(());
             }
 
-            let (from_block, to_block) = match results.first() {
-                Some(first) => (first.found_in_request.from_block, first.found_in_request.to_block),
+            let (from_block, to_block, live) = match results.first() {
+                Some(first) => (
+                    first.found_in_request.from_block,
+                    first.found_in_request.to_block,
+                    first.found_in_request.live,
+                ),
                 None => {
                     let error_message = "Unexpected error: no first event despite non-zero length.";
                     error!("{}", error_message);
@@ -320,7 +332,8 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
 
             if let Some(csv) = &params.csv {
                 if !csv_bulk_data.is_empty() {
-                    if let Err(e) = csv.append_bulk(csv_bulk_data).await {
+                    let span = info_span!("csv_append", rows = csv_bulk_data.len());
+                    if let Err(e) = csv.append_bulk(csv_bulk_data).instrument(span).await {
                         return Err(e.to_string());
                     }
                 }
@@ -339,7 +352,7 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
                 );
 
                 match streams_clients
-                    .stream(stream_id, &event_message, params.index_event_in_order)
+                    .stream(stream_id, &event_message, &to_block, params.index_event_in_order)
                     .await
                 {
                     Ok(streamed) => {
@@ -365,22 +378,17 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbackType {
             }
 
             if let Some(chat_clients) = params.chat_clients.as_ref() {
-                if !chat_clients.is_in_block_range_to_send(&from_block, &to_block) {
-                    warn!(
-                        "{}::{} - {} - messages has a max 10 block range due the rate limits - {}",
+                if !chat_clients.should_send(live) {
+                    debug!(
+                        "{}::{} - {} - messages are not sent for historic events - {}",
                         params.contract_name,
                         params.event_info.name,
-                        "CHAT_MESSAGES_DISABLED".yellow(),
+                        "CHAT_MESSAGES_SKIPPED".yellow(),
                         format!("- blocks: {} - {} - network: {}", from_block, to_block, network)
                     );
                 } else {
                     match chat_clients
-                        .send_message(
-                            &event_message,
-                            params.index_event_in_order,
-                            &from_block,
-                            &to_block,
-                        )
+                        .send_message(&event_message, params.index_event_in_order)
                         .await
                     {
                         Ok(messages_sent) => {
@@ -449,6 +457,15 @@ pub enum ProcessIndexersError {
 
     #[error("Event name not found in ABI for contract: {0} - event: {1}")]
     EventNameNotFoundInAbi(String, String),
+
+    #[error("Could not create stream checkpoints: {0}")]
+    StreamCheckpointError(#[from] StreamCheckpointError),
+
+    #[error("Could not create chat clients: {0}")]
+    ChatError(#[from] ChatError),
+
+    #[error("Could not create stream clients: {0}")]
+    StreamsClientsError(#[from] StreamsClientsError),
 }
 
 pub async fn process_events(
@@ -474,6 +491,29 @@ pub async fn process_events(
         let abi_items = ABIItem::get_abi_items(project_path, contract, is_filter)?;
         let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
 
+        // stream only contracts skip sinks which have already streamed the blocks
+        let stream_checkpoints = match &contract.streams {
+            Some(streams)
+                if postgres.is_none() &&
+                    !(contract.generate_csv.unwrap_or(true) &&
+                        manifest.storage.csv_enabled()) =>
+            {
+                Some(Arc::new(
+                    StreamCheckpoints::new(streams, project_path, &manifest.name, &contract.name)
+                        .await?,
+                ))
+            }
+            _ => None,
+        };
+
+        // one set of chat clients per contract so the bots, rate limits and digests are shared
+        // by all of its events
+        let chat_clients = Arc::new(if let Some(chats) = &contract.chat {
+            Some(ChatClients::new(chats.clone(), &manifest.networks).await?)
+        } else {
+            None
+        });
+
         for event_info in event_names {
             let event_name = event_info.name.clone();
             let event = &abi_gen
@@ -520,13 +560,15 @@ pub async fn process_events(
                 generate_event_table_full_name(&manifest.name, &contract.name, &event_info.name);
 
             let streams_client = if let Some(streams) = &contract.streams {
-                Some(StreamsClients::new(streams.clone()).await)
-            } else {
-                None
-            };
-
-            let chat_clients = if let Some(chats) = &contract.chat {
-                Some(ChatClients::new(chats.clone()).await)
+                Some(
+                    StreamsClients::new(
+                        streams.clone(),
+                        &contract.name,
+                        &event_info,
+                        stream_checkpoints.clone(),
+                    )
+                    .await?,
+                )
             } else {
                 None
             };
@@ -554,8 +596,9 @@ pub async fn process_events(
                     postgres_event_table_name,
                     postgres_column_names,
                     streams_clients: Arc::new(streams_client),
-                    chat_clients: Arc::new(chat_clients),
+                    chat_clients: Arc::clone(&chat_clients),
                 })),
+                stream_checkpoints: stream_checkpoints.clone(),
             };
 
             events.push(event);
@@ -3,14 +3,14 @@ use std::{collections::HashMap, sync::Arc};
 use async_std::prelude::StreamExt;
 use ethers::{
     prelude::ProviderError,
-    types::{H256, U64},
+    types::{Log, H256, U64},
 };
 use futures::future::join_all;
 use tokio::{
     sync::{Mutex, MutexGuard},
     task::{JoinError, JoinHandle},
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, info_span, Instrument};
 
 use crate::{
     event::{
@@ -19,11 +19,14 @@ use crate::{
     },
     indexer::{
         dependency::{ContractEventsDependenciesConfig, EventDependencies},
-        fetch_logs::{fetch_logs_stream, FetchLogsResult},
-        last_synced::update_progress_and_last_synced,
-        log_helpers::is_relevant_block,
+        fetch_logs::{fetch_logs_stream, get_logs_in_address_chunks, FetchLogsResult},
+        last_synced::{update_progress, update_progress_and_last_synced, SyncedBlockRanges},
+        log_helpers::{is_relevant_block, AddressBlooms},
         progress::IndexingEventProgressStatus,
+        reorg::finality_head,
     },
+    metrics::record_events_processed,
+    shutdown::{is_shutting_down, spawn_in_flight},
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -48,9 +51,15 @@ async fn process_event_logs(
     force_no_live_indexing: bool,
 ) -> Result<(), Box<ProviderError>> {
     let mut logs_stream = fetch_logs_stream(Arc::clone(&config), force_no_live_indexing);
+    let synced_blocks = Arc::new(Mutex::new(SyncedBlockRanges::new(config.start_block)));
 
     while let Some(result) = logs_stream.next().await {
-        handle_logs_result(Arc::clone(&config), result)
+        // the batch being handled finishes but no new batches are taken once shutting down
+        if is_shutting_down() {
+            break;
+        }
+
+        handle_logs_result(Arc::clone(&config), Some(Arc::clone(&synced_blocks)), result)
             .await
             .map_err(|e| Box::new(ProviderError::CustomError(e.to_string())))?;
     }
@@ -58,6 +67,89 @@ async fn process_event_logs(
     Ok(())
 }
 
+/// Fetches the logs of every event of a contract on a network with one `eth_getLogs` per block
+/// range and hands each event the logs of its own topic
+pub async fn process_contract_events(
+    configs: Vec<EventProcessingConfig>,
+) -> Result<(), ProcessEventError> {
+    let configs: Vec<Arc<EventProcessingConfig>> = configs.into_iter().map(Arc::new).collect();
+    let combined_config = Arc::new(EventProcessingConfig::combined(&configs));
+    debug!("{} - Processing events with one eth_getLogs", combined_config.info_log_name);
+
+    let synced_blocks: Vec<Arc<Mutex<SyncedBlockRanges>>> = configs
+        .iter()
+        .map(|config| Arc::new(Mutex::new(SyncedBlockRanges::new(config.start_block))))
+        .collect();
+
+    let mut logs_stream = fetch_logs_stream(combined_config, false);
+    while let Some(result) = logs_stream.next().await {
+        if is_shutting_down() {
+            break;
+        }
+
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Error fetching logs: {:?}", e);
+                return Err(Box::new(ProviderError::CustomError(e.to_string())).into());
+            }
+        };
+
+        let event_results = split_logs_by_event(&configs, result);
+        let handles = configs.iter().zip(&synced_blocks).zip(event_results).filter_map(
+            |((config, synced_blocks), event_result)| {
+                Some(handle_logs_result(
+                    Arc::clone(config),
+                    Some(Arc::clone(synced_blocks)),
+                    Ok(event_result?),
+                ))
+            },
+        );
+
+        for handled in join_all(handles).await {
+            handled.map_err(|e| Box::new(ProviderError::CustomError(e.to_string())))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits the logs of a shared `eth_getLogs` into the logs of each event, events further ahead
+/// which already indexed the blocks on a previous run get none
+fn split_logs_by_event(
+    configs: &[Arc<EventProcessingConfig>],
+    result: FetchLogsResult,
+) -> Vec<Option<FetchLogsResult>> {
+    let mut logs_by_topic: HashMap<H256, Vec<Log>> = HashMap::new();
+    for log in result.logs {
+        if let Some(topic_id) = log.topics.first() {
+            logs_by_topic.entry(*topic_id).or_default().push(log);
+        }
+    }
+
+    configs
+        .iter()
+        .map(|config| {
+            if result.to_block < config.start_block {
+                return None;
+            }
+            let logs = logs_by_topic
+                .remove(&config.topic_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|log| log.block_number.is_none_or(|block| block >= config.start_block))
+                .collect();
+            Some(FetchLogsResult {
+                logs,
+                from_block: result.from_block.max(config.start_block),
+                to_block: result.to_block,
+                live: result.live,
+                span: result.span.clone(),
+            })
+        })
+        .collect()
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ProcessContractsEventsWithDependenciesError {
     #[error("{0}")]
@@ -199,8 +291,11 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
         H256,
         Arc<Mutex<OrderedLiveIndexingDetails>>,
     > = HashMap::new();
+    let mut address_blooms: HashMap<H256, AddressBlooms> = HashMap::new();
 
     for (config, event_filter) in live_indexing_events.iter() {
+        address_blooms
+            .insert(config.topic_id, AddressBlooms::new(&event_filter.raw_filter().address));
         let mut filter = event_filter.clone();
         let last_seen_block_number = filter.get_to_block();
         let next_block_number = last_seen_block_number + 1;
@@ -216,6 +311,10 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
     loop {
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
 
+        if is_shutting_down() {
+            break;
+        }
+
         for (config, _) in live_indexing_events.iter() {
             let mut ordering_live_indexing_details = ordering_live_indexing_details_map
                 .get(&config.topic_id)
@@ -247,26 +346,46 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
                                 latest_block_number,
                                 ordering_live_indexing_details.last_seen_block_number
                             );
-                            let reorg_safe_distance = &config.indexing_distance_from_head;
-                            let safe_block_number = latest_block_number - reorg_safe_distance;
+                            let safe_block_number = match finality_head(
+                                &config.network_contract.cached_provider,
+                                config.finality,
+                                latest_block_number,
+                            )
+                            .await
+                            {
+                                Ok(safe_block_number) => safe_block_number,
+                                Err(e) => {
+                                    error!(
+                                        "{} - {} - Error getting the {}: {}",
+                                        &config.info_log_name,
+                                        IndexingEventProgressStatus::Live.log(),
+                                        config.finality.describe(),
+                                        e
+                                    );
+                                    continue;
+                                }
+                            };
                             let from_block = ordering_live_indexing_details.filter.get_from_block();
                             // check reorg distance and skip if not safe
                             if from_block > safe_block_number {
                                 info!(
-                                    "{} - {} - not in safe reorg block range yet block: {} > range: {}",
+                                    "{} - {} - not in safe reorg block range yet block: {} > range: {} - following the {}",
                                     &config.info_log_name,
                                     IndexingEventProgressStatus::Live.log(),
                                     from_block,
-                                    safe_block_number
+                                    safe_block_number,
+                                    config.finality.describe()
                                 );
                                 continue;
                             }
 
                             let to_block = safe_block_number;
+                            // the logs bloom is only of the latest block
                             if from_block == to_block &&
+                                to_block == latest_block_number &&
                                 !config.network_contract.disable_logs_bloom_checks &&
                                 !is_relevant_block(
-                                    &ordering_live_indexing_details.filter.raw_filter().address,
+                                    &address_blooms[&config.topic_id],
                                     &config.topic_id,
                                     latest_block,
                                 )
@@ -308,15 +427,16 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
                                 ordering_live_indexing_details.filter
                             );
 
-                            let semaphore_client = Arc::clone(&config.semaphore);
-                            let permit = semaphore_client.acquire_owned().await;
+                            let permit = config.request_concurrency.acquire().await;
 
                             if let Ok(permit) = permit {
-                                match config
-                                    .network_contract
-                                    .cached_provider
-                                    .get_logs(&ordering_live_indexing_details.filter)
-                                    .await
+                                let span = config.logs_span(from_block, to_block, true);
+                                match get_logs_in_address_chunks(
+                                    config,
+                                    &ordering_live_indexing_details.filter,
+                                )
+                                .instrument(span.clone())
+                                .await
                                 {
                                     Ok(logs) => {
                                         debug!(
@@ -342,12 +462,20 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
                                         // clone here over the full logs way less overhead
                                         let last_log = logs.last().cloned();
 
-                                        let fetched_logs =
-                                            Ok(FetchLogsResult { logs, from_block, to_block });
-
-                                        let result =
-                                            handle_logs_result(Arc::clone(config), fetched_logs)
-                                                .await;
+                                        let fetched_logs = Ok(FetchLogsResult {
+                                            logs,
+                                            from_block,
+                                            to_block,
+                                            live: true,
+                                            span,
+                                        });
+
+                                        let result = handle_logs_result(
+                                            Arc::clone(config),
+                                            None,
+                                            fetched_logs,
+                                        )
+                                        .await;
 
                                         match result {
                                             Ok(_) => {
@@ -431,8 +559,25 @@ async fn live_indexing_for_contract_event_dependencies<'a>(
     }
 }
 
+/// The block the last synced block can move to once the logs are processed, historic segments can
+/// finish out of order so only the contiguous blocks count
+async fn synced_to_block(
+    synced_blocks: &Option<Arc<Mutex<SyncedBlockRanges>>>,
+    result_from_block: U64,
+    result_to_block: U64,
+    live: bool,
+) -> Option<U64> {
+    match synced_blocks {
+        Some(synced_blocks) if !live => {
+            synced_blocks.lock().await.complete(result_from_block, result_to_block)
+        }
+        _ => Some(result_to_block),
+    }
+}
+
 async fn handle_logs_result(
     config: Arc<EventProcessingConfig>,
+    synced_blocks: Option<Arc<Mutex<SyncedBlockRanges>>>,
     result: Result<FetchLogsResult, Box<dyn std::error::Error + Send>>,
 ) -> Result<(), Box<dyn std::error::Error + Send>> {
     match result {
@@ -448,20 +593,74 @@ async fn handle_logs_result(
                         log,
                         result.from_block,
                         result.to_block,
+                        result.live,
                     )
                 })
                 .collect::<Vec<_>>();
 
+            record_events_processed(
+                &config.network_contract.network,
+                &config.contract_name,
+                &config.event_name,
+                fn_data.len(),
+            );
+
             if !fn_data.is_empty() {
+                let trigger_span = info_span!(parent: &result.span, "trigger_event");
                 if config.index_event_in_order {
-                    config.trigger_event(fn_data).await;
-                    update_progress_and_last_synced(config, result.to_block);
+                    config.trigger_event(fn_data).instrument(trigger_span).await;
+                    if result.live {
+                        config.publish_indexed_events(result.from_block, result.to_block, true);
+                    }
+                    if let Some(to_block) = synced_to_block(
+                        &synced_blocks,
+                        result.from_block,
+                        result.to_block,
+                        result.live,
+                    )
+                    .await
+                    {
+                        update_progress_and_last_synced(config, to_block);
+                    }
                 } else {
-                    tokio::spawn(async move {
-                        config.trigger_event(fn_data).await;
-                        update_progress_and_last_synced(config, result.to_block);
+                    // waiting here holds up the next fetched logs so fetching pauses once
+                    // `log_buffer_size` callbacks are running
+                    let permit = match Arc::clone(&config.callbacks_in_flight).acquire_owned().await
+                    {
+                        Ok(permit) => permit,
+                        Err(e) => {
+                            error!("Error waiting for a callback to finish: {:?}", e);
+                            return Err(Box::new(e));
+                        }
+                    };
+                    spawn_in_flight(async move {
+                        config.trigger_event(fn_data).instrument(trigger_span).await;
+                        if result.live {
+                            config.publish_indexed_events(result.from_block, result.to_block, true);
+                        }
+                        if let Some(to_block) = synced_to_block(
+                            &synced_blocks,
+                            result.from_block,
+                            result.to_block,
+                            result.live,
+                        )
+                        .await
+                        {
+                            update_progress_and_last_synced(config, to_block);
+                        }
+                        drop(permit);
                     });
                 }
+            } else {
+                if result.live {
+                    config.publish_indexed_events(result.from_block, result.to_block, false);
+                }
+                if let Some(to_block) =
+                    synced_to_block(&synced_blocks, result.from_block, result.to_block, result.live)
+                        .await
+                {
+                    update_progress(config, to_block);
+                }
             }
 
             Ok(())
@@ -472,3 +671,243 @@ async fn handle_logs_result(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use ethers::types::{Address, ValueOrArray};
+    use futures::FutureExt;
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+    use tokio::{
+        sync::{Notify, Semaphore},
+        time::timeout,
+    };
+    use tracing::Span;
+
+    use super::*;
+    use crate::{
+        event::{
+            callback_registry::{
+                noop_decoder, EventCallbackRegistry, EventCallbackRegistryInformation,
+            },
+            contract_setup::{
+                AddressDetails, ContractInformation, IndexingContractSetup, NetworkContract,
+            },
+        },
+        indexer::IndexingEventsProgressState,
+        manifest::network::Finality,
+        provider::{create_client, RequestConcurrency},
+    };
+
+    fn config(event_name: &str, topic_id: H256, start_block: u64) -> EventProcessingConfig {
+        let network_contract = NetworkContract {
+            id: "1".to_string(),
+            network: "ethereum".to_string(),
+            indexing_contract_setup: IndexingContractSetup::Address(AddressDetails {
+                address: Address::repeat_byte(1).into(),
+                indexed_filters: None,
+                address_source: None,
+            }),
+            cached_provider: create_client(
+                "ethereum",
+                "http://localhost:8545",
+                None,
+                None,
+                Default::default(),
+            )
+            .unwrap(),
+            decoder: noop_decoder(),
+            start_block: Some(start_block.into()),
+            end_block: Some(1_000.into()),
+            historic_source: None,
+            disable_logs_bloom_checks: false,
+        };
+
+        EventProcessingConfig {
+            id: event_name.to_string(),
+            project_path: PathBuf::new(),
+            indexer_name: "Indexer".to_string(),
+            contract_name: "Token".to_string(),
+            info_log_name: format!("Token::{}", event_name),
+            topic_id,
+            combined_topic_ids: vec![],
+            event_name: event_name.to_string(),
+            network_contract: Arc::new(network_contract),
+            start_block: start_block.into(),
+            end_block: 1_000.into(),
+            request_concurrency: RequestConcurrency::new(
+                Arc::new(Semaphore::new(1)),
+                Arc::new(Semaphore::new(1)),
+            ),
+            registry: Arc::new(EventCallbackRegistry::new()),
+            progress: Arc::new(Mutex::new(IndexingEventsProgressState { events: vec![] })),
+            database: None,
+            csv_details: None,
+            stream_checkpoints: None,
+            index_event_in_order: false,
+            historic_segments: 1,
+            log_buffer_size: 4,
+            callbacks_in_flight: Arc::new(Semaphore::new(4)),
+            live_indexing: false,
+            finality: Finality::Latest,
+            reorg_safe_distance: U64::zero(),
+        }
+    }
+
+    fn log(topic_id: H256, block_number: u64) -> Log {
+        Log {
+            topics: vec![topic_id],
+            block_number: Some(block_number.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_logs_by_event() {
+        let (transfer, approval, burn) =
+            (H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+        let configs = vec![
+            Arc::new(config("Transfer", transfer, 100)),
+            Arc::new(config("Approval", approval, 150)),
+            Arc::new(config("Burn", burn, 300)),
+        ];
+
+        // the shared request starts from the event furthest behind and fetches every topic
+        let combined = EventProcessingConfig::combined(&configs);
+        assert_eq!(combined.event_name, "Transfer,Approval,Burn");
+        assert_eq!(combined.start_block, U64::from(100));
+        assert_eq!(combined.topic_ids(), vec![transfer, approval, burn]);
+
+        let result = FetchLogsResult {
+            logs: vec![
+                log(transfer, 120),
+                log(approval, 120),
+                log(H256::repeat_byte(9), 130),
+                log(approval, 160),
+                log(transfer, 190),
+            ],
+            from_block: 100.into(),
+            to_block: 200.into(),
+            live: false,
+            span: Span::none(),
+        };
+        let results = split_logs_by_event(&configs, result);
+        let blocks = |result: &FetchLogsResult| {
+            result.logs.iter().map(|log| log.block_number.unwrap().as_u64()).collect::<Vec<_>>()
+        };
+
+        let transfers = results[0].as_ref().unwrap();
+        assert_eq!(blocks(transfers), vec![120, 190]);
+        assert_eq!((transfers.from_block, transfers.to_block), (100.into(), 200.into()));
+
+        // logs before the start block of an event were already indexed on a previous run
+        let approvals = results[1].as_ref().unwrap();
+        assert_eq!(blocks(approvals), vec![160]);
+        assert_eq!((approvals.from_block, approvals.to_block), (150.into(), 200.into()));
+
+        // an event which starts after the range is not handed the range at all
+        assert!(results[2].is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slow_callback_pauses_fetching() {
+        let transfer = H256::repeat_byte(1);
+        let release = Arc::new(Notify::new());
+        let callback_release = Arc::clone(&release);
+        let mut registry = EventCallbackRegistry::new();
+        registry.register_event(EventCallbackRegistryInformation {
+            id: "Transfer".to_string(),
+            indexer_name: "Indexer".to_string(),
+            topic_id: transfer,
+            event_name: "Transfer".to_string(),
+            index_event_in_order: false,
+            contract: ContractInformation {
+                name: "Token".to_string(),
+                details: vec![],
+                abi: String::new(),
+                reorg_safe_distance: false,
+                historic_segments: 1,
+                combine_get_logs: false,
+                log_buffer_size: 1,
+            },
+            callback: Arc::new(move |_| {
+                let release = Arc::clone(&callback_release);
+                async move {
+                    release.notified().await;
+                    Ok(())
+                }
+                .boxed()
+            }),
+            stream_checkpoints: None,
+        });
+
+        let mut config = config("Transfer", transfer, 100);
+        config.registry = Arc::new(registry);
+        config.log_buffer_size = 1;
+        config.callbacks_in_flight = Arc::new(Semaphore::new(1));
+        let config = Arc::new(config);
+        let result = |from_block: u64| {
+            Ok(FetchLogsResult {
+                logs: vec![Log {
+                    block_hash: Some(H256::zero()),
+                    transaction_hash: Some(H256::zero()),
+                    transaction_index: Some(0.into()),
+                    log_index: Some(0.into()),
+                    ..log(transfer, from_block)
+                }],
+                from_block: from_block.into(),
+                to_block: (from_block + 9).into(),
+                live: false,
+                span: Span::none(),
+            })
+        };
+
+        handle_logs_result(Arc::clone(&config), None, result(100)).await.unwrap();
+
+        // the first callback is still running so the next fetched logs wait for it to finish
+        let mut next = Box::pin(handle_logs_result(Arc::clone(&config), None, result(110)));
+        assert!(timeout(Duration::from_millis(100), &mut next).await.is_err());
+
+        release.notify_one();
+        timeout(Duration::from_secs(5), next).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_address_chunks_run_under_the_held_permit() {
+        let mock = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "method": "eth_getLogs" })))
+            .with_body(r#"{"jsonrpc":"2.0","id":0,"result":[]}"#)
+            .expect(3)
+            .create();
+
+        let mut config = config("Transfer", H256::repeat_byte(1), 100);
+        let addresses: Vec<Address> = (0..2_500u64).map(Address::from_low_u64_be).collect();
+        config.network_contract = Arc::new(NetworkContract {
+            indexing_contract_setup: IndexingContractSetup::Address(AddressDetails {
+                address: ValueOrArray::Array(addresses),
+                indexed_filters: None,
+                address_source: None,
+            }),
+            cached_provider: create_client(
+                "ethereum",
+                &mockito::server_url(),
+                None,
+                None,
+                Default::default(),
+            )
+            .unwrap(),
+            ..(*config.network_contract).clone()
+        });
+
+        // the fetch holds the only request slot while its 3 address chunks are requested
+        let _permit = config.request_concurrency.acquire().await.unwrap();
+        let filter = config.to_event_filter().unwrap();
+        let logs = timeout(Duration::from_secs(5), get_logs_in_address_chunks(&config, &filter))
+            .await
+            .expect("the address chunks waited on the request slot the fetch holds");
+        assert!(logs.unwrap().is_empty());
+        mock.assert();
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     hash::{Hash, Hasher},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use colored::{ColoredString, Colorize};
@@ -8,7 +8,10 @@ use ethers::types::U64;
 use tokio::sync::Mutex;
 use tracing::{error, info};
 
-use crate::event::callback_registry::EventCallbackRegistryInformation;
+use crate::{
+    event::callback_registry::EventCallbackRegistryInformation, lazy_static,
+    manifest::network::Finality,
+};
 
 #[derive(Clone, Debug, Hash)]
 pub enum IndexingEventProgressStatus {
@@ -19,7 +22,7 @@ pub enum IndexingEventProgressStatus {
 }
 
 impl IndexingEventProgressStatus {
-    fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &str {
         match self {
             Self::Syncing => "SYNCING",
             Self::Live => "LIVE",
@@ -43,6 +46,8 @@ pub struct IndexingEventProgress {
     pub syncing_to_block: U64,
     pub network: String,
     pub live_indexing: bool,
+    /// The head of the chain the event is indexed up to
+    pub finality: Finality,
     pub status: IndexingEventProgressStatus,
     pub progress: f64,
     pub info_log: String,
@@ -84,6 +89,7 @@ impl IndexingEventProgress {
             syncing_to_block,
             network,
             live_indexing,
+            finality: Finality::Latest,
             status: IndexingEventProgressStatus::Syncing,
             progress: 0.0,
             info_log,
@@ -95,6 +101,11 @@ pub struct IndexingEventsProgressState {
     pub events: Vec<IndexingEventProgress>,
 }
 
+lazy_static! {
+    static ref CURRENT_PROGRESS_STATE: RwLock<Option<Arc<Mutex<IndexingEventsProgressState>>>> =
+        RwLock::new(None);
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SyncError {
     #[error("Event with id {0} not found")]
@@ -142,7 +153,63 @@ impl IndexingEventsProgressState {
             }
         }
 
-        Arc::new(Mutex::new(Self { events }))
+        // indexing started again for some contracts on a reload keeps the others running
+        if let Some(state) = Self::current() {
+            state.lock().await.add_events(events);
+            return state;
+        }
+
+        let state = Arc::new(Mutex::new(Self { events }));
+        if let Ok(mut current) = CURRENT_PROGRESS_STATE.write() {
+            *current = Some(Arc::clone(&state));
+        }
+
+        state
+    }
+
+    /// Adds the events of contracts which started indexing, replacing any events those contracts
+    /// already had
+    pub fn add_events(&mut self, events: Vec<IndexingEventProgress>) {
+        self.events
+            .retain(|event| !events.iter().any(|added| added.contract_name == event.contract_name));
+        self.events.extend(events);
+    }
+
+    pub fn remove_contract(&mut self, contract_name: &str) {
+        self.events.retain(|event| event.contract_name != contract_name);
+    }
+
+    /// The progress of every indexing event, none if indexing has not started
+    pub fn current() -> Option<Arc<Mutex<IndexingEventsProgressState>>> {
+        CURRENT_PROGRESS_STATE.read().ok().and_then(|current| current.clone())
+    }
+
+    /// Records the head the event follows and the blocks it syncs, an event which already synced
+    /// past the end block is done straight away
+    pub fn set_sync_range(
+        &mut self,
+        id: &str,
+        finality: Finality,
+        start_block: U64,
+        end_block: U64,
+    ) {
+        for event in &mut self.events {
+            if event.id == id {
+                event.finality = finality;
+                if event.progress < 1.0 {
+                    event.syncing_to_block = event.syncing_to_block.min(end_block);
+                    if start_block > event.syncing_to_block {
+                        event.last_synced_block = event.syncing_to_block;
+                        event.progress = 1.0;
+                        event.status = if event.live_indexing {
+                            IndexingEventProgressStatus::Live
+                        } else {
+                            IndexingEventProgressStatus::Completed
+                        };
+                    }
+                }
+            }
+        }
     }
 
     pub fn update_last_synced_block(
@@ -198,13 +265,18 @@ impl IndexingEventsProgressState {
                     }
 
                     info!(
-                        "{} - network {} - {:.2}% progress",
+                        "{} - network {} - {:.2}% progress - following the {}",
                         event.info_log,
                         event.network,
-                        event.progress * 100.0
+                        event.progress * 100.0,
+                        event.finality.describe()
                     );
                 }
 
+                // once live the block being synced to moves with the head
+                if event.progress >= 1.0 && new_last_synced_block > event.syncing_to_block {
+                    event.syncing_to_block = new_last_synced_block;
+                }
                 event.last_synced_block = new_last_synced_block;
                 return Ok(());
             }
@@ -213,3 +285,77 @@ impl IndexingEventsProgressState {
         Err(SyncError::EventNotFound(id.to_string()))
     }
 }
+
+#[cfg(test)]
+impl IndexingEventProgress {
+    /// An event syncing from block 0 to 100 which has synced up to `last_synced_block`
+    pub(crate) fn for_test(
+        contract_name: &str,
+        event_name: &str,
+        network: &str,
+        status: IndexingEventProgressStatus,
+        last_synced_block: u64,
+    ) -> Self {
+        let mut event = Self::running(
+            format!("{}-{}", contract_name, event_name),
+            contract_name.to_string(),
+            event_name.to_string(),
+            U64::zero(),
+            U64::from(last_synced_block),
+            U64::from(100),
+            network.to_string(),
+            true,
+            format!("{}::{}", contract_name, event_name),
+        );
+        event.status = status;
+        event.progress = last_synced_block as f64 / 100.0;
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_progress(
+        contract_name: &str,
+        event_name: &str,
+        last_synced_block: u64,
+    ) -> IndexingEventProgress {
+        IndexingEventProgress::for_test(
+            contract_name,
+            event_name,
+            "ethereum",
+            IndexingEventProgressStatus::Syncing,
+            last_synced_block,
+        )
+    }
+
+    #[test]
+    fn test_add_events_keeps_the_other_contracts() {
+        let mut state = IndexingEventsProgressState {
+            events: vec![
+                event_progress("Reloaded", "Transfer", 50),
+                event_progress("Reloaded", "Approval", 50),
+                event_progress("Kept", "Transfer", 80),
+            ],
+        };
+
+        state.add_events(vec![event_progress("Reloaded", "Transfer", 0)]);
+
+        let events: Vec<(&str, &str, U64)> = state
+            .events
+            .iter()
+            .map(|event| {
+                (event.contract_name.as_str(), event.event_name.as_str(), event.last_synced_block)
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![("Kept", "Transfer", U64::from(80)), ("Reloaded", "Transfer", U64::zero())]
+        );
+
+        state.remove_contract("Reloaded");
+        assert_eq!(state.events.len(), 1);
+    }
+}
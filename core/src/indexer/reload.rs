@@ -0,0 +1,363 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use serde_yaml::Value;
+use tokio::task::AbortHandle;
+use tracing::{error, info, warn};
+
+use crate::{
+    database::postgres::setup::{setup_postgres, SetupPostgresError},
+    event::callback_registry::EventCallbackRegistry,
+    indexer::{
+        no_code::{process_events, ProcessIndexersError},
+        start::start_indexing,
+        ContractEventDependencies, IndexingEventsProgressState,
+    },
+    lazy_static,
+    manifest::{core::Manifest, yaml::read_manifest},
+    provider::{CreateNetworkProvider, RetryClientError},
+    PostgresClient,
+};
+
+lazy_static! {
+    /// The running event pipelines of each contract so a manifest reload can stop them
+    static ref CONTRACT_TASKS: Mutex<HashMap<String, Vec<AbortHandle>>> =
+        Mutex::new(HashMap::new());
+}
+
+pub fn register_contract_task(contract_name: &str, task: AbortHandle) {
+    if let Ok(mut tasks) = CONTRACT_TASKS.lock() {
+        tasks.entry(contract_name.to_string()).or_default().push(task);
+    }
+}
+
+async fn stop_contract_indexing(contract_name: &str) {
+    let tasks = CONTRACT_TASKS.lock().ok().and_then(|mut tasks| tasks.remove(contract_name));
+    for task in tasks.unwrap_or_default() {
+        task.abort();
+    }
+
+    if let Some(progress) = IndexingEventsProgressState::current() {
+        progress.lock().await.remove_contract(contract_name);
+    }
+
+    info!("{} - Stopped indexing", contract_name);
+}
+
+/// What changed between the running manifest and the manifest on disk
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestChanges {
+    pub added_contracts: Vec<String>,
+    pub removed_contracts: Vec<String>,
+    pub changed_contracts: Vec<String>,
+    /// Top level fields which only apply once rindexer is restarted
+    pub needs_restart: Vec<String>,
+}
+
+fn to_yaml_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_yaml::to_value(value).unwrap_or(Value::Null)
+}
+
+impl ManifestChanges {
+    pub fn between(running: &Manifest, updated: &Manifest) -> Self {
+        let mut changes = ManifestChanges::default();
+
+        for contract in &updated.contracts {
+            match running.contracts.iter().find(|c| c.name == contract.name) {
+                Some(running_contract) => {
+                    if to_yaml_value(running_contract) != to_yaml_value(contract) {
+                        changes.changed_contracts.push(contract.name.clone());
+                    }
+                }
+                None => changes.added_contracts.push(contract.name.clone()),
+            }
+        }
+
+        for contract in &running.contracts {
+            if !updated.contracts.iter().any(|c| c.name == contract.name) {
+                changes.removed_contracts.push(contract.name.clone());
+            }
+        }
+
+        let (running, updated) = (to_yaml_value(running), to_yaml_value(updated));
+        let (running, updated) = match (running.as_mapping(), updated.as_mapping()) {
+            (Some(running), Some(updated)) => (running, updated),
+            _ => return changes,
+        };
+        let fields: BTreeSet<&str> =
+            running.keys().chain(updated.keys()).filter_map(|key| key.as_str()).collect();
+        for field in fields {
+            if matches!(field, "contracts" | "description" | "repository") {
+                continue;
+            }
+            if running.get(field) != updated.get(field) {
+                changes.needs_restart.push(field.to_string());
+            }
+        }
+
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_contracts.is_empty() &&
+            self.removed_contracts.is_empty() &&
+            self.changed_contracts.is_empty() &&
+            self.needs_restart.is_empty()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReloadManifestError {
+    #[error("Could not setup postgres: {0}")]
+    SetupPostgres(#[from] SetupPostgresError),
+
+    #[error("{0}")]
+    RetryClient(#[from] RetryClientError),
+
+    #[error("Could not process indexers: {0}")]
+    ProcessIndexers(#[from] ProcessIndexersError),
+}
+
+/// Contracts in `dependency_events` run as one pipeline so they can not be swapped on their own
+fn dependency_contracts(manifest: &Manifest) -> Vec<String> {
+    ContractEventDependencies::parse(manifest)
+        .into_iter()
+        .flat_map(|dependencies| {
+            let mut contracts: Vec<String> = dependencies
+                .event_dependencies
+                .dependency_events
+                .iter()
+                .map(|event| event.contract_name.clone())
+                .collect();
+            contracts.push(dependencies.contract_name);
+            contracts
+        })
+        .collect()
+}
+
+/// Applies the contract changes and updates `running` with the contracts that were stopped and
+/// started, skipped contracts and fields which need a restart keep their running config
+async fn apply_changes(
+    project_path: &Path,
+    running: &mut Manifest,
+    updated: &Manifest,
+    changes: &ManifestChanges,
+) -> Result<(), ReloadManifestError> {
+    for field in &changes.needs_restart {
+        warn!("`{}` changed in the rindexer.yaml, restart rindexer to apply it", field);
+    }
+
+    let mut pipeline_contracts = dependency_contracts(running);
+    pipeline_contracts.extend(dependency_contracts(updated));
+
+    let mut to_stop = vec![];
+    let mut to_start = vec![];
+    for (contract_name, stop, start) in changes
+        .removed_contracts
+        .iter()
+        .map(|name| (name, true, false))
+        .chain(changes.changed_contracts.iter().map(|name| (name, true, true)))
+        .chain(changes.added_contracts.iter().map(|name| (name, false, true)))
+    {
+        if pipeline_contracts.contains(contract_name) {
+            warn!(
+                "{} - changed in the rindexer.yaml but is part of `dependency_events`, restart rindexer to apply it",
+                contract_name
+            );
+            continue;
+        }
+        if stop {
+            to_stop.push(contract_name);
+        }
+        if start {
+            to_start.push(contract_name);
+        }
+    }
+
+    for contract_name in &to_stop {
+        stop_contract_indexing(contract_name).await;
+    }
+    running.contracts.retain(|contract| !to_stop.contains(&&contract.name));
+
+    if to_start.is_empty() {
+        return Ok(());
+    }
+
+    let mut manifest = updated.clone();
+    manifest.contracts.retain(|contract| to_start.contains(&&contract.name));
+    let started_contracts = manifest.contracts.clone();
+
+    let mut postgres: Option<Arc<PostgresClient>> = None;
+    if manifest.storage.postgres_enabled() {
+        // only create the missing tables, never drop the data of the running contracts
+        if let Some(postgres_details) = manifest.storage.postgres.as_mut() {
+            postgres_details.drop_each_run = Some(false);
+        }
+        postgres = Some(Arc::new(setup_postgres(project_path, &manifest).await?));
+    }
+
+    let network_providers = CreateNetworkProvider::create(&manifest)?;
+    let events = process_events(project_path, &mut manifest, postgres, &network_providers).await?;
+    let registry = EventCallbackRegistry { events };
+
+    for contract in &manifest.contracts {
+        info!("{} - Starting indexing", contract.name);
+    }
+
+    running.contracts.extend(started_contracts);
+
+    let project_path = project_path.to_path_buf();
+    tokio::spawn(async move {
+        if let Err(e) =
+            start_indexing(&manifest, &project_path, &[], false, None, registry.complete()).await
+        {
+            error!("Could not start indexing the reloaded contracts: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+fn modified_at(manifest_path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(manifest_path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Watches the manifest and applies contract changes to the running indexer, contracts which did
+/// not change keep indexing
+pub async fn watch_manifest(project_path: PathBuf, manifest_path: PathBuf) {
+    let mut running = match read_manifest(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            error!("Could not read the rindexer.yaml to watch it: {}", e);
+            return;
+        }
+    };
+
+    let has_relationships = running
+        .storage
+        .postgres
+        .as_ref()
+        .and_then(|postgres| postgres.relationships.as_ref())
+        .is_some_and(|relationships| !relationships.is_empty());
+    if has_relationships {
+        warn!("Watching the rindexer.yaml is not supported with postgres relationships, changes will need a restart");
+        return;
+    }
+
+    info!("Watching the rindexer.yaml for changes");
+
+    let mut last_modified = modified_at(&manifest_path);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+
+        let modified = modified_at(&manifest_path);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        // read_manifest validates the manifest so a half written file is never applied
+        let updated = match read_manifest(&manifest_path) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                error!(
+                    "The rindexer.yaml changed but is not valid, keeping the running config: {}",
+                    e
+                );
+                continue;
+            }
+        };
+
+        let changes = ManifestChanges::between(&running, &updated);
+        if changes.is_empty() {
+            continue;
+        }
+
+        info!("The rindexer.yaml changed, applying the changes");
+        if let Err(e) = apply_changes(&project_path, &mut running, &updated, &changes).await {
+            error!("Could not apply the rindexer.yaml changes: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(yaml: &str) -> Manifest {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_changes() {
+        let running = manifest(
+            r#"
+name: Indexer
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://eth.rpc
+contracts:
+  - name: Kept
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000001"
+    abi: ./abis/kept.json
+  - name: Changed
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000002"
+    abi: ./abis/changed.json
+  - name: Removed
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000003"
+    abi: ./abis/removed.json
+"#,
+        );
+        let updated = manifest(
+            r#"
+name: Indexer
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://other.rpc
+contracts:
+  - name: Kept
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000001"
+    abi: ./abis/kept.json
+  - name: Changed
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000002"
+        start_block: "100"
+    abi: ./abis/changed.json
+  - name: Added
+    details:
+      - network: ethereum
+        address: "0x0000000000000000000000000000000000000004"
+    abi: ./abis/added.json
+"#,
+        );
+
+        assert_eq!(
+            ManifestChanges::between(&running, &updated),
+            ManifestChanges {
+                added_contracts: vec!["Added".to_string()],
+                removed_contracts: vec!["Removed".to_string()],
+                changed_contracts: vec!["Changed".to_string()],
+                needs_restart: vec!["networks".to_string()],
+            }
+        );
+        assert!(ManifestChanges::between(&running, &running).is_empty());
+    }
+}
@@ -1,4 +1,9 @@
-use ethers::types::{U256, U64};
+use ethers::{
+    providers::ProviderError,
+    types::{BlockNumber, U256, U64},
+};
+
+use crate::{manifest::network::Finality, provider::JsonRpcCachedProvider};
 
 pub fn reorg_safe_distance_for_chain(chain_id: &U256) -> U64 {
     match chain_id.as_u64() {
@@ -7,6 +12,27 @@ pub fn reorg_safe_distance_for_chain(chain_id: &U256) -> U64 {
     }
 }
 
+/// The block indexing can go up to when the latest block is `latest_block`
+pub async fn finality_head(
+    provider: &JsonRpcCachedProvider,
+    finality: Finality,
+    latest_block: U64,
+) -> Result<U64, ProviderError> {
+    let tag = match finality {
+        Finality::Latest => return Ok(latest_block),
+        Finality::Distance(distance) => return Ok(latest_block.saturating_sub(U64::from(distance))),
+        Finality::Safe => BlockNumber::Safe,
+        Finality::Finalized => BlockNumber::Finalized,
+    };
+
+    let head = provider.get_tagged_head(tag, latest_block).await?.ok_or_else(|| {
+        ProviderError::CustomError(format!("The node does not support the {} block tag", finality))
+    })?;
+
+    // load balanced nodes can be ahead of the node which returned the latest block
+    Ok(head.min(latest_block))
+}
+
 #[cfg(test)]
 mod tests {
     use ethers::types::U256;
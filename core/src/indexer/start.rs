@@ -1,32 +1,46 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
-use ethers::{providers::ProviderError, types::U64};
+use ethers::{
+    providers::ProviderError,
+    types::{Address, ValueOrArray, U256, U64},
+};
 use futures::future::try_join_all;
 use tokio::{
     sync::Semaphore,
     task::{JoinError, JoinHandle},
     time::Instant,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     database::postgres::client::PostgresConnectionError,
     event::{
-        callback_registry::EventCallbackRegistry, config::EventProcessingConfig,
-        contract_setup::NetworkContract,
+        callback_registry::EventCallbackRegistry,
+        config::EventProcessingConfig,
+        contract_setup::{IndexingContractSetup, NetworkContract},
     },
     indexer::{
+        address_source::{load_address_source, LoadAddressSourceError},
+        block_range::load_block_ranges,
         dependency::ContractEventsDependenciesConfig,
         last_synced::{get_last_synced_block_number, SyncConfig},
         process::{
-            process_contracts_events_with_dependencies, process_event,
+            process_contract_events, process_contracts_events_with_dependencies, process_event,
             ProcessContractsEventsWithDependenciesError, ProcessEventError,
         },
         progress::IndexingEventsProgressState,
-        reorg::reorg_safe_distance_for_chain,
+        reload::register_contract_task,
+        reorg::{finality_head, reorg_safe_distance_for_chain},
         ContractEventDependencies,
     },
-    manifest::core::Manifest,
+    manifest::{
+        contract::AddressSource,
+        core::Manifest,
+        network::{Finality, Network},
+    },
+    provider::RequestConcurrency,
+    rpc_cache::configure_rpc_cache,
+    streams::{StreamCheckpointError, StreamCheckpoints},
     PostgresClient,
 };
 
@@ -57,6 +71,9 @@ pub enum StartIndexingError {
     #[error("Could not get chain id from provider: {0}")]
     GetChainIdError(ProviderError),
 
+    #[error("Could not get the {1} block of {0} from provider: {2}")]
+    GetFinalityHeadError(String, Finality, ProviderError),
+
     #[error("Could not process event sequentially: {0}")]
     ProcessEventSequentiallyError(ProcessEventError),
 
@@ -68,6 +85,15 @@ pub enum StartIndexingError {
 
     #[error("The end block set for {0} is higher than the latest block: {1} - end block: {2}")]
     EndBlockIsHigherThanLatestBlockError(String, U64, U64),
+
+    #[error("The until block set for {0} is higher than the latest block: {2} - until block: {1}")]
+    UntilBlockIsHigherThanLatestBlockError(String, U64, U64),
+
+    #[error("Could not create stream checkpoints: {0}")]
+    StreamCheckpointError(#[from] StreamCheckpointError),
+
+    #[error("Could not load the contract addresses: {0}")]
+    LoadAddressSourceError(#[from] LoadAddressSourceError),
 }
 
 pub struct ProcessedNetworkContract {
@@ -80,15 +106,35 @@ pub async fn start_indexing(
     project_path: &Path,
     dependencies: &[ContractEventDependencies],
     no_live_indexing_forced: bool,
+    until_block: Option<U64>,
     registry: Arc<EventCallbackRegistry>,
 ) -> Result<Vec<ProcessedNetworkContract>, StartIndexingError> {
     let start = Instant::now();
 
     let database = initialize_database(manifest).await?;
     let event_progress_state = IndexingEventsProgressState::monitor(&registry.events).await;
-
-    // we can bring this into the yaml file later if required
-    let semaphore = Arc::new(Semaphore::new(100));
+    load_block_ranges(project_path).await;
+    configure_rpc_cache(project_path, manifest.rpc_cache.as_ref());
+
+    // each network has its own limit so a slow or rate limited network does not starve the others
+    let max_concurrent_requests = manifest.get_max_concurrent_requests();
+    let global_semaphore = Arc::new(Semaphore::new(max_concurrent_requests));
+    let network_semaphores: HashMap<String, Arc<Semaphore>> = manifest
+        .networks
+        .iter()
+        .map(|network| {
+            let network_max_concurrent_requests =
+                network.max_concurrent_requests.unwrap_or(max_concurrent_requests).max(1);
+            // the global limit caps every network so anything above it is never reached
+            if network_max_concurrent_requests > max_concurrent_requests {
+                warn!(
+                    "Network {} allows {} concurrent requests but the global max_concurrent_requests caps every network at {}, raise global.max_concurrent_requests to use them",
+                    network.name, network_max_concurrent_requests, max_concurrent_requests
+                );
+            }
+            (network.name.clone(), Arc::new(Semaphore::new(network_max_concurrent_requests)))
+        })
+        .collect();
     // need this to keep track of dependency_events cross contracts and events
     let mut event_processing_configs: Vec<Arc<EventProcessingConfig>> = vec![];
     // any events which are non-blocking and can be fired in parallel
@@ -101,19 +147,63 @@ pub async fn start_indexing(
 
     let mut processed_network_contracts: Vec<ProcessedNetworkContract> = Vec::new();
 
+    // events of contracts with `combine_get_logs` share one eth_getLogs per contract and network
+    let mut combined_get_logs_configs: HashMap<(String, String), Vec<EventProcessingConfig>> =
+        HashMap::new();
+
+    // stream only contracts checkpoint the last synced block for each sink
+    let mut contract_stream_checkpoints: HashMap<String, Arc<StreamCheckpoints>> = HashMap::new();
+
+    let mut loaded_address_sources: HashMap<AddressSource, Vec<Address>> = HashMap::new();
+
     for event in registry.events.iter() {
         let stream_details = manifest
             .contracts
             .iter()
             .find(|c| c.name == event.contract.name)
             .and_then(|c| c.streams.as_ref());
+        let stream_checkpoints = match stream_details {
+            Some(streams)
+                if database.is_none() && !manifest.contract_csv_enabled(&event.contract.name) =>
+            {
+                // the no code stream clients already hold the checkpoints of the contract
+                if let Some(checkpoints) = &event.stream_checkpoints {
+                    Some(Arc::clone(checkpoints))
+                } else if let Some(checkpoints) =
+                    contract_stream_checkpoints.get(&event.contract.name)
+                {
+                    Some(Arc::clone(checkpoints))
+                } else {
+                    let checkpoints = Arc::new(
+                        StreamCheckpoints::new(
+                            streams,
+                            project_path,
+                            &event.indexer_name,
+                            &event.contract.name,
+                        )
+                        .await?,
+                    );
+                    contract_stream_checkpoints
+                        .insert(event.contract.name.clone(), Arc::clone(&checkpoints));
+                    Some(checkpoints)
+                }
+            }
+            _ => None,
+        };
         for network_contract in event.contract.details.iter() {
+            let network_contract = &with_source_addresses(
+                network_contract,
+                project_path,
+                &database,
+                &mut loaded_address_sources,
+            )
+            .await?;
             let config = SyncConfig {
                 project_path,
                 database: &database,
                 csv_details: &manifest.storage.csv,
                 contract_csv_enabled: manifest.contract_csv_enabled(&event.contract.name),
-                stream_details: &stream_details,
+                stream_checkpoints: &stream_checkpoints,
                 indexer_name: &event.indexer_name,
                 contract_name: &event.contract.name,
                 event_name: &event.event_name,
@@ -144,6 +234,23 @@ pub async fn start_indexing(
                 }
             }
 
+            if let Some(until_block) = until_block {
+                if until_block > latest_block {
+                    error!(
+                        "{} - until block - {} {} is higher then latest block number - {}",
+                        event.info_log_name(),
+                        network_contract.network,
+                        until_block,
+                        latest_block
+                    );
+                    return Err(StartIndexingError::UntilBlockIsHigherThanLatestBlockError(
+                        event.info_log_name().to_string(),
+                        until_block,
+                        latest_block,
+                    ));
+                }
+            }
+
             let last_known_start_block = if network_contract.start_block.is_some() {
                 let last_synced_block = get_last_synced_block_number(config).await;
 
@@ -160,19 +267,39 @@ pub async fn start_indexing(
                 .unwrap_or(network_contract.start_block.unwrap_or(latest_block));
             let end_block =
                 std::cmp::min(network_contract.end_block.unwrap_or(latest_block), latest_block);
+            // a one-shot backfill stops every contract at the same block
+            let end_block = until_block.map_or(end_block, |until_block| end_block.min(until_block));
             if let Some(end_block) = network_contract.end_block {
                 if end_block > latest_block {
                     error!("{} - end_block supplied in yaml - {} is higher then latest - {} - end_block now will be {}", event.info_log_name(), end_block, latest_block, latest_block);
                 }
             }
 
-            let (end_block, indexing_distance_from_head) = calculate_safe_block_number(
-                event.contract.reorg_safe_distance,
-                network_contract,
-                latest_block,
+            let network =
+                manifest.networks.iter().find(|network| network.name == network_contract.network);
+            let finality =
+                contract_finality(event.contract.reorg_safe_distance, network, network_contract)
+                    .await?;
+            let end_block =
+                calculate_safe_block_number(finality, network_contract, latest_block, end_block)
+                    .await?;
+
+            event_progress_state.lock().await.set_sync_range(
+                &network_contract.id,
+                finality,
+                start_block,
                 end_block,
-            )
-            .await?;
+            );
+
+            // when the indexer follows the latest block listeners of the indexed events still need
+            // to know how far behind the head events are safe
+            let reorg_safe_distance = if finality == Finality::Latest {
+                network
+                    .map(|network| reorg_safe_distance_for_chain(&U256::from(network.chain_id)))
+                    .unwrap_or_default()
+            } else {
+                U64::zero()
+            };
 
             // push status to the processed state
             processed_network_contracts.push(ProcessedNetworkContract {
@@ -187,25 +314,36 @@ pub async fn start_indexing(
                 contract_name: event.contract.name.clone(),
                 info_log_name: event.info_log_name(),
                 topic_id: event.topic_id,
+                combined_topic_ids: vec![],
                 event_name: event.event_name.clone(),
                 network_contract: Arc::new(network_contract.clone()),
                 start_block,
                 end_block,
-                semaphore: Arc::clone(&semaphore),
+                request_concurrency: RequestConcurrency::new(
+                    network_semaphores
+                        .get(&network_contract.network)
+                        .cloned()
+                        .unwrap_or_else(|| Arc::new(Semaphore::new(max_concurrent_requests))),
+                    Arc::clone(&global_semaphore),
+                ),
                 registry: Arc::clone(&registry),
                 progress: Arc::clone(&event_progress_state),
                 database: database.clone(),
                 csv_details: manifest.storage.csv.clone(),
-                stream_last_synced_block_file_path: stream_details
-                    .as_ref()
-                    .map(|s| s.get_streams_last_synced_block_path()),
+                stream_checkpoints: stream_checkpoints.clone(),
                 live_indexing: if no_live_indexing_forced {
                     false
                 } else {
                     network_contract.is_live_indexing()
                 },
                 index_event_in_order: event.index_event_in_order,
-                indexing_distance_from_head,
+                historic_segments: event.contract.historic_segments,
+                log_buffer_size: event.contract.log_buffer_size,
+                callbacks_in_flight: Arc::new(Semaphore::new(
+                    event.contract.log_buffer_size.max(1),
+                )),
+                finality,
+                reorg_safe_distance,
             };
 
             let dependencies_status = ContractEventDependencies::dependencies_status(
@@ -237,13 +375,31 @@ pub async fn start_indexing(
                     event_processing_config_arc,
                     dependencies,
                 );
+            } else if event.contract.combine_get_logs &&
+                network_contract.indexing_contract_setup.can_combine_get_logs()
+            {
+                combined_get_logs_configs
+                    .entry((event.contract.name.clone(), network_contract.network.clone()))
+                    .or_default()
+                    .push(event_processing_config);
             } else {
                 let process_event = tokio::spawn(process_event(event_processing_config));
+                register_contract_task(&event.contract.name, process_event.abort_handle());
                 non_blocking_process_events.push(process_event);
             }
         }
     }
 
+    for ((contract_name, _), mut configs) in combined_get_logs_configs {
+        let process_events = if configs.len() == 1 {
+            tokio::spawn(process_event(configs.remove(0)))
+        } else {
+            tokio::spawn(process_contract_events(configs))
+        };
+        register_contract_task(&contract_name, process_events.abort_handle());
+        non_blocking_process_events.push(process_events);
+    }
+
     // apply dependency events config after processing to avoid ordering issues
     for apply in apply_cross_contract_dependency_events_config_after_processing {
         let (dependency_in_other_contract, event_processing_config) = apply;
@@ -271,10 +427,13 @@ pub async fn start_indexing(
 
     for handle in non_blocking_process_events {
         handles.push(tokio::spawn(async {
-            handle
-                .await
-                .map_err(CombinedLogEventProcessingError::from)
-                .and_then(|res| res.map_err(CombinedLogEventProcessingError::from))
+            match handle.await {
+                // stopped as the contract was removed or changed in the manifest
+                Err(e) if e.is_cancelled() => Ok(()),
+                result => result
+                    .map_err(CombinedLogEventProcessingError::from)
+                    .and_then(|res| res.map_err(CombinedLogEventProcessingError::from)),
+            }
         }));
     }
 
@@ -294,6 +453,37 @@ pub async fn start_indexing(
     Ok(processed_network_contracts)
 }
 
+/// Loads the addresses of contracts using an `address_source`, the events of a contract share the
+/// addresses loaded for the first event
+async fn with_source_addresses(
+    network_contract: &NetworkContract,
+    project_path: &Path,
+    database: &Option<Arc<PostgresClient>>,
+    loaded: &mut HashMap<AddressSource, Vec<Address>>,
+) -> Result<NetworkContract, StartIndexingError> {
+    let mut network_contract = network_contract.clone();
+    let IndexingContractSetup::Address(details) = &mut network_contract.indexing_contract_setup
+    else {
+        return Ok(network_contract);
+    };
+    let Some(source) = details.address_source.clone() else {
+        return Ok(network_contract);
+    };
+
+    let addresses = match loaded.get(&source) {
+        Some(addresses) => addresses.clone(),
+        None => {
+            let addresses = load_address_source(&source, project_path, database.as_ref()).await?;
+            info!("Loaded {} contract addresses from the {}", addresses.len(), source);
+            loaded.insert(source, addresses.clone());
+            addresses
+        }
+    };
+    details.address = ValueOrArray::Array(addresses);
+
+    Ok(network_contract)
+}
+
 async fn initialize_database(
     manifest: &Manifest,
 ) -> Result<Option<Arc<PostgresClient>>, StartIndexingError> {
@@ -310,25 +500,42 @@ async fn initialize_database(
     }
 }
 
-async fn calculate_safe_block_number(
+/// The finality of the network wins over the reorg safe distance of the contract
+async fn contract_finality(
     reorg_safe_distance: bool,
+    network: Option<&Network>,
     network_contract: &NetworkContract,
-    latest_block: U64,
-    mut end_block: U64,
-) -> Result<(U64, U64), StartIndexingError> {
-    let mut indexing_distance_from_head = U64::zero();
-    if reorg_safe_distance {
-        let chain_id = network_contract
-            .cached_provider
-            .get_chain_id()
-            .await
-            .map_err(StartIndexingError::GetChainIdError)?;
-        let reorg_safe_distance = reorg_safe_distance_for_chain(&chain_id);
-        let safe_block_number = latest_block - reorg_safe_distance;
-        if end_block > safe_block_number {
-            end_block = safe_block_number;
-        }
-        indexing_distance_from_head = reorg_safe_distance;
+) -> Result<Finality, StartIndexingError> {
+    if let Some(finality) = network.and_then(|network| network.finality) {
+        return Ok(finality);
+    }
+
+    if !reorg_safe_distance {
+        return Ok(Finality::Latest);
     }
-    Ok((end_block, indexing_distance_from_head))
+
+    let chain_id = network_contract
+        .cached_provider
+        .get_chain_id()
+        .await
+        .map_err(StartIndexingError::GetChainIdError)?;
+    Ok(Finality::Distance(reorg_safe_distance_for_chain(&chain_id).as_u64()))
+}
+
+async fn calculate_safe_block_number(
+    finality: Finality,
+    network_contract: &NetworkContract,
+    latest_block: U64,
+    end_block: U64,
+) -> Result<U64, StartIndexingError> {
+    let safe_block_number = finality_head(
+        &network_contract.cached_provider,
+        finality,
+        latest_block,
+    )
+    .await
+    .map_err(|e| {
+        StartIndexingError::GetFinalityHeadError(network_contract.network.clone(), finality, e)
+    })?;
+    Ok(end_block.min(safe_block_number))
 }
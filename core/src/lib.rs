@@ -18,16 +18,20 @@ pub use helpers::{
     write_file, WriteFileError,
 };
 mod api;
-pub use api::{generate_graphql_queries, GraphqlOverrideSettings};
+pub use api::{generate_graphql_queries, GraphqlOverrideSettings, RestOverrideSettings};
 
+mod health;
 mod logger;
-pub use logger::setup_info_logger;
+mod metrics;
+pub use logger::{setup_info_logger, setup_info_logger_with_telemetry};
 mod abi;
 pub use abi::ABIItem;
 mod chat;
 pub mod event;
 pub mod phantom;
 pub mod provider;
+pub mod rpc_cache;
+mod shutdown;
 mod start;
 mod streams;
 mod types;
@@ -39,8 +43,8 @@ pub use futures::FutureExt;
 pub use lazy_static::lazy_static;
 pub use reqwest::header::HeaderMap;
 pub use start::{
-    start_rindexer, start_rindexer_no_code, IndexerNoCodeDetails, IndexingDetails, StartDetails,
-    StartNoCodeDetails,
+    start_rindexer, start_rindexer_no_code, IndexUntil, IndexerNoCodeDetails, IndexingDetails,
+    StartDetails, StartNoCodeDetails,
 };
 pub use tokio::main as rindexer_main;
 pub use tokio_postgres::types::Type as PgType;
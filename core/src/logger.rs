@@ -1,7 +1,32 @@
-use tracing::{debug, level_filters::LevelFilter};
+use std::{env, fmt, sync::Mutex};
+
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use serde_json::{Map, Value};
+use tracing::{
+    debug, error,
+    field::{Field, Visit},
+    info,
+    level_filters::LevelFilter,
+    span, Event, Subscriber,
+};
 use tracing_subscriber::{
-    fmt::format::{Format, Writer},
-    EnvFilter,
+    fmt::{
+        format::{Format, Writer},
+        FmtContext, FormatEvent, FormatFields,
+    },
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    EnvFilter, Layer,
+};
+
+use crate::{
+    lazy_static,
+    manifest::{
+        core::{LogFormat, Manifest},
+        telemetry::OtlpProtocol,
+    },
 };
 
 struct CustomTimer;
@@ -13,23 +38,279 @@ impl tracing_subscriber::fmt::time::FormatTime for CustomTimer {
     }
 }
 
-pub fn setup_logger(log_level: LevelFilter) {
+/// The fields every json log line has, null when the log is not about an indexing event
+const JSON_LOG_CONTEXT_FIELDS: [&str; 6] =
+    ["contract", "event", "network", "from_block", "to_block", "status"];
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::from(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+}
+
+struct SpanFields(Map<String, Value>);
+
+/// Keeps the recorded fields of each span so the json log lines can include them
+struct SpanFieldsLayer;
+
+impl<S> Layer<S> for SpanFieldsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(&mut JsonVisitor(&mut fields.0));
+            }
+        }
+    }
+}
+
+/// Writes each event as a single json line with the fields of the spans it is in
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Value::from(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true)),
+        );
+        line.insert("level".to_string(), Value::from(event.metadata().level().as_str()));
+        for field in JSON_LOG_CONTEXT_FIELDS {
+            line.insert(field.to_string(), Value::Null);
+        }
+
+        // the innermost span wins when spans record the same field
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    line.extend(fields.0.clone());
+                }
+            }
+        }
+
+        event.record(&mut JsonVisitor(&mut line));
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+lazy_static! {
+    static ref TRACER_PROVIDER: Mutex<Option<TracerProvider>> = Mutex::new(None);
+}
+
+/// The `RINDEXER_LOG_FORMAT` environment variable (set by `--log-format`) wins over the manifest
+fn resolve_log_format(manifest_format: Option<LogFormat>) -> LogFormat {
+    match env::var("RINDEXER_LOG_FORMAT").map(|format| format.to_lowercase()).as_deref() {
+        Ok("json") => LogFormat::Json,
+        Ok("text") => LogFormat::Text,
+        _ => manifest_format.unwrap_or_default(),
+    }
+}
+
+fn setup_logger_with_tracer_provider(
+    log_level: LevelFilter,
+    log_format: LogFormat,
+    provider: Option<TracerProvider>,
+) {
     let filter = EnvFilter::from_default_env().add_directive(log_level.into());
 
-    let format = Format::default().with_timer(CustomTimer).with_level(true).with_target(false);
+    let (text_layer, json_layer) = match log_format {
+        LogFormat::Text => {
+            let format =
+                Format::default().with_timer(CustomTimer).with_level(true).with_target(false);
+            (Some(tracing_subscriber::fmt::layer().event_format(format)), None)
+        }
+        LogFormat::Json => {
+            // log pipelines can not parse the ansi colours
+            colored::control::set_override(false);
+            (
+                None,
+                Some(
+                    SpanFieldsLayer
+                        .and_then(tracing_subscriber::fmt::layer().event_format(JsonFormat)),
+                ),
+            )
+        }
+    };
+
+    let telemetry = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    });
 
-    let subscriber =
-        tracing_subscriber::fmt().with_env_filter(filter).event_format(format).finish();
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(text_layer)
+        .with(json_layer)
+        .with(telemetry);
 
     if tracing::subscriber::set_global_default(subscriber).is_err() {
         debug!("Logger has already been set up, continuing...");
+        return;
+    }
+
+    if let Ok(mut tracer_provider) = TRACER_PROVIDER.lock() {
+        *tracer_provider = provider;
     }
 }
 
+pub fn setup_logger(log_level: LevelFilter) {
+    setup_logger_with_tracer_provider(log_level, resolve_log_format(None), None);
+}
+
 pub fn setup_info_logger() {
     setup_logger(LevelFilter::INFO);
 }
 
+fn env_is_set(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| !value.is_empty())
+}
+
+/// The protocol from the manifest or the standard OTLP environment variables, grpc by default
+fn otlp_protocol(manifest_protocol: Option<OtlpProtocol>) -> OtlpProtocol {
+    if let Some(protocol) = manifest_protocol {
+        return protocol;
+    }
+
+    let protocol = env::var("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL")
+        .or_else(|_| env::var("OTEL_EXPORTER_OTLP_PROTOCOL"))
+        .unwrap_or_default();
+    if protocol.starts_with("http") {
+        OtlpProtocol::Http
+    } else {
+        OtlpProtocol::Grpc
+    }
+}
+
+/// Builds the OTLP tracer provider if the manifest `telemetry` section or the standard
+/// `OTEL_EXPORTER_OTLP_*` environment variables turn it on
+fn telemetry_tracer_provider(
+    manifest: &Manifest,
+) -> Result<Option<TracerProvider>, opentelemetry::trace::TraceError> {
+    if env::var("OTEL_SDK_DISABLED").is_ok_and(|disabled| disabled == "true") {
+        return Ok(None);
+    }
+
+    let settings = manifest.telemetry.clone();
+    let enabled = match &settings {
+        Some(settings) => settings.enabled,
+        None => {
+            env_is_set("OTEL_EXPORTER_OTLP_ENDPOINT") ||
+                env_is_set("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT")
+        }
+    };
+    if !enabled {
+        return Ok(None);
+    }
+
+    let endpoint = settings.as_ref().and_then(|settings| settings.endpoint.clone());
+    let exporter = match otlp_protocol(settings.as_ref().and_then(|settings| settings.protocol)) {
+        OtlpProtocol::Grpc => {
+            let mut builder = SpanExporter::builder().with_tonic();
+            if let Some(endpoint) = endpoint {
+                builder = builder.with_endpoint(endpoint);
+            }
+            builder.build()?
+        }
+        OtlpProtocol::Http => {
+            let mut builder = SpanExporter::builder().with_http();
+            if let Some(endpoint) = endpoint {
+                // the http exporter expects the full traces url
+                let endpoint = if endpoint.ends_with("/v1/traces") {
+                    endpoint
+                } else {
+                    format!("{}/v1/traces", endpoint.trim_end_matches('/'))
+                };
+                builder = builder.with_endpoint(endpoint);
+            }
+            builder.build()?
+        }
+    };
+
+    let service_name = env::var("OTEL_SERVICE_NAME")
+        .ok()
+        .or_else(|| settings.and_then(|settings| settings.service_name))
+        .unwrap_or_else(|| manifest.name.clone());
+
+    Ok(Some(
+        TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(
+                Resource::default()
+                    .merge(&Resource::new([KeyValue::new("service.name", service_name)])),
+            )
+            .build(),
+    ))
+}
+
+/// Sets up the info logger in the manifest log format and exports the spans over OTLP if
+/// telemetry is turned on
+pub fn setup_info_logger_with_telemetry(manifest: &Manifest) {
+    if tracing::dispatcher::has_been_set() {
+        debug!("Logger has already been set up, continuing...");
+        return;
+    }
+
+    let log_format = resolve_log_format(manifest.log_format);
+    match telemetry_tracer_provider(manifest) {
+        Ok(Some(provider)) => {
+            setup_logger_with_tracer_provider(LevelFilter::INFO, log_format, Some(provider));
+            info!("Exporting traces over OTLP");
+        }
+        Ok(None) => setup_logger_with_tracer_provider(LevelFilter::INFO, log_format, None),
+        Err(e) => {
+            setup_logger_with_tracer_provider(LevelFilter::INFO, log_format, None);
+            error!("Could not set up the OTLP trace exporter, traces will not be exported: {}", e);
+        }
+    }
+}
+
+/// Flushes any spans which have not been exported yet
+pub fn shutdown_telemetry() {
+    let provider = TRACER_PROVIDER.lock().ok().and_then(|mut provider| provider.take());
+    if let Some(provider) = provider {
+        if let Err(e) = provider.shutdown() {
+            error!("Could not flush the OTLP traces: {}", e);
+        }
+    }
+}
+
 // pub fn set_no_op_logger() -> DefaultGuard {
 //     let no_op_subscriber = FmtSubscriber::builder().with_writer(|| NullWriter).finish();
 //
@@ -37,3 +318,122 @@ pub fn setup_info_logger() {
 //
 //     tracing::dispatcher::set_default(&no_op_dispatch)
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::{io, sync::Arc};
+
+    use tracing::info_span;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(SpanFieldsLayer.and_then(
+            tracing_subscriber::fmt::layer().event_format(JsonFormat).with_writer(buffer.clone()),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("starting");
+            let span = info_span!(
+                "indexing",
+                contract = "RocketPoolETH",
+                event = "Transfer",
+                network = "ethereum",
+                status = "SYNCING"
+            );
+            let _entered = span.enter();
+            info_span!("index_logs", from_block = 1u64, to_block = 10u64)
+                .in_scope(|| info!("{} events", 2));
+            span.record("status", "LIVE");
+            error!("new block");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> =
+            output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+
+        assert_eq!(lines[0]["message"], "starting");
+        assert_eq!(lines[0]["level"], "INFO");
+        assert!(lines[0]["timestamp"].is_string());
+        for field in JSON_LOG_CONTEXT_FIELDS {
+            assert!(lines[0][field].is_null());
+        }
+
+        assert_eq!(lines[1]["message"], "2 events");
+        assert_eq!(lines[1]["contract"], "RocketPoolETH");
+        assert_eq!(lines[1]["from_block"], 1);
+        assert_eq!(lines[1]["to_block"], 10);
+        assert_eq!(lines[1]["status"], "SYNCING");
+
+        assert_eq!(lines[2]["level"], "ERROR");
+        assert_eq!(lines[2]["status"], "LIVE");
+        assert!(lines[2]["from_block"].is_null());
+    }
+
+    fn manifest(telemetry: &str) -> Manifest {
+        serde_yaml::from_str(&format!(
+            "name: Indexer\nproject_type: no-code\nnetworks: []\ncontracts: []\n{}",
+            telemetry
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_telemetry_config() {
+        let settings = manifest(
+            "telemetry:\n  endpoint: http://collector:4318\n  protocol: http\n  service_name: vaults",
+        )
+        .telemetry
+        .unwrap();
+        assert!(settings.enabled);
+        assert_eq!(settings.endpoint.as_deref(), Some("http://collector:4318"));
+        assert_eq!(settings.protocol, Some(OtlpProtocol::Http));
+        assert_eq!(settings.service_name.as_deref(), Some("vaults"));
+        assert_eq!(otlp_protocol(settings.protocol), OtlpProtocol::Http);
+
+        let settings = manifest("telemetry:\n  enabled: false").telemetry.unwrap();
+        assert!(!settings.enabled);
+        assert_eq!(settings.protocol, None);
+
+        assert!(serde_yaml::from_str::<Manifest>(
+            "name: Indexer\nproject_type: no-code\nnetworks: []\ncontracts: []\ntelemetry:\n  protocol: udp",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_disabled_telemetry_has_no_exporter() {
+        let manifest = manifest("telemetry:\n  enabled: false\n  endpoint: http://collector:4317");
+        assert!(telemetry_tracer_provider(&manifest).unwrap().is_none());
+
+        // nothing was exported so there is nothing to flush
+        shutdown_telemetry();
+        assert!(TRACER_PROVIDER.lock().unwrap().is_none());
+    }
+}
@@ -1,12 +1,21 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
+use crate::{
+    chat::{Template, TemplateContext, MAX_DECIMALS},
+    event::validate_conditions,
+    manifest::network::Network,
+};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TelegramConfig {
     pub bot_token: String,
     pub chat_id: i64,
     pub networks: Vec<String>,
     pub messages: Vec<TelegramEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,6 +34,9 @@ pub struct DiscordConfig {
     pub channel_id: u64,
     pub networks: Vec<String>,
     pub messages: Vec<DiscordEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,6 +55,9 @@ pub struct SlackConfig {
     pub channel: String,
     pub networks: Vec<String>,
     pub messages: Vec<SlackEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -55,8 +70,191 @@ pub struct SlackEvent {
     pub template_inline: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TeamsConfig {
+    pub webhook_url: String,
+    pub networks: Vec<String>,
+    pub messages: Vec<TeamsEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TeamsEvent {
+    pub event_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<Map<String, Value>>>,
+
+    pub template_inline: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatrixConfig {
+    pub homeserver_url: String,
+    pub access_token: String,
+    pub room_id: String,
+    pub networks: Vec<String>,
+    pub messages: Vec<MatrixEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatrixEvent {
+    pub event_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<Map<String, Value>>>,
+
+    pub template_inline: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Implicit TLS usually on port 465
+    Tls,
+    /// Upgrade the connection with STARTTLS usually on port 587
+    #[default]
+    Starttls,
+    /// No encryption, only use this for local SMTP servers
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmailConfig {
+    pub host: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    #[serde(default)]
+    pub tls: SmtpTls,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    pub from: String,
+    pub to: Vec<String>,
+    pub networks: Vec<String>,
+    pub messages: Vec<EmailEvent>,
+
+    #[serde(flatten)]
+    pub delivery: ChatDeliveryConfig,
+}
+
+impl EmailConfig {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.tls {
+            SmtpTls::Tls => 465,
+            SmtpTls::Starttls => 587,
+            SmtpTls::None => 25,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmailEvent {
+    pub event_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<Map<String, Value>>>,
+
+    pub subject: String,
+
+    pub template_inline: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatRateLimitConfig {
+    pub max_messages: u32,
+    pub window_seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatDedupeConfig {
+    /// A template rendered with the event data, events with the same key are only sent once
+    pub key: String,
+    pub window_seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatDigestConfig {
+    pub window_seconds: u64,
+    pub template_inline: String,
+
+    /// Only used by email
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+}
+
+/// Controls how many messages a chat target sends
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ChatDeliveryConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<ChatRateLimitConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedupe: Option<ChatDedupeConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<ChatDigestConfig>,
+}
+
+impl ChatDeliveryConfig {
+    fn validate(&self, context: &TemplateContext, networks: &[String]) -> Result<(), String> {
+        if let Some(rate_limit) = &self.rate_limit {
+            if rate_limit.max_messages == 0 || rate_limit.window_seconds == 0 {
+                return Err("rate_limit max_messages and window_seconds must be above 0".to_string());
+            }
+        }
+
+        if let Some(dedupe) = &self.dedupe {
+            if dedupe.window_seconds == 0 {
+                return Err("dedupe window_seconds must be above 0".to_string());
+            }
+            Template::validate(&dedupe.key, context, networks)
+                .map_err(|e| format!("dedupe key - {}", e))?;
+        }
+
+        if let Some(digest) = &self.digest {
+            if digest.window_seconds == 0 {
+                return Err("digest window_seconds must be above 0".to_string());
+            }
+            for template in digest.subject.iter().chain(std::iter::once(&digest.template_inline)) {
+                Template::validate(template, context, networks)
+                    .map_err(|e| format!("digest template - {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The token the contract represents, used by the `token_units` template helper
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatTokenConfig {
+    pub decimals: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<ChatTokenConfig>,
+
+    /// Send messages for events found while indexing historic blocks, off by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_historic: Option<bool>,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub telegram: Option<Vec<TelegramConfig>>,
 
@@ -65,4 +263,106 @@ pub struct ChatConfig {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub slack: Option<Vec<SlackConfig>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teams: Option<Vec<TeamsConfig>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Vec<MatrixConfig>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<Vec<EmailConfig>>,
+}
+
+// a message defined on any of the chat targets used for validation
+struct ChatMessage<'a> {
+    networks: &'a [String],
+    event_name: &'a str,
+    conditions: &'a Option<Vec<Map<String, Value>>>,
+    templates: Vec<&'a str>,
+}
+
+macro_rules! chat_messages {
+    ($configs:expr) => {
+        $configs.iter().flatten().flat_map(|config| {
+            config.messages.iter().map(|message| ChatMessage {
+                networks: &config.networks,
+                event_name: &message.event_name,
+                conditions: &message.conditions,
+                templates: vec![&message.template_inline],
+            })
+        })
+    };
+}
+
+macro_rules! chat_deliveries {
+    ($configs:expr) => {
+        $configs.iter().flatten().map(|config| (config.networks.as_slice(), &config.delivery))
+    };
+}
+
+impl ChatConfig {
+    pub fn send_historic(&self) -> bool {
+        self.send_historic.unwrap_or(false)
+    }
+
+    fn messages(&self) -> Vec<ChatMessage<'_>> {
+        let email = self.email.iter().flatten().flat_map(|config| {
+            config.messages.iter().map(|message| ChatMessage {
+                networks: &config.networks,
+                event_name: &message.event_name,
+                conditions: &message.conditions,
+                templates: vec![&message.subject, &message.template_inline],
+            })
+        });
+
+        chat_messages!(self.telegram)
+            .chain(chat_messages!(self.discord))
+            .chain(chat_messages!(self.slack))
+            .chain(chat_messages!(self.teams))
+            .chain(chat_messages!(self.matrix))
+            .chain(email)
+            .collect()
+    }
+
+    pub fn validate(&self, networks: &[Network]) -> Result<(), String> {
+        let context = TemplateContext::new(self.token.clone(), networks);
+
+        if let Some(token) = self.token.as_ref().filter(|token| token.decimals > MAX_DECIMALS) {
+            return Err(format!(
+                "Chat token has {} decimals but the most a uint256 can have is {}",
+                token.decimals, MAX_DECIMALS
+            ));
+        }
+
+        for email in self.email.iter().flatten() {
+            if email.to.is_empty() {
+                return Err(format!("Email config from {} has no `to` addresses", email.from));
+            }
+        }
+
+        let deliveries = chat_deliveries!(self.telegram)
+            .chain(chat_deliveries!(self.discord))
+            .chain(chat_deliveries!(self.slack))
+            .chain(chat_deliveries!(self.teams))
+            .chain(chat_deliveries!(self.matrix))
+            .chain(chat_deliveries!(self.email));
+        for (networks, delivery) in deliveries {
+            delivery.validate(&context, networks)?;
+        }
+
+        for message in self.messages() {
+            if let Some(conditions) = message.conditions {
+                validate_conditions(conditions)
+                    .map_err(|e| format!("Event {} - {}", message.event_name, e))?;
+            }
+
+            for template in message.templates {
+                Template::validate(template, &context, message.networks)
+                    .map_err(|e| format!("Event {} - {}", message.event_name, e))?;
+            }
+        }
+
+        Ok(())
+    }
 }
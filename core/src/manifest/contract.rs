@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, fmt};
 
 use ethers::{
     addressbook::Address,
@@ -6,7 +6,10 @@ use ethers::{
 };
 use serde::{Deserialize, Serialize};
 
-use super::core::{deserialize_option_u64_from_string, serialize_option_u64_as_string};
+use super::core::{
+    deserialize_option_u64_from_string, deserialize_u64_from_string,
+    serialize_option_u64_as_string, serialize_u64_as_string,
+};
 use crate::{
     event::contract_setup::{
         AddressDetails, ContractEventMapping, FilterDetails, IndexingContractSetup,
@@ -49,6 +52,52 @@ pub struct FilterDetailsYaml {
     pub event_name: String,
 }
 
+/// Logs exported ahead of time by tools like cryo, read instead of calling `eth_getLogs`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistoricSource {
+    /// A parquet or JSONL file of logs or a directory of them read in file name order, relative
+    /// paths are from the project
+    pub path: String,
+
+    /// The files hold every log of the contract up to this block, RPC takes over after it
+    #[serde(
+        deserialize_with = "deserialize_u64_from_string",
+        serialize_with = "serialize_u64_as_string"
+    )]
+    pub end_block: U64,
+}
+
+/// Where the addresses of a contract are loaded from when indexing starts, for lists too long to
+/// keep in the yaml
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum AddressSource {
+    /// A file with an address on each line or a JSON array of addresses
+    File { file: String },
+
+    /// A CSV file with a header, the addresses are in `column` which defaults to `address`
+    Csv {
+        csv: String,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        column: Option<String>,
+    },
+
+    /// A query against the postgres database of the indexer, the first column of each row is an
+    /// address as text or bytea
+    Sql { sql: String },
+}
+
+impl fmt::Display for AddressSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressSource::File { file } => write!(f, "file {}", file),
+            AddressSource::Csv { csv, .. } => write!(f, "CSV {}", csv),
+            AddressSource::Sql { sql } => write!(f, "SQL query `{}`", sql),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ContractDetails {
     pub network: String,
@@ -56,6 +105,9 @@ pub struct ContractDetails {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     address: Option<ValueOrArray<Address>>,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_source: Option<AddressSource>,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub filter: Option<FilterDetailsYaml>,
 
@@ -79,6 +131,9 @@ pub struct ContractDetails {
         serialize_with = "serialize_option_u64_as_string"
     )]
     pub end_block: Option<U64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub historic_source: Option<HistoricSource>,
 }
 
 impl ContractDetails {
@@ -87,6 +142,14 @@ impl ContractDetails {
             IndexingContractSetup::Address(AddressDetails {
                 address: address.clone(),
                 indexed_filters: self.indexed_filters.clone(),
+                address_source: None,
+            })
+        } else if let Some(address_source) = &self.address_source {
+            // the addresses are loaded when indexing starts
+            IndexingContractSetup::Address(AddressDetails {
+                address: ValueOrArray::Array(vec![]),
+                indexed_filters: self.indexed_filters.clone(),
+                address_source: Some(address_source.clone()),
             })
             // } else if let Some(factory) = &self.factory {
             //     IndexingContractSetup::Factory(factory.clone())
@@ -120,11 +183,13 @@ impl ContractDetails {
         Self {
             network,
             address: Some(address),
+            address_source: None,
             filter: None,
             indexed_filters,
             //factory: None,
             start_block,
             end_block,
+            historic_source: None,
         }
     }
 
@@ -138,11 +203,13 @@ impl ContractDetails {
         Self {
             network,
             address: None,
+            address_source: None,
             filter: Some(filter),
             indexed_filters,
             //factory: None,
             start_block,
             end_block,
+            historic_source: None,
         }
     }
 
@@ -214,6 +281,15 @@ pub struct Contract {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub index_event_in_order: Option<Vec<String>>,
 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub historic_segments: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub combine_get_logs: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_buffer_size: Option<usize>,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dependency_events: Option<DependencyEventTreeYaml>,
 
@@ -230,11 +306,18 @@ pub struct Contract {
     pub chat: Option<ChatConfig>,
 }
 
+/// How many fetched block ranges of logs each event holds while the callbacks catch up
+const DEFAULT_LOG_BUFFER_SIZE: usize = 10;
+
 impl Contract {
     pub fn override_name(&mut self, name: String) {
         self.name = name;
     }
 
+    pub fn get_log_buffer_size(&self) -> usize {
+        self.log_buffer_size.unwrap_or(DEFAULT_LOG_BUFFER_SIZE)
+    }
+
     pub fn convert_dependency_event_tree_yaml(
         &self,
         yaml: DependencyEventTreeYaml,
@@ -5,8 +5,9 @@ use serde_yaml::Value;
 use crate::{
     indexer::Indexer,
     manifest::{
-        contract::Contract, global::Global, graphql::GraphQLSettings, network::Network,
-        phantom::Phantom, storage::Storage,
+        contract::Contract, global::Global, graphql::GraphQLSettings, health::HealthSettings,
+        network::Network, phantom::Phantom, rest::RestSettings, rpc_cache::RpcCacheSettings,
+        storage::Storage, telemetry::TelemetrySettings,
     },
 };
 
@@ -43,6 +44,18 @@ pub enum ProjectType {
     NoCode,
 }
 
+/// How the log lines are written, json is meant for log pipelines
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// RPC requests in flight across every network when `global.max_concurrent_requests` is not set
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 100;
+
 fn default_storage() -> Storage {
     Storage::default()
 }
@@ -76,6 +89,21 @@ pub struct Manifest {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub graphql: Option<GraphQLSettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest: Option<RestSettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<HealthSettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetrySettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_cache: Option<RpcCacheSettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_format: Option<LogFormat>,
 }
 
 impl Manifest {
@@ -83,6 +111,15 @@ impl Manifest {
         Indexer { name: self.name.clone(), contracts: self.contracts.clone() }
     }
 
+    /// The RPC requests in flight across every network
+    pub fn get_max_concurrent_requests(&self) -> usize {
+        self.global
+            .as_ref()
+            .and_then(|global| global.max_concurrent_requests)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS)
+            .max(1)
+    }
+
     pub fn has_any_contracts_live_indexing(&self) -> bool {
         self.contracts.iter().filter(|c| c.details.iter().any(|p| p.end_block.is_none())).count() >
             0
@@ -120,6 +157,21 @@ where
     }
 }
 
+pub fn deserialize_u64_from_string<'de, D>(deserializer: D) -> Result<U64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    U64::from_dec_str(&s).map_err(serde::de::Error::custom)
+}
+
+pub fn serialize_u64_as_string<S>(value: &U64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.as_u64().to_string())
+}
+
 pub fn serialize_option_u64_as_string<S>(
     value: &Option<U64>,
     serializer: S,
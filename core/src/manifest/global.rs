@@ -9,4 +9,7 @@ pub struct Global {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub etherscan_api_key: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_requests: Option<usize>,
 }
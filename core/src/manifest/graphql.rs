@@ -4,6 +4,14 @@ fn default_port() -> u16 {
     3001
 }
 
+fn default_page_limit() -> u64 {
+    1000
+}
+
+fn default_timeout() -> u64 {
+    10000
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GraphQLSettings {
     #[serde(default = "default_port")]
@@ -14,11 +22,25 @@ pub struct GraphQLSettings {
 
     #[serde(default)]
     pub filter_only_on_indexed_columns: bool,
+
+    /// The maximum number of rows a single page can return
+    #[serde(default = "default_page_limit")]
+    pub page_limit: u64,
+
+    /// How long a query can run for in milliseconds before it is cancelled
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
 }
 
 impl Default for GraphQLSettings {
     fn default() -> Self {
-        Self { port: 3001, disable_advanced_filters: false, filter_only_on_indexed_columns: false }
+        Self {
+            port: default_port(),
+            disable_advanced_filters: false,
+            filter_only_on_indexed_columns: false,
+            page_limit: default_page_limit(),
+            timeout: default_timeout(),
+        }
     }
 }
 
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HealthSettings {
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// How many blocks an event can be behind the block it is syncing to and still be ready
+    #[serde(default)]
+    pub ready_max_blocks_behind: u64,
+
+    /// How many blocks a live network can be behind the chain head and still be ready
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_max_head_lag: Option<u64>,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self { port: default_port(), ready_max_blocks_behind: 0, ready_max_head_lag: None }
+    }
+}
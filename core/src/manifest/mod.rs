@@ -3,8 +3,12 @@ pub mod contract;
 pub mod core;
 pub mod global;
 pub mod graphql;
+pub mod health;
 pub mod network;
 pub mod phantom;
+pub mod rest;
+pub mod rpc_cache;
 pub mod storage;
 pub mod stream;
+pub mod telemetry;
 pub mod yaml;
@@ -1,5 +1,7 @@
+use std::{fmt, str::FromStr};
+
 use ethers::prelude::U64;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::core::{deserialize_option_u64_from_string, serialize_option_u64_as_string};
 
@@ -24,4 +26,128 @@ pub struct Network {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disable_logs_bloom_checks: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_requests: Option<usize>,
+
+    /// The head of the chain indexing follows, defaults to the reorg safe distance of the
+    /// contracts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality: Option<Finality>,
+
+    /// Base url of the block explorer used in chat templates, well known chains have defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_explorer: Option<String>,
+}
+
+/// The head of the chain indexing follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finality {
+    Latest,
+    /// The `safe` block tag of the node
+    Safe,
+    /// The `finalized` block tag of the node
+    Finalized,
+    /// Stays this many blocks behind the latest block
+    Distance(u64),
+}
+
+impl Finality {
+    /// The head in words for the logs
+    pub fn describe(&self) -> String {
+        match self {
+            Finality::Distance(distance) => format!("latest block - {} blocks", distance),
+            finality => format!("{} block", finality),
+        }
+    }
+}
+
+impl fmt::Display for Finality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finality::Latest => write!(f, "latest"),
+            Finality::Safe => write!(f, "safe"),
+            Finality::Finalized => write!(f, "finalized"),
+            Finality::Distance(distance) => write!(f, "{}", distance),
+        }
+    }
+}
+
+impl FromStr for Finality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "latest" => Ok(Finality::Latest),
+            "safe" => Ok(Finality::Safe),
+            "finalized" => Ok(Finality::Finalized),
+            other => other.parse().map(Finality::Distance).map_err(|_| {
+                format!(
+                    "Invalid finality {} - must be latest, safe, finalized or a number of blocks",
+                    s
+                )
+            }),
+        }
+    }
+}
+
+impl Serialize for Finality {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Finality {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Network {
+    pub fn block_explorer_url(&self) -> Option<String> {
+        if let Some(block_explorer) = &self.block_explorer {
+            return Some(block_explorer.trim_end_matches('/').to_string());
+        }
+
+        let url = match self.chain_id {
+            1 => "https://etherscan.io",
+            10 => "https://optimistic.etherscan.io",
+            56 => "https://bscscan.com",
+            100 => "https://gnosisscan.io",
+            137 => "https://polygonscan.com",
+            324 => "https://era.zksync.network",
+            8453 => "https://basescan.org",
+            42161 => "https://arbiscan.io",
+            43114 => "https://snowtrace.io",
+            59144 => "https://lineascan.build",
+            81457 => "https://blastscan.io",
+            11155111 => "https://sepolia.etherscan.io",
+            _ => return None,
+        };
+
+        Some(url.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finality() {
+        assert_eq!(serde_yaml::from_str::<Finality>("safe").unwrap(), Finality::Safe);
+        assert_eq!(serde_yaml::from_str::<Finality>("Finalized").unwrap(), Finality::Finalized);
+        assert_eq!(serde_yaml::from_str::<Finality>("latest").unwrap(), Finality::Latest);
+        assert_eq!(serde_yaml::from_str::<Finality>("12").unwrap(), Finality::Distance(12));
+        assert!(serde_yaml::from_str::<Finality>("pending").is_err());
+
+        assert_eq!(serde_yaml::to_string(&Finality::Distance(12)).unwrap().trim(), "'12'");
+    }
 }
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+fn default_port() -> u16 {
+    3002
+}
+
+fn default_page_limit() -> u64 {
+    1000
+}
+
+fn default_timeout() -> u64 {
+    10000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RestSettings {
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// The maximum number of rows a single page can return
+    #[serde(default = "default_page_limit")]
+    pub page_limit: u64,
+
+    /// How long a query can run for in milliseconds before it is cancelled
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for RestSettings {
+    fn default() -> Self {
+        Self { port: default_port(), page_limit: default_page_limit(), timeout: default_timeout() }
+    }
+}
+
+impl RestSettings {
+    pub fn set_port(&mut self, port: u16) {
+        self.port = port;
+    }
+}
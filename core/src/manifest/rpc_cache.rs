@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RpcCacheSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Where the cached responses are stored, relative paths are from the project, defaults to
+    /// `.rindexer/rpc-cache`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
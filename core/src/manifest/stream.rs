@@ -5,7 +5,11 @@ use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{Map, Value};
 use tokio::fs;
 
-use crate::types::aws_config::AwsConfig;
+use crate::{
+    chat::{Template, TemplateContext},
+    event::validate_conditions,
+    types::aws_config::AwsConfig,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamEvent {
@@ -105,16 +109,60 @@ impl RabbitMQStreamConfig {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KafkaEncoding {
+    #[default]
+    Json,
+    Avro,
+    Protobuf,
+}
+
+impl KafkaEncoding {
+    pub fn is_json(&self) -> bool {
+        matches!(self, KafkaEncoding::Json)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KafkaStreamQueueConfig {
     pub topic: String,
 
+    /// Can be a static key or a template resolved for every event, for example
+    /// `{{event_data.from}}`
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub key: Option<String>,
+
+    #[serde(default, skip_serializing_if = "KafkaEncoding::is_json")]
+    pub encoding: KafkaEncoding,
+
     pub networks: Vec<String>,
     pub events: Vec<StreamEvent>,
 }
 
+impl KafkaStreamQueueConfig {
+    pub fn is_key_templated(&self) -> bool {
+        self.key.as_ref().is_some_and(|key| key.contains("{{"))
+    }
+
+    /// Templated keys and schema encodings are resolved per event so each event is published
+    /// as its own message rather than in chunks
+    pub fn publish_per_event(&self) -> bool {
+        self.is_key_templated() || !self.encoding.is_json()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KafkaSchemaRegistryConfig {
+    pub url: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KafkaStreamConfig {
     pub brokers: Vec<String>,
@@ -130,9 +178,120 @@ pub struct KafkaStreamConfig {
     pub sasl_password: Option<String>,
 
     pub acks: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_registry: Option<KafkaSchemaRegistryConfig>,
+
     pub topics: Vec<KafkaStreamQueueConfig>,
 }
 
+impl KafkaStreamConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.topics.is_empty() {
+            return Err("No topics defined in Kafka config".to_string());
+        }
+
+        for config in &self.topics {
+            if let Some(key) = &config.key {
+                Template::validate(key, &TemplateContext::default(), &config.networks).map_err(
+                    |e| format!("Kafka topic {} has an invalid key template - {}", config.topic, e),
+                )?;
+            }
+
+            if !config.encoding.is_json() && self.schema_registry.is_none() {
+                return Err(format!(
+                    "Kafka topic {} uses {:?} encoding which requires a schema_registry to be defined",
+                    config.topic, config.encoding
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostgresStreamCheckpointConfig {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RedisStreamCheckpointConfig {
+    pub connection_uri: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct S3StreamCheckpointConfig {
+    pub bucket: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_prefix: Option<String>,
+
+    /// Custom endpoint for S3 compatible stores like minio or r2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    pub aws_config: AwsConfig,
+}
+
+/// Where stream only indexers store the last synced block for each sink, if not defined it is
+/// stored in files under the `.rindexer` folder
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamCheckpointConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres: Option<PostgresStreamCheckpointConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redis: Option<RedisStreamCheckpointConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3StreamCheckpointConfig>,
+}
+
+impl StreamCheckpointConfig {
+    pub fn postgres_enabled(&self) -> bool {
+        self.postgres.as_ref().is_some_and(|postgres| postgres.enabled)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let stores = [self.postgres_enabled(), self.redis.is_some(), self.s3.is_some()];
+        if stores.iter().filter(|enabled| **enabled).count() > 1 {
+            return Err("Only one stream checkpoint store can be defined".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// The sinks a stream can publish to, each sink keeps its own checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamSink {
+    RabbitMQ,
+    Sns,
+    Webhooks,
+    Kafka,
+}
+
+impl StreamSink {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamSink::RabbitMQ => "rabbitmq",
+            StreamSink::Sns => "sns",
+            StreamSink::Webhooks => "webhooks",
+            StreamSink::Kafka => "kafka",
+        }
+    }
+}
+
+impl std::fmt::Display for StreamSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StreamsConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -146,39 +305,84 @@ pub struct StreamsConfig {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub kafka: Option<KafkaStreamConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<StreamCheckpointConfig>,
 }
 
 impl StreamsConfig {
+    fn events(&self) -> Vec<&StreamEvent> {
+        let mut events = vec![];
+        if let Some(sns) = &self.sns {
+            events.extend(sns.topics.iter().flat_map(|topic| &topic.events));
+        }
+        if let Some(webhooks) = &self.webhooks {
+            events.extend(webhooks.iter().flat_map(|webhook| &webhook.events));
+        }
+        if let Some(rabbitmq) = &self.rabbitmq {
+            events.extend(rabbitmq.exchanges.iter().flat_map(|exchange| &exchange.events));
+        }
+        if let Some(kafka) = &self.kafka {
+            events.extend(kafka.topics.iter().flat_map(|topic| &topic.events));
+        }
+
+        events
+    }
+
     pub fn validate(&self) -> Result<(), String> {
+        for event in self.events() {
+            if let Some(conditions) = &event.conditions {
+                validate_conditions(conditions)
+                    .map_err(|e| format!("Event {} - {}", event.event_name, e))?;
+            }
+        }
+
         if let Some(rabbitmq) = &self.rabbitmq {
-            return rabbitmq.validate();
+            rabbitmq.validate()?;
+        }
+
+        if let Some(kafka) = &self.kafka {
+            kafka.validate()?;
+        }
+
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.validate()?;
         }
 
         Ok(())
     }
 
-    pub fn get_streams_last_synced_block_path(&self) -> String {
-        let mut path = ".rindexer/".to_string();
+    pub fn sinks(&self) -> Vec<StreamSink> {
+        let mut sinks = vec![];
         if self.rabbitmq.is_some() {
-            path.push_str("rabbitmq_");
-        } else if self.sns.is_some() {
-            path.push_str("sns_");
-        } else if self.webhooks.is_some() {
-            path.push_str("webhooks_");
-        } else if self.kafka.is_some() {
-            path.push_str("kafka_");
+            sinks.push(StreamSink::RabbitMQ);
+        }
+        if self.sns.is_some() {
+            sinks.push(StreamSink::Sns);
+        }
+        if self.webhooks.is_some() {
+            sinks.push(StreamSink::Webhooks);
         }
+        if self.kafka.is_some() {
+            sinks.push(StreamSink::Kafka);
+        }
+
+        sinks
+    }
 
-        path.trim_end_matches('_').to_string()
+    pub fn get_streams_last_synced_block_path(sink: StreamSink) -> String {
+        format!(".rindexer/{}", sink)
     }
 
     pub async fn create_full_streams_last_synced_block_path(
-        &self,
         project_path: &Path,
         contract_name: &str,
+        sink: StreamSink,
     ) {
-        let path =
-            self.get_streams_last_synced_block_path() + "/" + contract_name + "/last-synced-blocks";
+        let path = Self::get_streams_last_synced_block_path(sink) +
+            "/" +
+            contract_name +
+            "/last-synced-blocks";
 
         let full_path = project_path.join(path);
 
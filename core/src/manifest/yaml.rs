@@ -11,7 +11,10 @@ use tracing::error;
 use crate::{
     abi::ABIItem,
     helpers::replace_env_variable_to_raw_name,
-    manifest::core::{Manifest, ProjectType},
+    manifest::{
+        contract::AddressSource,
+        core::{Manifest, ProjectType},
+    },
 };
 
 pub const YAML_CONFIG_NAME: &str = "rindexer.yaml";
@@ -59,6 +62,17 @@ pub enum ValidateManifestError {
 
     #[error("Streams config is invalid: {0}")]
     StreamsConfigValidationError(String),
+
+    #[error("Chat config is invalid: {0}")]
+    ChatConfigValidationError(String),
+
+    #[error("Contract {0} on network {1} can only have one of address or address_source")]
+    AddressAndAddressSource(String, String),
+
+    #[error(
+        "Contract {0} loads its addresses with a SQL query which needs postgres storage enabled"
+    )]
+    AddressSourceSqlNeedsPostgres(String),
 }
 
 fn validate_manifest(
@@ -78,6 +92,21 @@ fn validate_manifest(
                 ));
             }
 
+            if detail.address().is_some() && detail.address_source.is_some() {
+                return Err(ValidateManifestError::AddressAndAddressSource(
+                    contract.name.clone(),
+                    detail.network.clone(),
+                ));
+            }
+
+            if matches!(detail.address_source, Some(AddressSource::Sql { .. })) &&
+                !manifest.storage.postgres_enabled()
+            {
+                return Err(ValidateManifestError::AddressSourceSqlNeedsPostgres(
+                    contract.name.clone(),
+                ));
+            }
+
             if let Some(address) = &detail.filter {
                 if !events.iter().any(|e| e.name == *address.event_name) {
                     return Err(ValidateManifestError::InvalidFilterEventNameDoesntExistInABI(
@@ -136,6 +165,12 @@ fn validate_manifest(
                 return Err(ValidateManifestError::StreamsConfigValidationError(e));
             }
         }
+
+        if let Some(chat) = &contract.chat {
+            if let Err(e) = chat.validate(&manifest.networks) {
+                return Err(ValidateManifestError::ChatConfigValidationError(e));
+            }
+        }
     }
 
     if let Some(postgres) = &manifest.storage.postgres {
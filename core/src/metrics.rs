@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use tracing::error;
+
+use crate::{indexer::IndexingEventProgress, lazy_static};
+
+/// The RPC methods the indexer calls through the cached provider
+#[derive(Debug, Clone, Copy)]
+pub enum RpcMethod {
+    BlockNumber,
+    ChainId,
+    GetBlockByNumber,
+    GetLogs,
+}
+
+impl RpcMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RpcMethod::BlockNumber => "eth_blockNumber",
+            RpcMethod::ChainId => "eth_chainId",
+            RpcMethod::GetBlockByNumber => "eth_getBlockByNumber",
+            RpcMethod::GetLogs => "eth_getLogs",
+        }
+    }
+}
+
+/// How the rows were written to postgres
+#[derive(Debug, Clone, Copy)]
+pub enum PostgresInsertMethod {
+    Copy,
+    Batch,
+}
+
+impl PostgresInsertMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostgresInsertMethod::Copy => "copy",
+            PostgresInsertMethod::Batch => "batch",
+        }
+    }
+}
+
+fn status(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+fn counter(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let counter = IntCounterVec::new(Opts::new(name, help), labels).expect("valid counter");
+    registry.register(Box::new(counter.clone())).expect("counter registered once");
+    counter
+}
+
+fn gauge(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    let gauge = IntGaugeVec::new(Opts::new(name, help), labels).expect("valid gauge");
+    registry.register(Box::new(gauge.clone())).expect("gauge registered once");
+    gauge
+}
+
+fn histogram(
+    registry: &Registry,
+    name: &str,
+    help: &str,
+    labels: &[&str],
+    buckets: Vec<f64>,
+) -> HistogramVec {
+    let histogram = HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets), labels)
+        .expect("valid histogram");
+    registry.register(Box::new(histogram.clone())).expect("histogram registered once");
+    histogram
+}
+
+struct Metrics {
+    registry: Registry,
+    rpc_requests: IntCounterVec,
+    rpc_errors: IntCounterVec,
+    rpc_retries: IntCounterVec,
+    rpc_duration: HistogramVec,
+    get_logs_retry_block_range: HistogramVec,
+    get_logs_block_range: IntGaugeVec,
+    events_processed: IntCounterVec,
+    fetch_backpressure: HistogramVec,
+    postgres_insert_duration: HistogramVec,
+    stream_publishes: IntCounterVec,
+    chat_messages: IntCounterVec,
+    chain_head: IntGaugeVec,
+    head_lag: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let seconds = exponential_buckets(0.005, 2.0, 14).expect("valid buckets");
+
+        Metrics {
+            rpc_requests: counter(
+                &registry,
+                "rindexer_rpc_requests_total",
+                "RPC requests made by the indexer",
+                &["network", "method"],
+            ),
+            rpc_errors: counter(
+                &registry,
+                "rindexer_rpc_errors_total",
+                "RPC requests which returned an error after any retries",
+                &["network", "method"],
+            ),
+            rpc_retries: counter(
+                &registry,
+                "rindexer_rpc_retries_total",
+                "RPC requests retried by the retry client due to rate limits",
+                &["network"],
+            ),
+            rpc_duration: histogram(
+                &registry,
+                "rindexer_rpc_request_duration_seconds",
+                "RPC request duration including any retries",
+                &["network", "method"],
+                seconds.clone(),
+            ),
+            get_logs_retry_block_range: histogram(
+                &registry,
+                "rindexer_get_logs_retry_block_range",
+                "Block range chosen when retrying eth_getLogs after the provider rejected the range",
+                &["network"],
+                exponential_buckets(1.0, 4.0, 12).expect("valid buckets"),
+            ),
+            get_logs_block_range: gauge(
+                &registry,
+                "rindexer_get_logs_block_range",
+                "The block range eth_getLogs requests adapted to",
+                &["network"],
+            ),
+            events_processed: counter(
+                &registry,
+                "rindexer_events_processed_total",
+                "Events processed by the indexer",
+                &["network", "contract", "event"],
+            ),
+            fetch_backpressure: histogram(
+                &registry,
+                "rindexer_fetch_backpressure_seconds",
+                "Time log fetching waited for the fetched logs to be processed",
+                &["network", "contract", "event"],
+                seconds.clone(),
+            ),
+            postgres_insert_duration: histogram(
+                &registry,
+                "rindexer_postgres_insert_duration_seconds",
+                "Postgres bulk insert duration by the insert method",
+                &["method"],
+                seconds,
+            ),
+            stream_publishes: counter(
+                &registry,
+                "rindexer_stream_publishes_total",
+                "Stream publishes by sink and outcome",
+                &["sink", "status"],
+            ),
+            chat_messages: counter(
+                &registry,
+                "rindexer_chat_messages_total",
+                "Chat messages sent by client and outcome",
+                &["client", "status"],
+            ),
+            chain_head: gauge(
+                &registry,
+                "rindexer_chain_head_block",
+                "The latest block seen from the RPC",
+                &["network"],
+            ),
+            head_lag: gauge(
+                &registry,
+                "rindexer_head_lag_blocks",
+                "Blocks between the latest block seen and the furthest behind event",
+                &["network"],
+            ),
+            registry,
+        }
+    }
+}
+
+lazy_static! {
+    static ref METRICS: Metrics = Metrics::new();
+    static ref CHAIN_HEADS: RwLock<HashMap<String, u64>> = RwLock::new(HashMap::new());
+}
+
+pub fn record_rpc_request(network: &str, method: RpcMethod, started: Instant, success: bool) {
+    let labels = [network, method.as_str()];
+    METRICS.rpc_requests.with_label_values(&labels).inc();
+    METRICS.rpc_duration.with_label_values(&labels).observe(started.elapsed().as_secs_f64());
+    if !success {
+        METRICS.rpc_errors.with_label_values(&labels).inc();
+    }
+}
+
+pub fn record_rpc_retry(network: &str) {
+    METRICS.rpc_retries.with_label_values(&[network]).inc();
+}
+
+pub fn record_get_logs_retry_block_range(network: &str, block_range: u64) {
+    METRICS.get_logs_retry_block_range.with_label_values(&[network]).observe(block_range as f64);
+}
+
+pub fn record_get_logs_block_range(network: &str, block_range: u64) {
+    METRICS.get_logs_block_range.with_label_values(&[network]).set(block_range as i64);
+}
+
+pub fn record_events_processed(network: &str, contract_name: &str, event_name: &str, count: usize) {
+    METRICS
+        .events_processed
+        .with_label_values(&[network, contract_name, event_name])
+        .inc_by(count as u64);
+}
+
+pub fn record_fetch_backpressure(
+    network: &str,
+    contract_name: &str,
+    event_name: &str,
+    waited: Duration,
+) {
+    METRICS
+        .fetch_backpressure
+        .with_label_values(&[network, contract_name, event_name])
+        .observe(waited.as_secs_f64());
+}
+
+pub fn record_postgres_insert(method: PostgresInsertMethod, duration: Duration) {
+    METRICS
+        .postgres_insert_duration
+        .with_label_values(&[method.as_str()])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_stream_publish(sink: &str, success: bool) {
+    METRICS.stream_publishes.with_label_values(&[sink, status(success)]).inc();
+}
+
+pub fn record_chat_message(client: &str, success: bool) {
+    METRICS.chat_messages.with_label_values(&[client, status(success)]).inc();
+}
+
+pub fn record_chain_head(network: &str, block: u64) {
+    METRICS.chain_head.with_label_values(&[network]).set(block as i64);
+    if let Ok(mut heads) = CHAIN_HEADS.write() {
+        heads.insert(network.to_string(), block);
+    }
+}
+
+/// The blocks each network is behind the chain head, measured from the furthest behind event
+pub(crate) fn head_lags(events: &[IndexingEventProgress]) -> HashMap<String, u64> {
+    let heads = match CHAIN_HEADS.read() {
+        Ok(heads) => heads,
+        Err(_) => return HashMap::new(),
+    };
+
+    let mut lags: HashMap<String, u64> = HashMap::new();
+    for event in events {
+        if let Some(head) = heads.get(&event.network) {
+            let lag = head.saturating_sub(event.last_synced_block.as_u64());
+            let entry = lags.entry(event.network.clone()).or_default();
+            *entry = (*entry).max(lag);
+        }
+    }
+    lags
+}
+
+/// Renders every metric in the prometheus text format
+pub fn render(events: &[IndexingEventProgress]) -> String {
+    for (network, lag) in head_lags(events) {
+        METRICS.head_lag.with_label_values(&[&network]).set(lag as i64);
+    }
+
+    let mut buffer = vec![];
+    if let Err(e) = TextEncoder::new().encode(&METRICS.registry.gather(), &mut buffer) {
+        error!("Could not encode the metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::IndexingEventProgressStatus;
+
+    #[test]
+    fn test_render() {
+        let event = IndexingEventProgress::for_test(
+            "RocketPoolETH",
+            "Transfer",
+            "metrics_test",
+            IndexingEventProgressStatus::Syncing,
+            90,
+        );
+
+        record_chain_head("metrics_test", 100);
+        record_rpc_request("metrics_test", RpcMethod::GetLogs, Instant::now(), false);
+        record_events_processed("metrics_test", "RocketPoolETH", "Transfer", 3);
+
+        let output = render(&[event]);
+        assert!(output.contains(
+            r#"rindexer_rpc_errors_total{method="eth_getLogs",network="metrics_test"} 1"#
+        ));
+        assert!(output.contains(
+            r#"rindexer_events_processed_total{contract="RocketPoolETH",event="Transfer",network="metrics_test"} 3"#
+        ));
+        assert!(output.contains(r#"rindexer_head_lag_blocks{network="metrics_test"} 10"#));
+    }
+}
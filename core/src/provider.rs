@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -6,32 +7,70 @@ use std::{
 use ethers::{
     middleware::Middleware,
     prelude::Log,
-    providers::{Http, Provider, ProviderError, RetryClient, RetryClientBuilder},
+    providers::{
+        Http, HttpClientError, HttpRateLimitRetryPolicy, Provider, ProviderError, RetryClient,
+        RetryClientBuilder, RetryPolicy,
+    },
     types::{Block, BlockNumber, H256, U256, U64},
 };
 use reqwest::header::HeaderMap;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{AcquireError, Mutex, OnceCell, OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info_span, Instrument};
 use url::Url;
 
-use crate::{event::RindexerEventFilter, manifest::core::Manifest};
+use crate::{
+    event::RindexerEventFilter,
+    indexer::reorg::reorg_safe_distance_for_chain,
+    manifest::core::Manifest,
+    metrics::{record_chain_head, record_rpc_request, record_rpc_retry, RpcMethod},
+    rpc_cache::{configured_rpc_cache_path, read_cached_logs, write_cached_logs},
+};
+
+/// How long the finalized block is reused before asking the node again, an older finalized block
+/// only means fewer ranges get cached
+const FINALIZED_BLOCK_TTL: Duration = Duration::from_secs(30);
 
 #[derive(Debug)]
 pub struct JsonRpcCachedProvider {
     provider: Arc<Provider<RetryClient<Http>>>,
     cache: Mutex<Option<(Instant, Arc<Block<H256>>)>>,
+    chain_id: OnceCell<U256>,
+    finalized_block: Mutex<Option<(Instant, U64)>>,
+    /// The block each tag pointed to and the latest block it was asked at
+    tagged_heads: Mutex<HashMap<BlockNumber, (U64, Option<U64>)>>,
+    rpc_url: String,
     pub max_block_range: Option<U64>,
+    pub network: String,
 }
 
 impl JsonRpcCachedProvider {
-    pub fn new(provider: Provider<RetryClient<Http>>, max_block_range: Option<U64>) -> Self {
+    pub fn new(
+        network: &str,
+        rpc_url: &str,
+        provider: Provider<RetryClient<Http>>,
+        max_block_range: Option<U64>,
+    ) -> Self {
         JsonRpcCachedProvider {
             provider: Arc::new(provider),
             cache: Mutex::new(None),
+            chain_id: OnceCell::new(),
+            finalized_block: Mutex::new(None),
+            tagged_heads: Mutex::new(HashMap::new()),
+            rpc_url: rpc_url.to_string(),
             max_block_range,
+            network: network.to_string(),
         }
     }
 
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    fn record<T>(&self, method: RpcMethod, started: Instant, result: &Result<T, ProviderError>) {
+        record_rpc_request(&self.network, method, started, result.is_ok());
+    }
+
     pub async fn get_latest_block(&self) -> Result<Option<Arc<Block<H256>>>, ProviderError> {
         let mut cache_guard = self.cache.lock().await;
 
@@ -41,9 +80,14 @@ impl JsonRpcCachedProvider {
             }
         }
 
-        let latest_block = self.provider.get_block(BlockNumber::Latest).await?;
+        let started = Instant::now();
+        let latest_block = self.provider.get_block(BlockNumber::Latest).await;
+        self.record(RpcMethod::GetBlockByNumber, started, &latest_block);
 
-        if let Some(block) = latest_block {
+        if let Some(block) = latest_block? {
+            if let Some(number) = block.number {
+                record_chain_head(&self.network, number.as_u64());
+            }
             let arc_block = Arc::new(block);
             *cache_guard = Some((Instant::now(), Arc::clone(&arc_block)));
             return Ok(Some(arc_block));
@@ -55,21 +99,214 @@ impl JsonRpcCachedProvider {
     }
 
     pub async fn get_block_number(&self) -> Result<U64, ProviderError> {
-        self.provider.get_block_number().await
+        let started = Instant::now();
+        let block_number = self.provider.get_block_number().await;
+        self.record(RpcMethod::BlockNumber, started, &block_number);
+        if let Ok(number) = &block_number {
+            record_chain_head(&self.network, number.as_u64());
+        }
+        block_number
+    }
+
+    /// The number of the block a tag like `safe` or `finalized` points to
+    pub async fn get_tagged_block_number(
+        &self,
+        tag: BlockNumber,
+    ) -> Result<Option<U64>, ProviderError> {
+        let started = Instant::now();
+        let block = self.provider.get_block(tag).await;
+        self.record(RpcMethod::GetBlockByNumber, started, &block);
+        Ok(block?.and_then(|block| block.number))
+    }
+
+    /// The number of the block a tag points to as of `latest_block`, every event on the network
+    /// follows the same head so the node is only asked once per block
+    pub async fn get_tagged_head(
+        &self,
+        tag: BlockNumber,
+        latest_block: U64,
+    ) -> Result<Option<U64>, ProviderError> {
+        let mut tagged_heads = self.tagged_heads.lock().await;
+        if let Some((asked_at, head)) = tagged_heads.get(&tag) {
+            if *asked_at == latest_block {
+                return Ok(*head);
+            }
+        }
+
+        let head = self.get_tagged_block_number(tag).await?;
+        tagged_heads.insert(tag, (latest_block, head));
+        Ok(head)
+    }
+
+    async fn cached_chain_id(&self) -> Result<U256, ProviderError> {
+        self.chain_id.get_or_try_init(|| self.get_chain_id()).await.copied()
+    }
+
+    /// Nodes without the `finalized` block tag fall back to the reorg safe distance of the chain
+    async fn finalized_block_number(&self) -> Result<U64, ProviderError> {
+        let mut finalized_block = self.finalized_block.lock().await;
+        if let Some((fetched_at, block_number)) = *finalized_block {
+            if fetched_at.elapsed() < FINALIZED_BLOCK_TTL {
+                return Ok(block_number);
+            }
+        }
+
+        let block_number = match self.get_tagged_block_number(BlockNumber::Finalized).await {
+            Ok(Some(block_number)) => block_number,
+            _ => {
+                let chain_id = self.cached_chain_id().await?;
+                self.get_block_number()
+                    .await?
+                    .saturating_sub(reorg_safe_distance_for_chain(&chain_id))
+            }
+        };
+
+        *finalized_block = Some((Instant::now(), block_number));
+        Ok(block_number)
     }
 
+    /// Replays the cached block ranges when the RPC cache is enabled whatever ranges they were
+    /// fetched with, the rest comes from the node and is cached once finalized
     pub async fn get_logs(&self, filter: &RindexerEventFilter) -> Result<Vec<Log>, ProviderError> {
-        self.provider.get_logs(filter.raw_filter()).await
+        let Some(rpc_cache_path) = configured_rpc_cache_path() else {
+            return self.get_logs_from_node(filter).await;
+        };
+
+        let chain_id = self.cached_chain_id().await?;
+        let to_block = filter.get_to_block();
+        let (from_block, mut logs) = read_cached_logs(
+            &rpc_cache_path,
+            &chain_id,
+            filter.raw_filter(),
+            filter.get_from_block(),
+            to_block,
+        )
+        .await;
+
+        if from_block > to_block {
+            debug!(
+                "{} - eth_getLogs served from the RPC cache - blocks: {} - {}",
+                self.network,
+                filter.get_from_block(),
+                to_block
+            );
+            return Ok(logs);
+        }
+
+        let remaining_filter = filter.clone().set_from_block(from_block);
+        let mut fetched = match self.get_logs_from_node(&remaining_filter).await {
+            Ok(fetched) => fetched,
+            // errors suggesting a block range are about the blocks requested so the caller gets
+            // the error of its own range, not of the blocks left after the cached ones
+            Err(_) if from_block != filter.get_from_block() => {
+                return self.get_logs_from_node(filter).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        match self.finalized_block_number().await {
+            Ok(finalized_block) if to_block <= finalized_block => {
+                fetched = write_cached_logs(
+                    &rpc_cache_path,
+                    &chain_id,
+                    filter.raw_filter(),
+                    from_block,
+                    to_block,
+                    fetched,
+                )
+                .await;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                debug!(
+                    "{} - not caching logs as the finalized block is unknown: {}",
+                    self.network, e
+                )
+            }
+        }
+
+        logs.extend(fetched);
+        Ok(logs)
+    }
+
+    async fn get_logs_from_node(
+        &self,
+        filter: &RindexerEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        let started = Instant::now();
+        let logs = self
+            .provider
+            .get_logs(filter.raw_filter())
+            .instrument(info_span!(
+                "eth_getLogs",
+                network = %self.network,
+                from_block = filter.get_from_block().as_u64(),
+                to_block = filter.get_to_block().as_u64(),
+            ))
+            .await;
+        self.record(RpcMethod::GetLogs, started, &logs);
+        logs
     }
 
     pub async fn get_chain_id(&self) -> Result<U256, ProviderError> {
-        self.provider.get_chainid().await
+        let started = Instant::now();
+        let chain_id = self.provider.get_chainid().await;
+        self.record(RpcMethod::ChainId, started, &chain_id);
+        chain_id
     }
 
     pub fn get_inner_provider(&self) -> Arc<Provider<RetryClient<Http>>> {
         Arc::clone(&self.provider)
     }
 }
+/// Caps the RPC requests in flight, each network has its own limit under the global one so a slow
+/// or rate limited network can not take every request slot
+#[derive(Debug, Clone)]
+pub struct RequestConcurrency {
+    network: Arc<Semaphore>,
+    global: Arc<Semaphore>,
+}
+
+/// Holds a request slot of the network and of the global limit until dropped
+pub struct RequestPermit {
+    _network: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+impl RequestConcurrency {
+    pub fn new(network: Arc<Semaphore>, global: Arc<Semaphore>) -> Self {
+        RequestConcurrency { network, global }
+    }
+
+    /// Waits on the network limit first so a network at its limit does not hold a global slot
+    pub async fn acquire(&self) -> Result<RequestPermit, AcquireError> {
+        let network = Arc::clone(&self.network).acquire_owned().await?;
+        let global = Arc::clone(&self.global).acquire_owned().await?;
+        Ok(RequestPermit { _network: network, _global: global })
+    }
+}
+
+/// The http rate limit retry policy which also counts the retries for the metrics
+#[derive(Debug)]
+struct MetricsRetryPolicy {
+    network: String,
+    inner: HttpRateLimitRetryPolicy,
+}
+
+impl RetryPolicy<HttpClientError> for MetricsRetryPolicy {
+    fn should_retry(&self, error: &HttpClientError) -> bool {
+        let should_retry = self.inner.should_retry(error);
+        if should_retry {
+            record_rpc_retry(&self.network);
+        }
+        should_retry
+    }
+
+    fn backoff_hint(&self, error: &HttpClientError) -> Option<Duration> {
+        self.inner.backoff_hint(error)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum RetryClientError {
     #[error("http provider can't be created for {0}: {1}")]
@@ -80,6 +317,7 @@ pub enum RetryClientError {
 }
 
 pub fn create_client(
+    network: &str,
     rpc_url: &str,
     compute_units_per_second: Option<u64>,
     max_block_range: Option<U64>,
@@ -98,9 +336,15 @@ pub fn create_client(
             .rate_limit_retries(5000)
             .timeout_retries(1000)
             .initial_backoff(Duration::from_millis(500))
-            .build(provider, Box::<ethers::providers::HttpRateLimitRetryPolicy>::default()),
+            .build(
+                provider,
+                Box::new(MetricsRetryPolicy {
+                    network: network.to_string(),
+                    inner: HttpRateLimitRetryPolicy,
+                }),
+            ),
     );
-    Ok(Arc::new(JsonRpcCachedProvider::new(instance, max_block_range)))
+    Ok(Arc::new(JsonRpcCachedProvider::new(network, rpc_url, instance, max_block_range)))
 }
 
 pub async fn get_chain_id(rpc_url: &str) -> Result<U256, ProviderError> {
@@ -122,6 +366,7 @@ impl CreateNetworkProvider {
         let mut result: Vec<CreateNetworkProvider> = vec![];
         for network in &manifest.networks {
             let provider = create_client(
+                &network.name,
                 &network.rpc,
                 network.compute_units_per_second,
                 network.max_block_range,
@@ -140,19 +385,22 @@ impl CreateNetworkProvider {
 
 #[cfg(test)]
 mod tests {
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
     use super::*;
 
     #[test]
     fn test_create_retry_client() {
         let rpc_url = "http://localhost:8545";
-        let result = create_client(rpc_url, Some(660), None, HeaderMap::new());
+        let result = create_client("localhost", rpc_url, Some(660), None, HeaderMap::new());
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_create_retry_client_invalid_url() {
         let rpc_url = "invalid_url";
-        let result = create_client(rpc_url, Some(660), None, HeaderMap::new());
+        let result = create_client("localhost", rpc_url, Some(660), None, HeaderMap::new());
         assert!(result.is_err());
         if let Err(RetryClientError::HttpProviderCantBeCreated(url, _)) = result {
             assert_eq!(url, rpc_url);
@@ -160,4 +408,42 @@ mod tests {
             panic!("Expected HttpProviderCantBeCreated error");
         }
     }
+
+    #[tokio::test]
+    async fn test_request_concurrency() {
+        let global = Arc::new(Semaphore::new(2));
+        let slow_network =
+            RequestConcurrency::new(Arc::new(Semaphore::new(1)), Arc::clone(&global));
+        let other_network =
+            RequestConcurrency::new(Arc::new(Semaphore::new(2)), Arc::clone(&global));
+
+        let _permit = slow_network.acquire().await.unwrap();
+        let blocked = tokio::time::timeout(Duration::from_millis(50), slow_network.acquire()).await;
+        assert!(blocked.is_err(), "network limit should block the second request");
+
+        // the network waiting on its own limit did not take the last global slot
+        let _other_permit = other_network.acquire().await.unwrap();
+        assert_eq!(global.available_permits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tagged_head_is_asked_once_per_block() {
+        let mock = mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "eth_getBlockByNumber",
+                "params": ["finalized", false]
+            })))
+            .with_body(r#"{"jsonrpc":"2.0","id":0,"result":{"number":"0x10"}}"#)
+            .expect(2)
+            .create();
+
+        let provider =
+            create_client("ethereum", &mockito::server_url(), None, None, HeaderMap::new())
+                .unwrap();
+        for latest_block in [20, 20, 21, 21] {
+            let head = provider.get_tagged_head(BlockNumber::Finalized, latest_block.into()).await;
+            assert_eq!(head.unwrap(), Some(16.into()));
+        }
+        mock.assert();
+    }
 }
@@ -0,0 +1,396 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::{Duration, SystemTime},
+};
+
+use ethers::{
+    types::{Filter, FilterBlockOption, Log, U256, U64},
+    utils::{hex, keccak256},
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{debug, error, info, warn};
+
+use crate::{lazy_static, manifest::rpc_cache::RpcCacheSettings};
+
+const DEFAULT_RPC_CACHE_PATH: &str = ".rindexer/rpc-cache";
+
+lazy_static! {
+    static ref RPC_CACHE_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Where the cached RPC responses of the project are stored
+pub fn rpc_cache_path(project_path: &Path, settings: Option<&RpcCacheSettings>) -> PathBuf {
+    let path = settings.and_then(|settings| settings.path.as_deref());
+    project_path.join(path.unwrap_or(DEFAULT_RPC_CACHE_PATH))
+}
+
+/// Turns the on-disk cache of `eth_getLogs` responses on or off for every provider
+pub fn configure_rpc_cache(project_path: &Path, settings: Option<&RpcCacheSettings>) {
+    let path = settings
+        .filter(|settings| settings.enabled)
+        .map(|settings| rpc_cache_path(project_path, Some(settings)));
+
+    if let Some(path) = &path {
+        info!(
+            "RPC cache enabled - finalized eth_getLogs responses are stored in {}",
+            path.display()
+        );
+    }
+
+    if let Ok(mut current) = RPC_CACHE_PATH.write() {
+        *current = path;
+    }
+}
+
+pub(crate) fn configured_rpc_cache_path() -> Option<PathBuf> {
+    RPC_CACHE_PATH.read().ok().and_then(|path| path.clone())
+}
+
+/// The logs of a block range, stored in a file named after the range
+#[derive(Serialize, Deserialize)]
+struct CachedLogs {
+    to_block: U64,
+    logs: Vec<Log>,
+}
+
+/// Every project indexing the same contract builds the same filter so they share the entries, the
+/// block range is left out so each range is a file in the directory of the filter
+fn cache_filter_path(cache_path: &Path, chain_id: &U256, filter: &Filter) -> Option<PathBuf> {
+    let mut filter = filter.clone();
+    filter.block_option = FilterBlockOption::default();
+    let key = hex::encode(keccak256(serde_json::to_vec(&filter).ok()?));
+    Some(cache_path.join(chain_id.to_string()).join(key))
+}
+
+fn cache_entry_file_name(from_block: U64, to_block: U64) -> String {
+    format!("{}-{}.json", from_block, to_block)
+}
+
+/// The block range of an entry from its file name, None for anything else in the directory
+fn cache_entry_range(file_name: &str) -> Option<(U64, U64)> {
+    let (from_block, to_block) = file_name.strip_suffix(".json")?.split_once('-')?;
+    Some((U64::from_dec_str(from_block).ok()?, U64::from_dec_str(to_block).ok()?))
+}
+
+/// The cached block ranges of the filter, the file names are the index so nothing has to be read
+async fn cached_ranges(filter_path: &Path) -> Vec<(U64, U64)> {
+    let mut ranges = vec![];
+    let Ok(mut files) = fs::read_dir(filter_path).await else {
+        return ranges;
+    };
+    while let Ok(Some(file)) = files.next_entry().await {
+        if let Some(range) = file.file_name().to_str().and_then(cache_entry_range) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+async fn read_cache_entry(path: &Path) -> Option<CachedLogs> {
+    let content = fs::read(path).await.ok()?;
+    match serde_json::from_slice::<CachedLogs>(&content) {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            warn!("Ignoring the unreadable RPC cache entry {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Replays the cached logs from `from_block` for as long as the cached ranges cover the blocks,
+/// whatever ranges they were written with, returns the block the node has to carry on from
+pub(crate) async fn read_cached_logs(
+    cache_path: &Path,
+    chain_id: &U256,
+    filter: &Filter,
+    mut from_block: U64,
+    to_block: U64,
+) -> (U64, Vec<Log>) {
+    let mut logs = vec![];
+    let Some(filter_path) = cache_filter_path(cache_path, chain_id, filter) else {
+        return (from_block, logs);
+    };
+
+    let ranges = cached_ranges(&filter_path).await;
+    while from_block <= to_block {
+        // of the entries covering the block the one reaching furthest
+        let Some((entry_from_block, entry_to_block)) = ranges
+            .iter()
+            .filter(|(entry_from_block, entry_to_block)| {
+                *entry_from_block <= from_block && from_block <= *entry_to_block
+            })
+            .max_by_key(|(_, entry_to_block)| *entry_to_block)
+            .copied()
+        else {
+            break;
+        };
+
+        let path = filter_path.join(cache_entry_file_name(entry_from_block, entry_to_block));
+        let Some(cached) = read_cache_entry(&path).await else {
+            break;
+        };
+        logs.extend(cached.logs.into_iter().filter(|log| {
+            log.block_number
+                .is_none_or(|block_number| from_block <= block_number && block_number <= to_block)
+        }));
+        from_block = entry_to_block.min(to_block) + 1;
+    }
+
+    (from_block, logs)
+}
+
+/// Only call this for finalized block ranges, the entries are never invalidated
+pub(crate) async fn write_cached_logs(
+    cache_path: &Path,
+    chain_id: &U256,
+    filter: &Filter,
+    from_block: U64,
+    to_block: U64,
+    logs: Vec<Log>,
+) -> Vec<Log> {
+    let Some(filter_path) = cache_filter_path(cache_path, chain_id, filter) else {
+        return logs;
+    };
+    let path = filter_path.join(cache_entry_file_name(from_block, to_block));
+
+    let cached = CachedLogs { to_block, logs };
+    let content = match serde_json::to_vec(&cached) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Could not serialize the logs for the RPC cache: {}", e);
+            return cached.logs;
+        }
+    };
+
+    // a unique temp file then a rename so projects sharing the cache never read half an entry
+    let temp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    let result = async {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&temp_path, content).await?;
+        fs::rename(&temp_path, &path).await
+    }
+    .await;
+
+    match result {
+        Ok(_) => debug!("Cached {} logs in {}", cached.logs.len(), path.display()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            error!("Could not write the RPC cache entry {}: {}", path.display(), e);
+        }
+    }
+
+    cached.logs
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RpcCacheSize {
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+impl RpcCacheSize {
+    fn add(&mut self, bytes: u64) {
+        self.entries += 1;
+        self.bytes += bytes;
+    }
+}
+
+async fn cache_entries(cache_path: &Path) -> io::Result<Vec<(u64, PathBuf, std::fs::Metadata)>> {
+    let mut entries = vec![];
+    let mut chains = match fs::read_dir(cache_path).await {
+        Ok(chains) => chains,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(e) => return Err(e),
+    };
+
+    while let Some(chain) = chains.next_entry().await? {
+        let Some(chain_id) = chain.file_name().to_str().and_then(|name| name.parse().ok()) else {
+            continue;
+        };
+        if !chain.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let mut filters = fs::read_dir(chain.path()).await?;
+        while let Some(filter) = filters.next_entry().await? {
+            if !filter.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut files = fs::read_dir(filter.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                let metadata = file.metadata().await?;
+                if metadata.is_file() {
+                    entries.push((chain_id, file.path(), metadata));
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The size of the cache for each chain id
+pub async fn rpc_cache_size(cache_path: &Path) -> io::Result<BTreeMap<u64, RpcCacheSize>> {
+    let mut sizes: BTreeMap<u64, RpcCacheSize> = BTreeMap::new();
+    for (chain_id, _, metadata) in cache_entries(cache_path).await? {
+        sizes.entry(chain_id).or_default().add(metadata.len());
+    }
+    Ok(sizes)
+}
+
+/// Removes the entries of a chain or every chain, `older_than` keeps entries written since then,
+/// returns what was removed
+pub async fn prune_rpc_cache(
+    cache_path: &Path,
+    chain_id: Option<u64>,
+    older_than: Option<Duration>,
+) -> io::Result<RpcCacheSize> {
+    let now = SystemTime::now();
+    let mut removed = RpcCacheSize::default();
+    for (entry_chain_id, path, metadata) in cache_entries(cache_path).await? {
+        if chain_id.is_some_and(|chain_id| chain_id != entry_chain_id) {
+            continue;
+        }
+
+        if let Some(older_than) = older_than {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < older_than {
+                continue;
+            }
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(_) => removed.add(metadata.len()),
+            // another project sharing the cache pruned it first
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, H256};
+
+    use super::*;
+
+    fn log_at(block_number: u64) -> Log {
+        Log {
+            block_hash: Some(H256::zero()),
+            block_number: Some(U64::from(block_number)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rpc_cache() {
+        let cache_path = tempfile::tempdir().unwrap();
+        let chain_id = U256::from(1);
+        let filter = Filter::new().address(Address::zero()).from_block(10).to_block(20);
+        let log = log_at(15);
+        let from_block = U64::from(10);
+
+        let cached = read_cached_logs(cache_path.path(), &chain_id, &filter, from_block, 20.into());
+        assert_eq!(cached.await, (from_block, vec![]));
+        write_cached_logs(
+            cache_path.path(),
+            &chain_id,
+            &filter,
+            from_block,
+            U64::from(20),
+            vec![log.clone()],
+        )
+        .await;
+
+        // the block range of the filter is not part of the key
+        let other_range = filter.clone().to_block(30);
+        let cached =
+            read_cached_logs(cache_path.path(), &chain_id, &other_range, from_block, 30.into());
+        assert_eq!(cached.await, (U64::from(21), vec![log]));
+
+        // another contract or chain is another entry
+        let other_filter = filter.clone().address(Address::repeat_byte(1));
+        let cached =
+            read_cached_logs(cache_path.path(), &chain_id, &other_filter, from_block, 20.into());
+        assert_eq!(cached.await, (from_block, vec![]));
+        let other_chain_id = U256::from(10);
+        let cached =
+            read_cached_logs(cache_path.path(), &other_chain_id, &filter, from_block, 20.into());
+        assert_eq!(cached.await, (from_block, vec![]));
+
+        write_cached_logs(
+            cache_path.path(),
+            &other_chain_id,
+            &filter,
+            from_block,
+            from_block,
+            vec![],
+        )
+        .await;
+        let sizes = rpc_cache_size(cache_path.path()).await.unwrap();
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[&1].entries, 1);
+
+        let removed =
+            prune_rpc_cache(cache_path.path(), Some(1), Some(Duration::from_secs(3600))).await;
+        assert_eq!(removed.unwrap().entries, 0);
+        let removed = prune_rpc_cache(cache_path.path(), Some(1), None).await.unwrap();
+        assert_eq!(removed, sizes[&1]);
+        assert_eq!(rpc_cache_size(cache_path.path()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_cache_replays_another_range_split() {
+        let cache_path = tempfile::tempdir().unwrap();
+        let chain_id = U256::from(1);
+        let filter = Filter::new().address(Address::zero());
+
+        // written as 0 - 99 and 100 - 299 with a log every 10 blocks
+        for (from_block, to_block) in [(0, 99), (100, 299)] {
+            let logs = (from_block..=to_block).step_by(10).map(log_at).collect();
+            write_cached_logs(
+                cache_path.path(),
+                &chain_id,
+                &filter,
+                from_block.into(),
+                to_block.into(),
+                logs,
+            )
+            .await;
+        }
+
+        // replayed from the block after the last log of each request with another range size
+        let mut from_block = U64::zero();
+        let mut replayed = vec![];
+        while from_block <= U64::from(299) {
+            let to_block = (from_block + 49).min(U64::from(299));
+            let (next_block, logs) =
+                read_cached_logs(cache_path.path(), &chain_id, &filter, from_block, to_block).await;
+            assert_eq!(next_block, to_block + 1);
+            assert!(logs.iter().all(|log| {
+                log.block_number.is_some_and(|block| from_block <= block && block <= to_block)
+            }));
+            from_block = logs.last().and_then(|log| log.block_number).unwrap_or(to_block) + 1;
+            replayed.extend(logs);
+        }
+        let expected: Vec<Log> = (0..300).step_by(10).map(log_at).collect();
+        assert_eq!(replayed, expected);
+
+        // blocks past the cached ranges are left for the node
+        let cached =
+            read_cached_logs(cache_path.path(), &chain_id, &filter, 250.into(), 400.into());
+        assert_eq!(cached.await.0, U64::from(300));
+    }
+}
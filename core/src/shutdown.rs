@@ -0,0 +1,181 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, Once,
+    },
+    time::Duration,
+};
+
+use futures::future::join_all;
+use tokio::{signal, sync::watch, task::JoinHandle};
+use tracing::{error, info, warn};
+
+use crate::{lazy_static, logger::shutdown_telemetry};
+
+/// How long in-flight work gets to finish once indexing has stopped
+const IN_FLIGHT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// rindexer exits after this even if it is still waiting on something like a slow RPC, under the
+/// 30 second default kubernetes termination grace period
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(25);
+
+type ShutdownHook = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+lazy_static! {
+    static ref SHUTTING_DOWN: watch::Sender<bool> = watch::channel(false).0;
+    static ref IN_FLIGHT: watch::Sender<usize> = watch::channel(0).0;
+    static ref SHUTDOWN_HOOKS: Mutex<Vec<(u64, ShutdownHook)>> = Mutex::new(vec![]);
+}
+
+static LISTEN_FOR_SIGNALS: Once = Once::new();
+static NEXT_SHUTDOWN_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+
+pub fn is_shutting_down() -> bool {
+    *SHUTTING_DOWN.borrow()
+}
+
+/// Resolves once rindexer starts shutting down
+pub async fn shutdown_signal() {
+    let mut shutting_down = SHUTTING_DOWN.subscribe();
+    let _ = shutting_down.wait_for(|shutting_down| *shutting_down).await;
+}
+
+/// ctrl-c or SIGTERM so kubernetes rollouts shut down the same way as a terminal
+async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(e) => {
+                error!("Could not listen for SIGTERM, only ctrl-c will shut down cleanly: {}", e);
+                let _ = signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+    }
+}
+
+/// Starts shutting down on the first ctrl-c or SIGTERM, a second one exits straight away
+pub fn listen_for_shutdown_signals() {
+    LISTEN_FOR_SIGNALS.call_once(|| {
+        tokio::spawn(async {
+            termination_signal().await;
+            info!("Shutting down, waiting for in-flight work to finish - send the signal again to exit straight away");
+            SHUTTING_DOWN.send_replace(true);
+
+            tokio::select! {
+                _ = termination_signal() => {
+                    warn!("Exiting without waiting for in-flight work to finish");
+                }
+                _ = tokio::time::sleep(SHUTDOWN_TIMEOUT) => {
+                    warn!("rindexer did not shut down within {:?}, exiting", SHUTDOWN_TIMEOUT);
+                }
+            }
+            shutdown_telemetry();
+            std::process::exit(1);
+        });
+    });
+}
+
+struct InFlightGuard;
+
+impl InFlightGuard {
+    fn new() -> Self {
+        IN_FLIGHT.send_modify(|in_flight| *in_flight += 1);
+        InFlightGuard
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.send_modify(|in_flight| *in_flight -= 1);
+    }
+}
+
+/// Spawns work shutdown waits for, like handling a batch of events or committing the last synced
+/// block
+pub fn spawn_in_flight<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let guard = InFlightGuard::new();
+    tokio::spawn(async move {
+        let _guard = guard;
+        future.await
+    })
+}
+
+/// Runs once the in-flight work has finished, used to close producers cleanly, returns the id
+/// to remove the hook with when what it closes goes away before shutdown
+pub fn on_shutdown<F>(hook: F) -> u64
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let id = NEXT_SHUTDOWN_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut hooks) = SHUTDOWN_HOOKS.lock() {
+        hooks.push((id, Box::pin(hook)));
+    }
+    id
+}
+
+pub fn remove_shutdown_hook(id: u64) {
+    if let Ok(mut hooks) = SHUTDOWN_HOOKS.lock() {
+        hooks.retain(|(hook_id, _)| *hook_id != id);
+    }
+}
+
+/// Waits for the in-flight work to finish within the timeout then runs the shutdown hooks
+pub async fn finish_in_flight_work() {
+    let mut in_flight = IN_FLIGHT.subscribe();
+    if tokio::time::timeout(IN_FLIGHT_TIMEOUT, in_flight.wait_for(|in_flight| *in_flight == 0))
+        .await
+        .is_err()
+    {
+        warn!(
+            "In-flight work did not finish within {:?}, {} tasks will be cut off",
+            IN_FLIGHT_TIMEOUT,
+            *IN_FLIGHT.borrow()
+        );
+    }
+
+    let hooks: Vec<ShutdownHook> = SHUTDOWN_HOOKS
+        .lock()
+        .map(|mut hooks| hooks.drain(..).map(|(_, hook)| hook).collect())
+        .unwrap_or_default();
+    join_all(hooks).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_finish_in_flight_work() {
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        let handle = spawn_in_flight(async move {
+            let _ = receiver.await;
+        });
+        assert_eq!(*IN_FLIGHT.borrow(), 1);
+
+        let finished = tokio::spawn(finish_in_flight_work());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!finished.is_finished());
+
+        sender.send(()).unwrap();
+        handle.await.unwrap();
+        finished.await.unwrap();
+        assert_eq!(*IN_FLIGHT.borrow(), 0);
+    }
+}
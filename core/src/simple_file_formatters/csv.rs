@@ -30,6 +30,7 @@ impl AsyncCsvAppender {
             let mut writer = Writer::from_writer(file);
 
             writer.write_record(data)?;
+            writer.flush()?;
 
             Ok(())
         })
@@ -49,6 +50,7 @@ impl AsyncCsvAppender {
             for record in records {
                 writer.write_record(record)?;
             }
+            writer.flush()?;
 
             Ok(())
         })
@@ -66,6 +68,7 @@ impl AsyncCsvAppender {
             let mut writer = Writer::from_writer(file);
 
             writer.write_record(header)?;
+            writer.flush()?;
 
             Ok(())
         })
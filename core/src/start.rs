@@ -1,10 +1,14 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{fmt, path::PathBuf, str::FromStr, sync::Arc, time::Instant};
 
-use tokio::signal;
-use tracing::{error, info};
+use ethers::types::U64;
+use futures::future::join_all;
+use tracing::{error, info, warn};
 
 use crate::{
-    api::{start_graphql_server, GraphqlOverrideSettings, StartGraphqlServerError},
+    api::{
+        start_graphql_server, start_rest_server, GraphqlOverrideSettings, RestOverrideSettings,
+        StartGraphqlServerError,
+    },
     database::postgres::{
         client::PostgresConnectionError,
         indexes::{ApplyPostgresIndexesError, PostgresIndexResult},
@@ -12,28 +16,136 @@ use crate::{
         setup::{setup_postgres, SetupPostgresError},
     },
     event::callback_registry::EventCallbackRegistry,
+    health::start_health_server,
     indexer::{
         no_code::{setup_no_code, SetupNoCodeError},
+        reload::watch_manifest,
         start::{start_indexing, StartIndexingError},
         ContractEventDependencies, ContractEventDependenciesMapFromRelationshipsError,
+        IndexingEventsProgressState,
     },
     load_env_from_path,
+    logger::{setup_info_logger_with_telemetry, shutdown_telemetry},
     manifest::{
         core::ProjectType,
         storage::RelationshipsAndIndexersError,
         yaml::{read_manifest, ReadManifestError},
     },
-    setup_info_logger,
+    shutdown::{
+        finish_in_flight_work, is_shutting_down, listen_for_shutdown_signals, shutdown_signal,
+    },
 };
 
+/// How far a one-shot backfill indexes before rindexer exits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexUntil {
+    /// The latest block when indexing starts
+    Latest,
+    Block(U64),
+}
+
+impl IndexUntil {
+    fn until_block(&self) -> Option<U64> {
+        match self {
+            IndexUntil::Latest => None,
+            IndexUntil::Block(block) => Some(*block),
+        }
+    }
+}
+
+impl fmt::Display for IndexUntil {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexUntil::Latest => write!(f, "latest"),
+            IndexUntil::Block(block) => write!(f, "{}", block),
+        }
+    }
+}
+
+impl FromStr for IndexUntil {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "latest" => Ok(IndexUntil::Latest),
+            block => U64::from_dec_str(block)
+                .map(IndexUntil::Block)
+                .map_err(|_| format!("Invalid until {} - must be latest or a block number", s)),
+        }
+    }
+}
+
+/// Logs how far every event got, returns the events which did not reach their end block
+async fn backfill_summary(started: Instant) -> Vec<String> {
+    let Some(state) = IndexingEventsProgressState::current() else {
+        return vec![];
+    };
+
+    let mut incomplete = vec![];
+    for event in &state.lock().await.events {
+        let complete = event.last_synced_block >= event.syncing_to_block;
+        info!(
+            "{} - network {} - indexed blocks {} to {} of {} - {}",
+            event.info_log,
+            event.network,
+            event.starting_block,
+            event.last_synced_block,
+            event.syncing_to_block,
+            if complete { "complete" } else { "incomplete" }
+        );
+        if !complete {
+            incomplete.push(format!("{} on {}", event.info_log, event.network));
+        }
+    }
+
+    info!("Backfill finished in {:.2?}", started.elapsed());
+    incomplete
+}
+
 pub struct IndexingDetails {
     pub registry: EventCallbackRegistry,
+    /// Set by `--until` or `--no-live` to run a one-shot backfill which exits once done
+    pub until: Option<IndexUntil>,
+}
+
+impl IndexingDetails {
+    /// Indexes the registered events and keeps following the chain
+    pub fn new(registry: EventCallbackRegistry) -> Self {
+        Self { registry, until: None }
+    }
+
+    pub fn with_until(mut self, until: Option<IndexUntil>) -> Self {
+        self.until = until;
+        self
+    }
 }
 
 pub struct StartDetails<'a> {
     pub manifest_path: &'a PathBuf,
     pub indexing_details: Option<IndexingDetails>,
     pub graphql_details: GraphqlOverrideSettings,
+    pub rest_details: RestOverrideSettings,
+}
+
+impl<'a> StartDetails<'a> {
+    /// Starts without the REST API, which is all projects generated before it existed set
+    pub fn new(
+        manifest_path: &'a PathBuf,
+        indexing_details: Option<IndexingDetails>,
+        graphql_details: GraphqlOverrideSettings,
+    ) -> Self {
+        Self {
+            manifest_path,
+            indexing_details,
+            graphql_details,
+            rest_details: RestOverrideSettings::default(),
+        }
+    }
+
+    pub fn with_rest_details(mut self, rest_details: RestOverrideSettings) -> Self {
+        self.rest_details = rest_details;
+        self
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -72,6 +184,9 @@ pub enum StartRindexerError {
 
     #[error("{0}")]
     RelationshipsAndIndexersError(#[from] RelationshipsAndIndexersError),
+
+    #[error("Backfill did not complete for: {0}")]
+    BackfillIncomplete(String),
 }
 
 pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindexerError> {
@@ -80,35 +195,91 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
         Some(project_path) => {
             load_env_from_path(project_path);
             let manifest = Arc::new(read_manifest(details.manifest_path)?);
+            let until = details.indexing_details.as_ref().and_then(|details| details.until);
+            let started = Instant::now();
 
             if manifest.project_type != ProjectType::NoCode {
-                setup_info_logger();
+                setup_info_logger_with_telemetry(&manifest);
                 info!("Starting rindexer rust project");
             }
 
-            // Spawn a separate task for the GraphQL server if specified
-            let graphql_server_handle =
-                if details.graphql_details.enabled && manifest.storage.postgres_enabled() {
-                    let manifest_clone = Arc::clone(&manifest);
-                    let indexer = manifest_clone.to_indexer();
-                    let mut graphql_settings = manifest.graphql.clone().unwrap_or_default();
-                    if let Some(override_port) = &details.graphql_details.override_port {
-                        graphql_settings.set_port(*override_port);
+            // the servers and indexer all stop on ctrl-c or SIGTERM
+            listen_for_shutdown_signals();
+
+            // a one-shot backfill only indexes so nothing keeps it running once done
+            let (graphql_enabled, rest_enabled) = match until {
+                Some(until) => {
+                    match until {
+                        IndexUntil::Latest => {
+                            info!("Backfilling up to the latest block, rindexer exits once done")
+                        }
+                        IndexUntil::Block(block) => {
+                            info!("Backfilling up to block {}, rindexer exits once done", block)
+                        }
                     }
-                    Some(tokio::spawn(async move {
-                        if let Err(e) = start_graphql_server(&indexer, &graphql_settings).await {
+                    if details.graphql_details.enabled || details.rest_details.enabled {
+                        warn!("GraphQL and REST are not started for a backfill until a block");
+                    }
+                    (false, false)
+                }
+                _ => (details.graphql_details.enabled, details.rest_details.enabled),
+            };
+
+            // Spawn a separate task for each server, they return true once they have shut down
+            let mut server_handles = vec![];
+            if graphql_enabled && manifest.storage.postgres_enabled() {
+                let indexer = manifest.to_indexer();
+                let mut graphql_settings = manifest.graphql.clone().unwrap_or_default();
+                if let Some(override_port) = &details.graphql_details.override_port {
+                    graphql_settings.set_port(*override_port);
+                }
+                let project_path = project_path.to_path_buf();
+                server_handles.push(tokio::spawn(async move {
+                    match start_graphql_server(&project_path, &indexer, &graphql_settings).await {
+                        Ok(_) => true,
+                        Err(e) => {
                             error!("Failed to start GraphQL server: {:?}", e);
+                            false
                         }
-                    }))
-                } else {
-                    None
-                };
-
-            if graphql_server_handle.is_none() && details.graphql_details.enabled {
+                    }
+                }));
+            } else if graphql_enabled {
                 error!("GraphQL can not run without postgres storage enabled, you have tried to run GraphQL which will now be skipped.");
             }
 
+            if rest_enabled && manifest.storage.postgres_enabled() {
+                let indexer = manifest.to_indexer();
+                let mut rest_settings = manifest.rest.clone().unwrap_or_default();
+                if let Some(override_port) = &details.rest_details.override_port {
+                    rest_settings.set_port(*override_port);
+                }
+                let project_path = project_path.to_path_buf();
+                server_handles.push(tokio::spawn(async move {
+                    match start_rest_server(&project_path, &indexer, &rest_settings).await {
+                        Ok(_) => true,
+                        Err(e) => {
+                            error!("Failed to start REST server: {:?}", e);
+                            false
+                        }
+                    }
+                }));
+            } else if rest_enabled {
+                error!("REST can not run without postgres storage enabled, you have tried to run REST which will now be skipped.");
+            }
+
+            let servers_enabled = !server_handles.is_empty();
+            let servers_handle =
+                if servers_enabled { Some(tokio::spawn(join_all(server_handles))) } else { None };
+
             if let Some(mut indexing_details) = details.indexing_details {
+                if let Some(health_settings) = manifest.health.clone() {
+                    tokio::spawn(async move {
+                        if let Err(e) = start_health_server(&health_settings).await {
+                            error!("Failed to start health server: {:?}", e);
+                        }
+                    });
+                }
+
                 let postgres_enabled = &manifest.storage.postgres_enabled();
 
                 // setup postgres is already called in no-code startup
@@ -133,25 +304,30 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
                     project_path,
                     &dependencies,
                     // we index all the historic data first before then applying FKs
-                    !relationships.is_empty(),
+                    !relationships.is_empty() || until.is_some(),
+                    until.and_then(|until| until.until_block()),
                     indexing_details.registry.complete(),
                 )
                 .await?;
 
-                // TODO if graphql isn't up yet, and we apply this on graphql wont refresh we need
-                // to handle this
-                info!(
-                    "Applying indexes if any back to the database as historic resync is complete"
-                );
-                PostgresIndexResult::apply_indexes(postgres_indexes).await?;
+                // the indexes and relationships are applied again on the next start when rindexer
+                // shut down before the historic resync completed
+                if !is_shutting_down() {
+                    // TODO if graphql isn't up yet, and we apply this on graphql wont refresh we
+                    // need to handle this
+                    info!(
+                        "Applying indexes if any back to the database as historic resync is complete"
+                    );
+                    PostgresIndexResult::apply_indexes(postgres_indexes).await?;
+                }
 
-                if !relationships.is_empty() {
+                if !relationships.is_empty() && !is_shutting_down() {
                     // TODO if graphql isn't up yet, and we apply this on graphql wont refresh we
                     // need to handle this
                     info!("Applying constraints relationships back to the database as historic resync is complete");
                     Relationship::apply_all(&relationships).await?;
 
-                    if manifest.has_any_contracts_live_indexing() {
+                    if manifest.has_any_contracts_live_indexing() && until.is_none() {
                         info!("Starting live indexing now relationship re-applied..");
 
                         if dependencies.is_empty() {
@@ -166,6 +342,7 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
                             project_path,
                             &dependencies,
                             false,
+                            None,
                             indexing_details
                                 .registry
                                 .reapply_after_historic(processed_network_contracts),
@@ -175,27 +352,39 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
                     }
                 }
 
-                // keep graphql alive even if indexing has finished
-                if details.graphql_details.enabled {
-                    signal::ctrl_c()
-                        .await
-                        .map_err(|_| StartRindexerError::FailedToListenToGraphqlSocket)?;
-                } else {
+                // keep the servers alive even if indexing has finished
+                if servers_enabled && !is_shutting_down() {
+                    shutdown_signal().await;
+                } else if !is_shutting_down() {
                     info!("rindexer resync is complete");
-                    // to avoid the thread closing before the stream is consumed
-                    // lets just sit here for 5 seconds to avoid the race
-                    // 100% a better way to handle this
-                    // TODO - handle this nicer
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+
+                // wait for the handlers, stream publishes and last synced blocks still running
+                finish_in_flight_work().await;
+
+                if until.is_some() {
+                    let incomplete = backfill_summary(started).await;
+                    if is_shutting_down() || !incomplete.is_empty() {
+                        shutdown_telemetry();
+                        return Err(StartRindexerError::BackfillIncomplete(
+                            if incomplete.is_empty() {
+                                "rindexer shut down before it finished".to_string()
+                            } else {
+                                incomplete.join(", ")
+                            },
+                        ));
+                    }
                 }
             }
 
-            // Await the GraphQL server task if it was started
-            if let Some(handle) = graphql_server_handle {
-                handle.await.unwrap_or_else(|e| {
-                    error!("GraphQL server task failed: {:?}", e);
-                });
+            // Await the server tasks if they were started
+            if let Some(handle) = servers_handle {
+                if let Err(e) = handle.await {
+                    error!("Server task failed: {:?}", e);
+                }
             }
+
+            shutdown_telemetry();
         }
         None => {
             return Err(StartRindexerError::NoProjectPathFoundUsingParentOfManifestPath);
@@ -207,12 +396,17 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
 
 pub struct IndexerNoCodeDetails {
     pub enabled: bool,
+    /// Apply contract changes in the rindexer.yaml without restarting
+    pub watch: bool,
+    /// Set by `--until` or `--no-live` to run a one-shot backfill which exits once done
+    pub until: Option<IndexUntil>,
 }
 
 pub struct StartNoCodeDetails<'a> {
     pub manifest_path: &'a PathBuf,
     pub indexing_details: IndexerNoCodeDetails,
     pub graphql_details: GraphqlOverrideSettings,
+    pub rest_details: RestOverrideSettings,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -227,7 +421,37 @@ pub enum StartRindexerNoCode {
 pub async fn start_rindexer_no_code(
     details: StartNoCodeDetails<'_>,
 ) -> Result<(), StartRindexerNoCode> {
+    let mut watch = details.indexing_details.enabled && details.indexing_details.watch;
+    if watch && details.indexing_details.until.is_some() {
+        warn!("Not watching the rindexer.yaml as a backfill until a block exits once done");
+        watch = false;
+    }
     let start_details = setup_no_code(details).await?;
 
+    if watch {
+        if let Some(project_path) = start_details.manifest_path.parent() {
+            tokio::spawn(watch_manifest(
+                project_path.to_path_buf(),
+                start_details.manifest_path.to_path_buf(),
+            ));
+        }
+    }
+
     start_rindexer(start_details).await.map_err(StartRindexerNoCode::StartRindexerError)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_until() {
+        assert_eq!("latest".parse::<IndexUntil>(), Ok(IndexUntil::Latest));
+        assert_eq!("19000000".parse::<IndexUntil>(), Ok(IndexUntil::Block(U64::from(19_000_000))));
+        assert!("finalized".parse::<IndexUntil>().is_err());
+        assert_eq!(
+            IndexUntil::Block(U64::from(10)).to_string().parse(),
+            Ok(IndexUntil::Block(U64::from(10)))
+        );
+    }
+}
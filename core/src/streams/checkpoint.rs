@@ -0,0 +1,543 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use aws_config::{meta::region::RegionProviderChain, BehaviorVersion, Region};
+use aws_sdk_s3::{config::Credentials, primitives::ByteStream, Client as S3Client};
+use ethers::types::U64;
+use redis::{aio::ConnectionManager, AsyncCommands, Script};
+use rust_decimal::Decimal;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::{
+    database::postgres::client::{PostgresConnectionError, PostgresError},
+    helpers::camel_to_snake,
+    indexer::last_synced::{
+        get_last_synced_block_number_file, write_last_synced_block_number_file,
+        UpdateLastSyncedBlockNumberFile,
+    },
+    manifest::stream::{StreamCheckpointConfig, StreamSink, StreamsConfig},
+    types::aws_config::AwsConfig,
+    EthereumSqlTypeWrapper, PostgresClient,
+};
+
+// only moves the checkpoint forward so out of order batches can not rewind it
+const REDIS_SET_IF_HIGHER_SCRIPT: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if not current or tonumber(ARGV[1]) > tonumber(current) then
+    redis.call('SET', KEYS[1], ARGV[1])
+end
+return 1
+"#;
+
+#[derive(thiserror::Error, Debug)]
+pub enum StreamCheckpointError {
+    #[error("{0}")]
+    PostgresConnection(#[from] PostgresConnectionError),
+
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] PostgresError),
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+
+    #[error("S3 error: {0}")]
+    S3(String),
+
+    #[error("{0}")]
+    File(#[from] UpdateLastSyncedBlockNumberFile),
+
+    #[error("Failed to parse checkpoint block number: {0}")]
+    InvalidBlockNumber(String),
+}
+
+type SinkCheckpoints = HashMap<StreamSink, Option<U64>>;
+
+enum StreamCheckpointStore {
+    File { project_path: PathBuf },
+    Postgres { client: PostgresClient, table_name: String },
+    Redis { connection: Box<ConnectionManager>, key_prefix: String },
+    S3 { client: S3Client, bucket: String, key_prefix: String },
+}
+
+/// Checkpoints for stream only indexers, each sink keeps its own last synced block so adding a new
+/// sink backfills it without resending to the sinks which are already ahead
+pub struct StreamCheckpoints {
+    store: StreamCheckpointStore,
+    indexer_name: String,
+    contract_name: String,
+    sinks: Vec<StreamSink>,
+    // the checkpoints found on startup keyed by event and network
+    resumed: Mutex<HashMap<(String, String), SinkCheckpoints>>,
+    // the sinks whose last publish failed keyed by event and network
+    failed: Mutex<HashSet<(String, String, StreamSink)>>,
+    write_lock: Mutex<()>,
+}
+
+impl StreamCheckpoints {
+    pub async fn new(
+        config: &StreamsConfig,
+        project_path: &Path,
+        indexer_name: &str,
+        contract_name: &str,
+    ) -> Result<Self, StreamCheckpointError> {
+        let sinks = config.sinks();
+        let store = match &config.checkpoint {
+            Some(checkpoint) => {
+                Self::create_store(checkpoint, project_path, indexer_name, contract_name, &sinks)
+                    .await?
+            }
+            None => {
+                Self::create_file_store(project_path, contract_name, &sinks).await;
+                StreamCheckpointStore::File { project_path: project_path.to_path_buf() }
+            }
+        };
+
+        Ok(Self {
+            store,
+            indexer_name: indexer_name.to_string(),
+            contract_name: contract_name.to_string(),
+            sinks,
+            resumed: Mutex::new(HashMap::new()),
+            failed: Mutex::new(HashSet::new()),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    async fn create_store(
+        config: &StreamCheckpointConfig,
+        project_path: &Path,
+        indexer_name: &str,
+        contract_name: &str,
+        sinks: &[StreamSink],
+    ) -> Result<StreamCheckpointStore, StreamCheckpointError> {
+        if config.postgres_enabled() {
+            let client = PostgresClient::new().await?;
+            let table_name =
+                format!("rindexer_internal.{}_stream_checkpoints", camel_to_snake(indexer_name));
+            client
+                .batch_execute(&format!(
+                    r#"
+                    CREATE SCHEMA IF NOT EXISTS rindexer_internal;
+                    CREATE TABLE IF NOT EXISTS {} (
+                        contract_name TEXT NOT NULL,
+                        event_name TEXT NOT NULL,
+                        network TEXT NOT NULL,
+                        sink TEXT NOT NULL,
+                        last_synced_block NUMERIC NOT NULL,
+                        PRIMARY KEY (contract_name, event_name, network, sink)
+                    );
+                    "#,
+                    table_name
+                ))
+                .await?;
+
+            return Ok(StreamCheckpointStore::Postgres { client, table_name });
+        }
+
+        if let Some(redis) = &config.redis {
+            let client = redis::Client::open(redis.connection_uri.as_str())?;
+            let connection = ConnectionManager::new(client).await?;
+
+            return Ok(StreamCheckpointStore::Redis {
+                connection: Box::new(connection),
+                key_prefix: redis.key_prefix.clone().unwrap_or_else(|| "rindexer:".to_string()),
+            });
+        }
+
+        if let Some(s3) = &config.s3 {
+            return Ok(StreamCheckpointStore::S3 {
+                client: Self::create_s3_client(&s3.aws_config, &s3.endpoint).await,
+                bucket: s3.bucket.clone(),
+                key_prefix: s3.key_prefix.clone().unwrap_or_else(|| "rindexer/".to_string()),
+            });
+        }
+
+        Self::create_file_store(project_path, contract_name, sinks).await;
+        Ok(StreamCheckpointStore::File { project_path: project_path.to_path_buf() })
+    }
+
+    async fn create_file_store(project_path: &Path, contract_name: &str, sinks: &[StreamSink]) {
+        for sink in sinks {
+            StreamsConfig::create_full_streams_last_synced_block_path(
+                project_path,
+                contract_name,
+                *sink,
+            )
+            .await;
+        }
+    }
+
+    async fn create_s3_client(config: &AwsConfig, endpoint: &Option<String>) -> S3Client {
+        let region_provider = RegionProviderChain::first_try(Region::new(config.region.clone()));
+
+        let credentials_provider = Credentials::new(
+            &config.access_key,
+            &config.secret_key,
+            config.session_token.clone(),
+            None,
+            "manual",
+        );
+
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(region_provider)
+            .credentials_provider(credentials_provider)
+            .load()
+            .await;
+
+        let mut s3_config = aws_sdk_s3::config::Builder::from(&sdk_config);
+        if let Some(endpoint) = endpoint {
+            // s3 compatible stores generally do not support virtual hosted buckets
+            s3_config = s3_config.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        S3Client::from_conf(s3_config.build())
+    }
+
+    pub fn sinks(&self) -> &[StreamSink] {
+        &self.sinks
+    }
+
+    fn file_path(&self, project_path: &Path, sink: StreamSink) -> PathBuf {
+        project_path.join(StreamsConfig::get_streams_last_synced_block_path(sink))
+    }
+
+    fn key(&self, sink: StreamSink, event_name: &str, network: &str, separator: &str) -> String {
+        [
+            camel_to_snake(&self.indexer_name),
+            camel_to_snake(&self.contract_name),
+            camel_to_snake(event_name),
+            network.to_lowercase(),
+            sink.to_string(),
+        ]
+        .join(separator)
+    }
+
+    pub async fn get(
+        &self,
+        sink: StreamSink,
+        event_name: &str,
+        network: &str,
+    ) -> Result<Option<U64>, StreamCheckpointError> {
+        let block = match &self.store {
+            StreamCheckpointStore::File { project_path } => {
+                get_last_synced_block_number_file(
+                    &self.file_path(project_path, sink),
+                    &self.contract_name,
+                    network,
+                    event_name,
+                )
+                .await?
+            }
+            StreamCheckpointStore::Postgres { client, table_name } => {
+                let row = client
+                    .query_one_or_none(
+                        &format!(
+                            "SELECT last_synced_block FROM {} WHERE contract_name = $1 AND event_name = $2 AND network = $3 AND sink = $4",
+                            table_name
+                        ),
+                        &[&self.contract_name, &event_name, &network, &sink.as_str()],
+                    )
+                    .await?;
+
+                match row {
+                    Some(row) => {
+                        let value: Decimal = row.get("last_synced_block");
+                        Some(parse_block(&value.to_string())?)
+                    }
+                    None => None,
+                }
+            }
+            StreamCheckpointStore::Redis { connection, key_prefix } => {
+                let key = format!("{}{}", key_prefix, self.key(sink, event_name, network, ":"));
+                let value: Option<String> = connection.as_ref().clone().get(key).await?;
+                value.map(|value| parse_block(&value)).transpose()?
+            }
+            StreamCheckpointStore::S3 { client, bucket, key_prefix } => {
+                let key = format!("{}{}.txt", key_prefix, self.key(sink, event_name, network, "/"));
+                Self::get_s3_object(client, bucket, &key).await?
+            }
+        };
+
+        Ok(block.filter(|block| !block.is_zero()))
+    }
+
+    async fn get_s3_object(
+        client: &S3Client,
+        bucket: &str,
+        key: &str,
+    ) -> Result<Option<U64>, StreamCheckpointError> {
+        match client.get_object().bucket(bucket).key(key).send().await {
+            Ok(output) => {
+                let body = output
+                    .body
+                    .collect()
+                    .await
+                    .map_err(|e| StreamCheckpointError::S3(e.to_string()))?;
+                let value = String::from_utf8_lossy(&body.into_bytes()).to_string();
+                Ok(Some(parse_block(&value)?))
+            }
+            Err(e) => {
+                if e.as_service_error().is_some_and(|e| e.is_no_such_key()) {
+                    Ok(None)
+                } else {
+                    Err(StreamCheckpointError::S3(e.to_string()))
+                }
+            }
+        }
+    }
+
+    pub async fn set(
+        &self,
+        sink: StreamSink,
+        event_name: &str,
+        network: &str,
+        to_block: U64,
+    ) -> Result<(), StreamCheckpointError> {
+        match &self.store {
+            StreamCheckpointStore::File { project_path } => {
+                let _guard = self.write_lock.lock().await;
+                write_last_synced_block_number_file(
+                    &self.file_path(project_path, sink),
+                    &self.contract_name,
+                    network,
+                    event_name,
+                    to_block,
+                )
+                .await?;
+            }
+            StreamCheckpointStore::Postgres { client, table_name } => {
+                client
+                    .execute(
+                        &format!(
+                            "INSERT INTO {table_name} (contract_name, event_name, network, sink, last_synced_block) VALUES ($1, $2, $3, $4, $5) \
+                             ON CONFLICT (contract_name, event_name, network, sink) DO UPDATE SET last_synced_block = GREATEST({table_name}.last_synced_block, EXCLUDED.last_synced_block)"
+                        ),
+                        &[
+                            &self.contract_name,
+                            &event_name,
+                            &network,
+                            &sink.as_str(),
+                            &EthereumSqlTypeWrapper::U64(to_block),
+                        ],
+                    )
+                    .await?;
+            }
+            StreamCheckpointStore::Redis { connection, key_prefix } => {
+                let key = format!("{}{}", key_prefix, self.key(sink, event_name, network, ":"));
+                Script::new(REDIS_SET_IF_HIGHER_SCRIPT)
+                    .key(key)
+                    .arg(to_block.as_u64())
+                    .invoke_async::<()>(&mut connection.as_ref().clone())
+                    .await?;
+            }
+            StreamCheckpointStore::S3 { client, bucket, key_prefix } => {
+                let key = format!("{}{}.txt", key_prefix, self.key(sink, event_name, network, "/"));
+
+                let _guard = self.write_lock.lock().await;
+                let last_block = Self::get_s3_object(client, bucket, &key).await?;
+                if last_block.is_some_and(|last_block| last_block >= to_block) {
+                    return Ok(());
+                }
+
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .body(ByteStream::from(to_block.to_string().into_bytes()))
+                    .send()
+                    .await
+                    .map_err(|e| StreamCheckpointError::S3(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads every sink checkpoint and returns the block the indexer should resume from, a sink
+    /// without a checkpoint means the indexer has to start from the beginning to backfill it
+    pub async fn last_synced_block(
+        &self,
+        event_name: &str,
+        network: &str,
+    ) -> Result<Option<U64>, StreamCheckpointError> {
+        let checkpoints = self.load_checkpoints(event_name, network).await?;
+
+        let missing: Vec<String> = checkpoints
+            .iter()
+            .filter(|(_, block)| block.is_none())
+            .map(|(sink, _)| sink.to_string())
+            .collect();
+        if !missing.is_empty() && missing.len() < checkpoints.len() {
+            warn!(
+                "{}::{} - network {} - stream sinks {} have no checkpoint and will be backfilled from the start block, sinks with a checkpoint will skip blocks they already streamed",
+                self.contract_name,
+                event_name,
+                network,
+                missing.join(", ")
+            );
+        }
+
+        Ok(resume_from(&checkpoints))
+    }
+
+    async fn load_checkpoints(
+        &self,
+        event_name: &str,
+        network: &str,
+    ) -> Result<SinkCheckpoints, StreamCheckpointError> {
+        let mut resumed = self.resumed.lock().await;
+        let key = (event_name.to_string(), network.to_string());
+        if let Some(checkpoints) = resumed.get(&key) {
+            return Ok(checkpoints.clone());
+        }
+
+        let mut checkpoints = HashMap::new();
+        for sink in &self.sinks {
+            checkpoints.insert(*sink, self.get(*sink, event_name, network).await?);
+        }
+
+        resumed.insert(key, checkpoints.clone());
+
+        Ok(checkpoints)
+    }
+
+    /// If the sink had already streamed up to this block when the indexer started
+    pub async fn is_sink_ahead(
+        &self,
+        sink: StreamSink,
+        event_name: &str,
+        network: &str,
+        to_block: &U64,
+    ) -> Result<bool, StreamCheckpointError> {
+        let checkpoints = self.load_checkpoints(event_name, network).await?;
+
+        Ok(checkpoints.get(&sink).copied().flatten().is_some_and(|block| block >= *to_block))
+    }
+
+    /// Records if the last publish of a sink succeeded, a failed sink is not moved forward until it
+    /// publishes again
+    pub async fn record_publish(
+        &self,
+        sink: StreamSink,
+        event_name: &str,
+        network: &str,
+        published: bool,
+    ) {
+        let key = (event_name.to_string(), network.to_string(), sink);
+        let mut failed = self.failed.lock().await;
+        if published {
+            failed.remove(&key);
+        } else {
+            failed.insert(key);
+        }
+    }
+
+    /// The sinks which published the blocks up to `to_block`, sinks which were skipped as they
+    /// were already ahead or failed to publish are left as they are
+    async fn published_sinks(
+        &self,
+        event_name: &str,
+        network: &str,
+        to_block: &U64,
+    ) -> Result<Vec<StreamSink>, StreamCheckpointError> {
+        let failed = self.failed.lock().await.clone();
+        let mut published = vec![];
+        for sink in &self.sinks {
+            if failed.contains(&(event_name.to_string(), network.to_string(), *sink)) ||
+                self.is_sink_ahead(*sink, event_name, network, to_block).await?
+            {
+                continue;
+            }
+            published.push(*sink);
+        }
+
+        Ok(published)
+    }
+
+    pub async fn update(
+        &self,
+        event_name: &str,
+        network: &str,
+        to_block: U64,
+    ) -> Result<(), StreamCheckpointError> {
+        for sink in self.published_sinks(event_name, network, &to_block).await? {
+            self.set(sink, event_name, network, to_block).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_block(value: &str) -> Result<U64, StreamCheckpointError> {
+    U64::from_dec_str(value.trim())
+        .map_err(|_| StreamCheckpointError::InvalidBlockNumber(value.trim().to_string()))
+}
+
+fn resume_from(checkpoints: &SinkCheckpoints) -> Option<U64> {
+    if checkpoints.values().any(|block| block.is_none()) {
+        return None;
+    }
+
+    checkpoints.values().flatten().min().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_from_lowest_sink_checkpoint() {
+        let checkpoints = HashMap::from([
+            (StreamSink::Kafka, Some(U64::from(200))),
+            (StreamSink::Sns, Some(U64::from(150))),
+        ]);
+
+        assert_eq!(resume_from(&checkpoints), Some(U64::from(150)));
+    }
+
+    #[test]
+    fn test_resume_from_start_when_sink_has_no_checkpoint() {
+        let checkpoints =
+            HashMap::from([(StreamSink::Kafka, Some(U64::from(200))), (StreamSink::Sns, None)]);
+
+        assert_eq!(resume_from(&checkpoints), None);
+    }
+
+    #[tokio::test]
+    async fn test_update_only_advances_published_sinks() {
+        let project = tempfile::tempdir().unwrap();
+        let config: StreamsConfig = serde_yaml::from_str(
+            r#"
+webhooks:
+  - endpoint: http://localhost
+    shared_secret: secret
+    networks: [ethereum]
+    events: [{ event_name: Transfer }]
+rabbitmq:
+  url: amqp://localhost
+  exchanges:
+    - exchange: transfers
+      exchange_type: fanout
+      networks: [ethereum]
+      events: [{ event_name: Transfer }]
+"#,
+        )
+        .unwrap();
+        let checkpoints =
+            StreamCheckpoints::new(&config, project.path(), "Indexer", "Token").await.unwrap();
+        let block = |sink| checkpoints.get(sink, "Transfer", "ethereum");
+
+        checkpoints.set(StreamSink::Webhooks, "Transfer", "ethereum", 300.into()).await.unwrap();
+        checkpoints.record_publish(StreamSink::RabbitMQ, "Transfer", "ethereum", false).await;
+        checkpoints.update("Transfer", "ethereum", 200.into()).await.unwrap();
+        assert_eq!(block(StreamSink::RabbitMQ).await.unwrap(), None);
+
+        checkpoints.record_publish(StreamSink::RabbitMQ, "Transfer", "ethereum", true).await;
+        checkpoints.update("Transfer", "ethereum", 200.into()).await.unwrap();
+        assert_eq!(block(StreamSink::RabbitMQ).await.unwrap(), Some(200.into()));
+        assert_eq!(block(StreamSink::Webhooks).await.unwrap(), Some(300.into()));
+    }
+}
@@ -1,24 +1,28 @@
-use std::sync::Arc;
+use std::{collections::HashMap, future::Future, sync::Arc};
 
 use aws_sdk_sns::{config::http::HttpResponse, error::SdkError, operation::publish::PublishError};
+use ethers::types::U64;
 use futures::future::join_all;
-use serde_json::Value;
+use serde_json::{json, Value};
 use thiserror::Error;
-use tokio::{
-    task,
-    task::{JoinError, JoinHandle},
-};
-use tracing::error;
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, info_span, Instrument};
 
 use crate::{
-    event::{filter_event_data_by_conditions, EventMessage},
+    abi::EventInfo,
+    chat::Template,
+    event::{ConditionError, Conditions, EventMessage},
     manifest::stream::{
         KafkaStreamConfig, KafkaStreamQueueConfig, RabbitMQStreamConfig, RabbitMQStreamQueueConfig,
-        SNSStreamTopicConfig, StreamEvent, StreamsConfig, WebhookStreamConfig,
+        SNSStreamTopicConfig, StreamEvent, StreamSink, StreamsConfig, WebhookStreamConfig,
     },
+    metrics::record_stream_publish,
+    shutdown::spawn_in_flight,
     streams::{
-        kafka::{Kafka, KafkaError},
-        RabbitMQ, RabbitMQError, Webhook, WebhookError, SNS,
+        kafka::{Kafka, KafkaError, KafkaHeaders, KafkaTopicSchema},
+        RabbitMQ, RabbitMQError, StreamCheckpoints, Webhook, WebhookError, SNS,
+        STREAM_BLOCK_NUMBER_KEY, STREAM_CONTRACT_KEY, STREAM_EVENT_KEY, STREAM_FROM_BLOCK_KEY,
+        STREAM_NETWORK_KEY, STREAM_TO_BLOCK_KEY,
     },
 };
 
@@ -31,6 +35,8 @@ type StreamPublishes = Vec<JoinHandle<Result<usize, StreamError>>>;
 #[derive(Debug, Clone)]
 struct SNSStream {
     config: Vec<SNSStreamTopicConfig>,
+    // the parsed conditions of the event for each topic
+    conditions: Vec<Option<Conditions>>,
     client: Arc<SNS>,
 }
 
@@ -52,48 +58,88 @@ pub enum StreamError {
     JoinError(JoinError),
 }
 
+#[derive(Error, Debug)]
+pub enum StreamsClientsError {
+    #[error("Could not register the Kafka schema for topic {0}: {1}")]
+    KafkaSchemaRegistrationError(String, KafkaError),
+
+    #[error("Could not create the Kafka client: {0}")]
+    KafkaClientError(KafkaError),
+
+    #[error("Invalid stream conditions: {0}")]
+    InvalidConditions(#[from] ConditionError),
+}
+
 #[derive(Debug, Clone)]
 struct WebhookStream {
     config: Vec<WebhookStreamConfig>,
+    conditions: Vec<Option<Conditions>>,
     client: Arc<Webhook>,
 }
 
 pub struct RabbitMQStream {
     config: RabbitMQStreamConfig,
+    conditions: Vec<Option<Conditions>>,
     client: Arc<RabbitMQ>,
 }
 
 pub struct KafkaStream {
     config: KafkaStreamConfig,
+    conditions: Vec<Option<Conditions>>,
     client: Arc<Kafka>,
+    // registered schemas keyed by topic for topics which are not json encoded
+    schemas: HashMap<String, Arc<KafkaTopicSchema>>,
 }
 
 pub struct StreamsClients {
+    contract_name: String,
     sns: Option<SNSStream>,
     webhook: Option<WebhookStream>,
     rabbitmq: Option<RabbitMQStream>,
     kafka: Option<KafkaStream>,
+    checkpoints: Option<Arc<StreamCheckpoints>>,
 }
 
 impl StreamsClients {
-    pub async fn new(stream_config: StreamsConfig) -> Self {
+    pub async fn new(
+        stream_config: StreamsConfig,
+        contract_name: &str,
+        event_info: &EventInfo,
+        checkpoints: Option<Arc<StreamCheckpoints>>,
+    ) -> Result<Self, StreamsClientsError> {
         let sns = if let Some(config) = &stream_config.sns {
             Some(SNSStream {
                 config: config.topics.clone(),
+                conditions: event_conditions(
+                    config.topics.iter().map(|topic| &topic.events),
+                    &event_info.name,
+                )?,
                 client: Arc::new(SNS::new(&config.aws_config).await),
             })
         } else {
             None
         };
 
-        let webhook = stream_config.webhooks.as_ref().map(|config| WebhookStream {
-            config: config.clone(),
-            client: Arc::new(Webhook::new()),
-        });
+        let webhook = if let Some(config) = stream_config.webhooks.as_ref() {
+            Some(WebhookStream {
+                config: config.clone(),
+                conditions: event_conditions(
+                    config.iter().map(|webhook| &webhook.events),
+                    &event_info.name,
+                )?,
+                client: Arc::new(Webhook::new()),
+            })
+        } else {
+            None
+        };
 
         let rabbitmq = if let Some(config) = stream_config.rabbitmq.as_ref() {
             Some(RabbitMQStream {
                 config: config.clone(),
+                conditions: event_conditions(
+                    config.exchanges.iter().map(|exchange| &exchange.events),
+                    &event_info.name,
+                )?,
                 client: Arc::new(RabbitMQ::new(&config.url).await),
             })
         } else {
@@ -101,19 +147,89 @@ impl StreamsClients {
         };
 
         let kafka = if let Some(config) = stream_config.kafka.as_ref() {
+            let mut schemas = HashMap::new();
+            for topic in &config.topics {
+                if topic.encoding.is_json() ||
+                    !topic.events.iter().any(|e| e.event_name == event_info.name)
+                {
+                    continue;
+                }
+
+                let schema = KafkaTopicSchema::register(
+                    config,
+                    &topic.topic,
+                    &topic.encoding,
+                    contract_name,
+                    event_info,
+                )
+                .await
+                .map_err(|e| {
+                    StreamsClientsError::KafkaSchemaRegistrationError(topic.topic.clone(), e)
+                })?;
+                schemas.insert(topic.topic.clone(), Arc::new(schema));
+            }
+
             Some(KafkaStream {
                 config: config.clone(),
+                conditions: event_conditions(
+                    config.topics.iter().map(|topic| &topic.events),
+                    &event_info.name,
+                )?,
                 client: Arc::new(
-                    Kafka::new(config)
-                        .await
-                        .unwrap_or_else(|e| panic!("Failed to create Kafka client: {:?}", e)),
+                    Kafka::new(config).await.map_err(StreamsClientsError::KafkaClientError)?,
                 ),
+                schemas,
             })
         } else {
             None
         };
 
-        Self { sns, webhook, rabbitmq, kafka }
+        Ok(Self {
+            contract_name: contract_name.to_string(),
+            sns,
+            webhook,
+            rabbitmq,
+            kafka,
+            checkpoints,
+        })
+    }
+
+    /// The sinks which had already streamed up to this block when the indexer started
+    async fn sinks_ahead(&self, event_message: &EventMessage, to_block: &U64) -> Vec<StreamSink> {
+        let mut sinks_ahead = vec![];
+        if let Some(checkpoints) = &self.checkpoints {
+            for sink in checkpoints.sinks() {
+                match checkpoints
+                    .is_sink_ahead(
+                        *sink,
+                        &event_message.event_name,
+                        &event_message.network,
+                        to_block,
+                    )
+                    .await
+                {
+                    Ok(true) => sinks_ahead.push(*sink),
+                    Ok(false) => {}
+                    Err(e) => error!("Error loading {} stream checkpoint: {:?}", sink, e),
+                }
+            }
+        }
+
+        sinks_ahead
+    }
+
+    /// Tells the checkpoints if a sink published so a failed sink is not moved forward
+    async fn record_publish(
+        &self,
+        sink: StreamSink,
+        event_message: &EventMessage,
+        published: bool,
+    ) {
+        if let Some(checkpoints) = &self.checkpoints {
+            checkpoints
+                .record_publish(sink, &event_message.event_name, &event_message.network, published)
+                .await;
+        }
     }
 
     fn has_any_streams(&self) -> bool {
@@ -185,24 +301,12 @@ impl StreamsClients {
 
     fn filter_chunk_event_data_by_conditions(
         &self,
-        events: &[StreamEvent],
-        event_message: &EventMessage,
+        conditions: Option<&Conditions>,
         chunk: &[Value],
     ) -> Vec<Value> {
-        let stream_event = events
-            .iter()
-            .find(|e| e.event_name == event_message.event_name)
-            .expect("Failed to find stream event - should never happen please raise an issue");
-
         let filtered_chunk: Vec<Value> = chunk
             .iter()
-            .filter(|event_data| {
-                if let Some(conditions) = &stream_event.conditions {
-                    filter_event_data_by_conditions(event_data, conditions)
-                } else {
-                    true
-                }
-            })
+            .filter(|event_data| conditions.is_none_or(|conditions| conditions.matches(event_data)))
             .cloned()
             .collect();
 
@@ -212,6 +316,7 @@ impl StreamsClients {
     fn sns_stream_tasks(
         &self,
         config: &SNSStreamTopicConfig,
+        conditions: Option<&Conditions>,
         client: Arc<SNS>,
         id: &str,
         event_message: &EventMessage,
@@ -221,18 +326,15 @@ impl StreamsClients {
             .iter()
             .enumerate()
             .map(|(index, chunk)| {
-                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
-                    &config.events,
-                    event_message,
-                    chunk,
-                );
+                let filtered_chunk: Vec<Value> =
+                    self.filter_chunk_event_data_by_conditions(conditions, chunk);
 
                 let publish_message_id =
                     self.generate_publish_message_id(id, index, &config.prefix_id);
                 let client = Arc::clone(&client);
                 let topic_arn = config.topic_arn.clone();
                 let publish_message = self.create_chunk_message_raw(event_message, &filtered_chunk);
-                task::spawn(async move {
+                spawn_publish(StreamSink::Sns, async move {
                     let _ =
                         client.publish(&publish_message_id, &topic_arn, &publish_message).await?;
 
@@ -247,6 +349,7 @@ impl StreamsClients {
     fn webhook_stream_tasks(
         &self,
         config: &WebhookStreamConfig,
+        conditions: Option<&Conditions>,
         client: Arc<Webhook>,
         id: &str,
         event_message: &EventMessage,
@@ -256,11 +359,8 @@ impl StreamsClients {
             .iter()
             .enumerate()
             .map(|(index, chunk)| {
-                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
-                    &config.events,
-                    event_message,
-                    chunk,
-                );
+                let filtered_chunk: Vec<Value> =
+                    self.filter_chunk_event_data_by_conditions(conditions, chunk);
 
                 let publish_message_id = self.generate_publish_message_id(id, index, &None);
                 let endpoint = config.endpoint.clone();
@@ -268,7 +368,7 @@ impl StreamsClients {
                 let client = Arc::clone(&client);
                 let publish_message =
                     self.create_chunk_message_json(event_message, &filtered_chunk);
-                task::spawn(async move {
+                spawn_publish(StreamSink::Webhooks, async move {
                     client
                         .publish(&publish_message_id, &endpoint, &shared_secret, &publish_message)
                         .await?;
@@ -284,6 +384,7 @@ impl StreamsClients {
     fn rabbitmq_stream_tasks(
         &self,
         config: &RabbitMQStreamQueueConfig,
+        conditions: Option<&Conditions>,
         client: Arc<RabbitMQ>,
         id: &str,
         event_message: &EventMessage,
@@ -293,11 +394,8 @@ impl StreamsClients {
             .iter()
             .enumerate()
             .map(|(index, chunk)| {
-                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
-                    &config.events,
-                    event_message,
-                    chunk,
-                );
+                let filtered_chunk: Vec<Value> =
+                    self.filter_chunk_event_data_by_conditions(conditions, chunk);
 
                 let publish_message_id = self.generate_publish_message_id(id, index, &None);
                 let client = Arc::clone(&client);
@@ -307,7 +405,7 @@ impl StreamsClients {
                 let publish_message =
                     self.create_chunk_message_json(event_message, &filtered_chunk);
 
-                task::spawn(async move {
+                spawn_publish(StreamSink::RabbitMQ, async move {
                     client
                         .publish(
                             &publish_message_id,
@@ -324,34 +422,57 @@ impl StreamsClients {
         tasks
     }
 
+    fn kafka_headers(&self, event_message: &EventMessage) -> KafkaHeaders {
+        vec![
+            (STREAM_NETWORK_KEY, event_message.network.clone()),
+            (STREAM_CONTRACT_KEY, self.contract_name.clone()),
+            (STREAM_EVENT_KEY, event_message.event_name.clone()),
+        ]
+    }
+
     fn kafka_stream_tasks(
         &self,
         config: &KafkaStreamQueueConfig,
-        client: Arc<Kafka>,
+        conditions: Option<&Conditions>,
+        kafka: &KafkaStream,
         id: &str,
         event_message: &EventMessage,
         chunks: Arc<Vec<Vec<Value>>>,
     ) -> StreamPublishes {
+        if config.publish_per_event() {
+            return self.kafka_stream_per_event_tasks(
+                config,
+                conditions,
+                kafka,
+                id,
+                event_message,
+                chunks,
+            );
+        }
+
         let tasks: Vec<_> = chunks
             .iter()
             .enumerate()
             .map(|(index, chunk)| {
-                let filtered_chunk: Vec<Value> = self.filter_chunk_event_data_by_conditions(
-                    &config.events,
-                    event_message,
-                    chunk,
-                );
+                let filtered_chunk: Vec<Value> =
+                    self.filter_chunk_event_data_by_conditions(conditions, chunk);
 
                 let publish_message_id = self.generate_publish_message_id(id, index, &None);
-                let client = Arc::clone(&client);
-                let exchange = config.topic.clone();
-                let routing_key = config.key.clone();
+                let client = Arc::clone(&kafka.client);
+                let topic = config.topic.clone();
+                let key = config.key.clone();
+                let mut headers = self.kafka_headers(event_message);
+                if let Some(from_block) = filtered_chunk.first().and_then(event_block_number) {
+                    headers.push((STREAM_FROM_BLOCK_KEY, from_block));
+                }
+                if let Some(to_block) = filtered_chunk.last().and_then(event_block_number) {
+                    headers.push((STREAM_TO_BLOCK_KEY, to_block));
+                }
                 let publish_message =
                     self.create_chunk_message_json(event_message, &filtered_chunk);
-                task::spawn(async move {
-                    client
-                        .publish(&publish_message_id, &exchange, &routing_key, &publish_message)
-                        .await?;
+                spawn_publish(StreamSink::Kafka, async move {
+                    let payload = serde_json::to_vec(&publish_message).map_err(KafkaError::from)?;
+                    client.publish(&publish_message_id, &topic, &key, &headers, &payload).await?;
                     Ok(filtered_chunk.len())
                 })
             })
@@ -359,116 +480,252 @@ impl StreamsClients {
         tasks
     }
 
+    fn kafka_stream_per_event_tasks(
+        &self,
+        config: &KafkaStreamQueueConfig,
+        conditions: Option<&Conditions>,
+        kafka: &KafkaStream,
+        id: &str,
+        event_message: &EventMessage,
+        chunks: Arc<Vec<Vec<Value>>>,
+    ) -> StreamPublishes {
+        let schema = kafka.schemas.get(&config.topic).cloned();
+        let key_template = config.key.clone().map(Template::new);
+
+        let tasks: Vec<_> = chunks
+            .iter()
+            .flat_map(|chunk| self.filter_chunk_event_data_by_conditions(conditions, chunk))
+            .enumerate()
+            .map(|(index, event_data)| {
+                let publish_message_id = self.generate_publish_message_id(id, index, &None);
+                let client = Arc::clone(&kafka.client);
+                let topic = config.topic.clone();
+                let schema = schema.clone();
+                let message = json!({
+                    "event_name": event_message.event_name,
+                    "event_data": event_data,
+                    "network": event_message.network,
+                });
+                let key = key_template.as_ref().map(|t| t.parse_template_inline(&message));
+                let mut headers = self.kafka_headers(event_message);
+                if let Some(block_number) = event_block_number(&event_data) {
+                    headers.push((STREAM_BLOCK_NUMBER_KEY, block_number));
+                }
+                spawn_publish(StreamSink::Kafka, async move {
+                    let payload = match schema {
+                        Some(schema) => schema.encode(&event_data)?,
+                        None => serde_json::to_vec(&message).map_err(KafkaError::from)?,
+                    };
+                    client.publish(&publish_message_id, &topic, &key, &headers, &payload).await?;
+                    Ok(1)
+                })
+            })
+            .collect();
+        tasks
+    }
+
     pub async fn stream(
         &self,
         id: String,
         event_message: &EventMessage,
+        to_block: &U64,
         index_event_in_order: bool,
     ) -> Result<usize, StreamError> {
         if !self.has_any_streams() {
             return Ok(0);
         }
 
+        let sinks_ahead = self.sinks_ahead(event_message, to_block).await;
+
         // will always have something even if the event has no parameters due to the tx_information
         if let Value::Array(data_array) = &event_message.event_data {
             let chunks = Arc::new(self.chunk_data(data_array));
-            let mut streams: Vec<StreamPublishes> = Vec::new();
+            let mut streams: Vec<(StreamSink, StreamPublishes)> = Vec::new();
 
-            if let Some(sns) = &self.sns {
-                for config in &sns.config {
+            if let Some(sns) = self.sns.as_ref().filter(|_| !sinks_ahead.contains(&StreamSink::Sns))
+            {
+                for (config, conditions) in sns.config.iter().zip(&sns.conditions) {
                     if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
                         config.networks.contains(&event_message.network)
                     {
-                        streams.push(self.sns_stream_tasks(
-                            config,
-                            Arc::clone(&sns.client),
-                            &id,
-                            event_message,
-                            Arc::clone(&chunks),
+                        streams.push((
+                            StreamSink::Sns,
+                            self.sns_stream_tasks(
+                                config,
+                                conditions.as_ref(),
+                                Arc::clone(&sns.client),
+                                &id,
+                                event_message,
+                                Arc::clone(&chunks),
+                            ),
                         ));
                     }
                 }
             };
 
-            if let Some(webhook) = &self.webhook {
-                for config in &webhook.config {
+            if let Some(webhook) =
+                self.webhook.as_ref().filter(|_| !sinks_ahead.contains(&StreamSink::Webhooks))
+            {
+                for (config, conditions) in webhook.config.iter().zip(&webhook.conditions) {
                     if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
                         config.networks.contains(&event_message.network)
                     {
-                        streams.push(self.webhook_stream_tasks(
-                            config,
-                            Arc::clone(&webhook.client),
-                            &id,
-                            event_message,
-                            Arc::clone(&chunks),
+                        streams.push((
+                            StreamSink::Webhooks,
+                            self.webhook_stream_tasks(
+                                config,
+                                conditions.as_ref(),
+                                Arc::clone(&webhook.client),
+                                &id,
+                                event_message,
+                                Arc::clone(&chunks),
+                            ),
                         ));
                     }
                 }
             }
 
-            if let Some(rabbitmq) = &self.rabbitmq {
-                for config in &rabbitmq.config.exchanges {
+            if let Some(rabbitmq) =
+                self.rabbitmq.as_ref().filter(|_| !sinks_ahead.contains(&StreamSink::RabbitMQ))
+            {
+                for (config, conditions) in
+                    rabbitmq.config.exchanges.iter().zip(&rabbitmq.conditions)
+                {
                     if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
                         config.networks.contains(&event_message.network)
                     {
-                        streams.push(self.rabbitmq_stream_tasks(
-                            config,
-                            Arc::clone(&rabbitmq.client),
-                            &id,
-                            event_message,
-                            Arc::clone(&chunks),
+                        streams.push((
+                            StreamSink::RabbitMQ,
+                            self.rabbitmq_stream_tasks(
+                                config,
+                                conditions.as_ref(),
+                                Arc::clone(&rabbitmq.client),
+                                &id,
+                                event_message,
+                                Arc::clone(&chunks),
+                            ),
                         ));
                     }
                 }
             }
 
-            if let Some(kafka) = &self.kafka {
-                for config in &kafka.config.topics {
+            if let Some(kafka) =
+                self.kafka.as_ref().filter(|_| !sinks_ahead.contains(&StreamSink::Kafka))
+            {
+                for (config, conditions) in kafka.config.topics.iter().zip(&kafka.conditions) {
                     if config.events.iter().any(|e| e.event_name == event_message.event_name) &&
                         config.networks.contains(&event_message.network)
                     {
-                        streams.push(self.kafka_stream_tasks(
-                            config,
-                            Arc::clone(&kafka.client),
-                            &id,
-                            event_message,
-                            Arc::clone(&chunks),
+                        streams.push((
+                            StreamSink::Kafka,
+                            self.kafka_stream_tasks(
+                                config,
+                                conditions.as_ref(),
+                                kafka,
+                                &id,
+                                event_message,
+                                Arc::clone(&chunks),
+                            ),
                         ));
                     }
                 }
             }
 
             let mut streamed_total = 0;
+            let mut published: HashMap<StreamSink, bool> = HashMap::new();
+            let mut first_error = None;
 
             if index_event_in_order {
-                for stream in streams {
+                'sinks: for (sink, stream) in streams {
                     for task in stream {
-                        match task.await {
+                        let error = match task.await {
                             Ok(Ok(streamed)) => {
                                 streamed_total += streamed;
+                                continue;
                             }
-                            Ok(Err(e)) => return Err(e),
-                            Err(e) => return Err(StreamError::JoinError(e)),
-                        }
+                            Ok(Err(e)) => e,
+                            Err(e) => StreamError::JoinError(e),
+                        };
+                        published.insert(sink, false);
+                        first_error = Some(error);
+                        break 'sinks;
                     }
+                    published.entry(sink).or_insert(true);
                 }
             } else {
-                let tasks: Vec<_> = streams.into_iter().flatten().collect();
+                let (sinks, tasks): (Vec<_>, Vec<_>) = streams
+                    .into_iter()
+                    .flat_map(|(sink, stream)| stream.into_iter().map(move |task| (sink, task)))
+                    .unzip();
                 let results = join_all(tasks).await;
-                for result in results {
-                    match result {
+                for (sink, result) in sinks.into_iter().zip(results) {
+                    let error = match result {
                         Ok(Ok(streamed)) => {
                             streamed_total += streamed;
+                            published.entry(sink).or_insert(true);
+                            continue;
                         }
-                        Ok(Err(e)) => return Err(e),
-                        Err(e) => return Err(StreamError::JoinError(e)),
-                    }
+                        Ok(Err(e)) => e,
+                        Err(e) => StreamError::JoinError(e),
+                    };
+                    published.insert(sink, false);
+                    first_error.get_or_insert(error);
                 }
             }
 
+            for (sink, published) in published {
+                self.record_publish(sink, event_message, published).await;
+            }
+
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+
             Ok(streamed_total)
         } else {
             unreachable!("Event data should be an array");
         }
     }
 }
+
+/// Parses the conditions of the event for each config of a sink, configs without the event or
+/// without conditions have none
+fn event_conditions<'a>(
+    configs: impl Iterator<Item = &'a Vec<StreamEvent>>,
+    event_name: &str,
+) -> Result<Vec<Option<Conditions>>, ConditionError> {
+    configs
+        .map(|events| {
+            events
+                .iter()
+                .find(|event| event.event_name == event_name)
+                .and_then(|event| event.conditions.as_ref())
+                .map(|conditions| Conditions::parse(conditions))
+                .transpose()
+        })
+        .collect()
+}
+
+/// Spawns a publish to a sink and records whether it succeeded
+fn spawn_publish<F>(sink: StreamSink, publish: F) -> JoinHandle<Result<usize, StreamError>>
+where
+    F: Future<Output = Result<usize, StreamError>> + Send + 'static,
+{
+    let span = info_span!("stream_publish", sink = sink.as_str());
+    spawn_in_flight(
+        async move {
+            let result = publish.await;
+            record_stream_publish(sink.as_str(), result.is_ok());
+            result
+        }
+        .instrument(span),
+    )
+}
+
+fn event_block_number(event_data: &Value) -> Option<String> {
+    event_data
+        .get("transaction_information")
+        .and_then(|tx| tx.get("block_number"))
+        .and_then(|block_number| serde_json::from_value::<U64>(block_number.clone()).ok())
+        .map(|block_number| block_number.to_string())
+}
@@ -0,0 +1,570 @@
+use ethers::types::U256;
+use serde_json::{json, Value};
+
+use crate::{
+    abi::{ABIInput, EventInfo},
+    helpers::camel_to_snake,
+};
+
+// Confluent wire format magic byte which prefixes the schema id
+const CONFLUENT_MAGIC_BYTE: u8 = 0;
+
+#[derive(thiserror::Error, Debug)]
+pub enum EventSchemaError {
+    #[error("Field {0} is missing from the event data")]
+    MissingField(String),
+
+    #[error("Field {0} expected {1} but got {2}")]
+    InvalidFieldValue(String, &'static str, Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EventFieldType {
+    String,
+    Bool,
+    Long,
+    // integers wider than 32 bits are sent as decimal strings so uint256 is never truncated
+    BigNumber,
+    Array(Box<EventFieldType>),
+    Record(String, Vec<EventField>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct EventField {
+    // the name used in the schema which has to be a valid avro and protobuf identifier
+    name: String,
+    // the key the value lives under in the event data json
+    json_key: String,
+    field_type: EventFieldType,
+}
+
+fn sanitize_field_name(name: &str, index: usize) -> String {
+    if name.is_empty() {
+        return format!("param_{}", index);
+    }
+
+    let sanitized: String =
+        name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", sanitized)
+    } else {
+        sanitized
+    }
+}
+
+fn to_record_name(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn solidity_type_to_field_type(
+    record_name: &str,
+    abi_type: &str,
+    components: &Option<Vec<ABIInput>>,
+) -> EventFieldType {
+    if let Some(array_start) = abi_type.rfind('[') {
+        let inner = solidity_type_to_field_type(record_name, &abi_type[..array_start], components);
+        // nested arrays can not be represented in protobuf so fallback to the json string
+        return if matches!(inner, EventFieldType::Array(_)) {
+            EventFieldType::String
+        } else {
+            EventFieldType::Array(Box::new(inner))
+        };
+    }
+
+    match abi_type {
+        "tuple" => EventFieldType::Record(
+            record_name.to_string(),
+            abi_inputs_to_fields(record_name, components.as_deref().unwrap_or_default()),
+        ),
+        "bool" => EventFieldType::Bool,
+        t if t.starts_with("int") || t.starts_with("uint") => {
+            let size: usize = t.trim_start_matches('u')[3..].parse().unwrap_or(256);
+            if size <= 32 {
+                EventFieldType::Long
+            } else {
+                EventFieldType::BigNumber
+            }
+        }
+        // address, string, bytes and anything unknown are sent as strings
+        _ => EventFieldType::String,
+    }
+}
+
+fn abi_inputs_to_fields(parent_record_name: &str, inputs: &[ABIInput]) -> Vec<EventField> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let name = sanitize_field_name(&input.name, index);
+            let record_name = format!("{}{}", parent_record_name, to_record_name(&name));
+            EventField {
+                field_type: solidity_type_to_field_type(
+                    &record_name,
+                    &input.type_,
+                    &input.components,
+                ),
+                name,
+                json_key: input.name.clone(),
+            }
+        })
+        .collect()
+}
+
+fn transaction_information_field() -> EventField {
+    let fields = [
+        ("network", EventFieldType::String),
+        ("address", EventFieldType::String),
+        ("block_hash", EventFieldType::String),
+        ("block_number", EventFieldType::BigNumber),
+        ("transaction_hash", EventFieldType::String),
+        ("log_index", EventFieldType::BigNumber),
+        ("transaction_index", EventFieldType::BigNumber),
+    ]
+    .into_iter()
+    .map(|(name, field_type)| EventField {
+        name: name.to_string(),
+        json_key: name.to_string(),
+        field_type,
+    })
+    .collect();
+
+    EventField {
+        name: "transaction_information".to_string(),
+        json_key: "transaction_information".to_string(),
+        field_type: EventFieldType::Record("TransactionInformation".to_string(), fields),
+    }
+}
+
+/// A schema for a single decoded event, generated from the ABI inputs with the
+/// `transaction_information` appended so it matches the json we stream.
+#[derive(Debug, Clone)]
+pub struct EventSchema {
+    namespace: String,
+    name: String,
+    fields: Vec<EventField>,
+}
+
+impl EventSchema {
+    pub fn from_event_info(contract_name: &str, event_info: &EventInfo) -> Self {
+        let name = sanitize_field_name(&event_info.name, 0);
+        let mut fields = abi_inputs_to_fields(&name, &event_info.inputs);
+        fields.push(transaction_information_field());
+
+        Self { namespace: format!("rindexer.{}", camel_to_snake(contract_name)), name, fields }
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.namespace, self.name)
+    }
+
+    pub fn to_avro_schema(&self) -> String {
+        fn avro_type(field_type: &EventFieldType) -> Value {
+            match field_type {
+                EventFieldType::String | EventFieldType::BigNumber => json!("string"),
+                EventFieldType::Bool => json!("boolean"),
+                EventFieldType::Long => json!("long"),
+                EventFieldType::Array(inner) => {
+                    json!({ "type": "array", "items": avro_type(inner) })
+                }
+                EventFieldType::Record(name, fields) => avro_record(name, fields),
+            }
+        }
+
+        fn avro_record(name: &str, fields: &[EventField]) -> Value {
+            json!({
+                "type": "record",
+                "name": name,
+                "fields": fields
+                    .iter()
+                    .map(|f| json!({ "name": f.name, "type": avro_type(&f.field_type) }))
+                    .collect::<Vec<_>>()
+            })
+        }
+
+        let mut schema = avro_record(&self.name, &self.fields);
+        schema["namespace"] = json!(self.namespace);
+
+        schema.to_string()
+    }
+
+    pub fn to_protobuf_schema(&self) -> String {
+        fn proto_type(field_type: &EventFieldType) -> String {
+            match field_type {
+                EventFieldType::String | EventFieldType::BigNumber => "string".to_string(),
+                EventFieldType::Bool => "bool".to_string(),
+                EventFieldType::Long => "int64".to_string(),
+                EventFieldType::Array(inner) => format!("repeated {}", proto_type(inner)),
+                EventFieldType::Record(name, _) => name.clone(),
+            }
+        }
+
+        fn proto_message(name: &str, fields: &[EventField], indent: usize) -> String {
+            let padding = "  ".repeat(indent);
+            let mut message = format!("{}message {} {{\n", padding, name);
+
+            for field in fields {
+                let nested = match &field.field_type {
+                    EventFieldType::Record(name, fields) => Some((name, fields)),
+                    EventFieldType::Array(inner) => match inner.as_ref() {
+                        EventFieldType::Record(name, fields) => Some((name, fields)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some((name, fields)) = nested {
+                    message.push_str(&proto_message(name, fields, indent + 1));
+                }
+            }
+
+            for (index, field) in fields.iter().enumerate() {
+                message.push_str(&format!(
+                    "{}  {} {} = {};\n",
+                    padding,
+                    proto_type(&field.field_type),
+                    field.name,
+                    index + 1
+                ));
+            }
+
+            message.push_str(&format!("{}}}\n", padding));
+            message
+        }
+
+        format!(
+            "syntax = \"proto3\";\n\npackage {};\n\n{}",
+            self.namespace,
+            proto_message(&self.name, &self.fields, 0)
+        )
+    }
+
+    /// Encodes the event data into the confluent avro wire format
+    pub fn encode_avro(
+        &self,
+        schema_id: u32,
+        event_data: &Value,
+    ) -> Result<Vec<u8>, EventSchemaError> {
+        let mut buf = confluent_header(schema_id);
+        avro_encode_record(&self.fields, event_data, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Encodes the event data into the confluent protobuf wire format
+    pub fn encode_protobuf(
+        &self,
+        schema_id: u32,
+        event_data: &Value,
+    ) -> Result<Vec<u8>, EventSchemaError> {
+        let mut buf = confluent_header(schema_id);
+        // message indexes - the event is always the first message in the schema which confluent
+        // shortens to a single 0
+        buf.push(0);
+        protobuf_encode_message(&self.fields, event_data, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+fn confluent_header(schema_id: u32) -> Vec<u8> {
+    let mut buf = vec![CONFLUENT_MAGIC_BYTE];
+    buf.extend_from_slice(&schema_id.to_be_bytes());
+    buf
+}
+
+fn field_value<'a>(field: &EventField, data: &'a Value) -> Result<&'a Value, EventSchemaError> {
+    data.get(&field.json_key).ok_or_else(|| EventSchemaError::MissingField(field.name.clone()))
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn value_to_big_number(name: &str, value: &Value) -> Result<String, EventSchemaError> {
+    match value {
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).map(|v| v.to_string()).map_err(|_| {
+                EventSchemaError::InvalidFieldValue(name.to_string(), "number", value.clone())
+            }),
+            None => Ok(s.clone()),
+        },
+        _ => Err(EventSchemaError::InvalidFieldValue(name.to_string(), "number", value.clone())),
+    }
+}
+
+fn value_to_i64(name: &str, value: &Value) -> Result<i64, EventSchemaError> {
+    let parsed = match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    };
+
+    parsed
+        .ok_or_else(|| EventSchemaError::InvalidFieldValue(name.to_string(), "long", value.clone()))
+}
+
+fn value_to_bool(name: &str, value: &Value) -> Result<bool, EventSchemaError> {
+    value
+        .as_bool()
+        .ok_or_else(|| EventSchemaError::InvalidFieldValue(name.to_string(), "bool", value.clone()))
+}
+
+fn value_to_array<'a>(name: &str, value: &'a Value) -> Result<&'a Vec<Value>, EventSchemaError> {
+    value.as_array().ok_or_else(|| {
+        EventSchemaError::InvalidFieldValue(name.to_string(), "array", value.clone())
+    })
+}
+
+fn write_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn avro_write_long(value: i64, buf: &mut Vec<u8>) {
+    // avro longs are zig-zag encoded
+    write_varint(((value << 1) ^ (value >> 63)) as u64, buf);
+}
+
+fn avro_write_string(value: &str, buf: &mut Vec<u8>) {
+    avro_write_long(value.len() as i64, buf);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn avro_encode_value(
+    name: &str,
+    field_type: &EventFieldType,
+    value: &Value,
+    buf: &mut Vec<u8>,
+) -> Result<(), EventSchemaError> {
+    match field_type {
+        EventFieldType::String => avro_write_string(&value_to_string(value), buf),
+        EventFieldType::BigNumber => avro_write_string(&value_to_big_number(name, value)?, buf),
+        EventFieldType::Bool => buf.push(value_to_bool(name, value)? as u8),
+        EventFieldType::Long => avro_write_long(value_to_i64(name, value)?, buf),
+        EventFieldType::Array(inner) => {
+            let items = value_to_array(name, value)?;
+            if !items.is_empty() {
+                avro_write_long(items.len() as i64, buf);
+                for item in items {
+                    avro_encode_value(name, inner, item, buf)?;
+                }
+            }
+            // end of array blocks
+            avro_write_long(0, buf);
+        }
+        EventFieldType::Record(_, fields) => avro_encode_record(fields, value, buf)?,
+    }
+
+    Ok(())
+}
+
+fn avro_encode_record(
+    fields: &[EventField],
+    data: &Value,
+    buf: &mut Vec<u8>,
+) -> Result<(), EventSchemaError> {
+    for field in fields {
+        avro_encode_value(&field.name, &field.field_type, field_value(field, data)?, buf)?;
+    }
+    Ok(())
+}
+
+const PROTOBUF_WIRE_VARINT: u64 = 0;
+const PROTOBUF_WIRE_LENGTH_DELIMITED: u64 = 2;
+
+fn protobuf_write_tag(field_number: u64, wire_type: u64, buf: &mut Vec<u8>) {
+    write_varint((field_number << 3) | wire_type, buf);
+}
+
+fn protobuf_write_bytes(field_number: u64, bytes: &[u8], buf: &mut Vec<u8>) {
+    protobuf_write_tag(field_number, PROTOBUF_WIRE_LENGTH_DELIMITED, buf);
+    write_varint(bytes.len() as u64, buf);
+    buf.extend_from_slice(bytes);
+}
+
+// writes the raw value without a tag, only valid for varint scalar types
+fn protobuf_scalar_varint(
+    name: &str,
+    field_type: &EventFieldType,
+    value: &Value,
+) -> Result<Option<u64>, EventSchemaError> {
+    match field_type {
+        EventFieldType::Bool => Ok(Some(value_to_bool(name, value)? as u64)),
+        EventFieldType::Long => Ok(Some(value_to_i64(name, value)? as u64)),
+        _ => Ok(None),
+    }
+}
+
+fn protobuf_encode_field(
+    field_number: u64,
+    name: &str,
+    field_type: &EventFieldType,
+    value: &Value,
+    buf: &mut Vec<u8>,
+) -> Result<(), EventSchemaError> {
+    match field_type {
+        EventFieldType::String => {
+            protobuf_write_bytes(field_number, value_to_string(value).as_bytes(), buf)
+        }
+        EventFieldType::BigNumber => {
+            protobuf_write_bytes(field_number, value_to_big_number(name, value)?.as_bytes(), buf)
+        }
+        EventFieldType::Bool | EventFieldType::Long => {
+            if let Some(varint) = protobuf_scalar_varint(name, field_type, value)? {
+                protobuf_write_tag(field_number, PROTOBUF_WIRE_VARINT, buf);
+                write_varint(varint, buf);
+            }
+        }
+        EventFieldType::Array(inner) => {
+            let items = value_to_array(name, value)?;
+            if matches!(inner.as_ref(), EventFieldType::Bool | EventFieldType::Long) {
+                // proto3 packs repeated scalars by default
+                if !items.is_empty() {
+                    let mut packed = Vec::new();
+                    for item in items {
+                        if let Some(varint) = protobuf_scalar_varint(name, inner, item)? {
+                            write_varint(varint, &mut packed);
+                        }
+                    }
+                    protobuf_write_bytes(field_number, &packed, buf);
+                }
+            } else {
+                for item in items {
+                    protobuf_encode_field(field_number, name, inner, item, buf)?;
+                }
+            }
+        }
+        EventFieldType::Record(_, fields) => {
+            let mut nested = Vec::new();
+            protobuf_encode_message(fields, value, &mut nested)?;
+            protobuf_write_bytes(field_number, &nested, buf);
+        }
+    }
+
+    Ok(())
+}
+
+fn protobuf_encode_message(
+    fields: &[EventField],
+    data: &Value,
+    buf: &mut Vec<u8>,
+) -> Result<(), EventSchemaError> {
+    for (index, field) in fields.iter().enumerate() {
+        protobuf_encode_field(
+            index as u64 + 1,
+            &field.name,
+            &field.field_type,
+            field_value(field, data)?,
+            buf,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::ABIItem;
+
+    fn transfer_event_info() -> EventInfo {
+        let item: ABIItem = serde_json::from_value(json!({
+            "type": "event",
+            "name": "Transfer",
+            "inputs": [
+                { "indexed": true, "name": "from", "type": "address" },
+                { "indexed": true, "name": "to", "type": "address" },
+                { "indexed": false, "name": "value", "type": "uint256" }
+            ]
+        }))
+        .unwrap();
+        let signature = item.format_event_signature().unwrap();
+        EventInfo::new(item, signature)
+    }
+
+    fn transfer_event_data() -> Value {
+        json!({
+            "from": "0x01",
+            "to": "0x02",
+            "value": "340282366920938463463374607431768211456",
+            "transaction_information": {
+                "network": "ethereum",
+                "address": "0x03",
+                "block_hash": "0x04",
+                "block_number": "0x10",
+                "transaction_hash": "0x05",
+                "log_index": "0x1",
+                "transaction_index": "0x2"
+            }
+        })
+    }
+
+    #[test]
+    fn test_avro_schema_from_event_info() {
+        let schema = EventSchema::from_event_info("RocketPoolETH", &transfer_event_info());
+        let avro: Value = serde_json::from_str(&schema.to_avro_schema()).unwrap();
+
+        assert_eq!(schema.full_name(), "rindexer.rocket_pool_eth.Transfer");
+        assert_eq!(avro["name"], "Transfer");
+        assert_eq!(avro["fields"][2], json!({ "name": "value", "type": "string" }));
+        assert_eq!(avro["fields"][3]["type"]["name"], "TransactionInformation");
+    }
+
+    #[test]
+    fn test_protobuf_schema_from_event_info() {
+        let schema = EventSchema::from_event_info("RocketPoolETH", &transfer_event_info());
+        let proto = schema.to_protobuf_schema();
+
+        assert!(proto.contains("package rindexer.rocket_pool_eth;"));
+        assert!(proto.contains("  string value = 3;"));
+        assert!(proto.contains("  TransactionInformation transaction_information = 4;"));
+    }
+
+    #[test]
+    fn test_encode_avro() {
+        let schema = EventSchema::from_event_info("RocketPoolETH", &transfer_event_info());
+        let encoded = schema.encode_avro(7, &transfer_event_data()).unwrap();
+
+        assert_eq!(&encoded[..5], &[0, 0, 0, 0, 7]);
+        // "0x01" as an avro string - zig-zag length 4 is 8
+        assert_eq!(&encoded[5..10], &[8, b'0', b'x', b'0', b'1']);
+        // the uint256 is kept whole as a decimal string
+        let value = b"340282366920938463463374607431768211456";
+        assert!(encoded.windows(value.len()).any(|w| w == value));
+        // hex block number becomes a decimal string
+        assert!(encoded.windows(3).any(|w| w == [4, b'1', b'6']));
+    }
+
+    #[test]
+    fn test_encode_protobuf() {
+        let schema = EventSchema::from_event_info("RocketPoolETH", &transfer_event_info());
+        let encoded = schema.encode_protobuf(7, &transfer_event_data()).unwrap();
+
+        assert_eq!(&encoded[..6], &[0, 0, 0, 0, 7, 0]);
+        // field 1, length delimited, length 4
+        assert_eq!(&encoded[6..12], &[0x0a, 4, b'0', b'x', b'0', b'1']);
+    }
+
+    #[test]
+    fn test_encode_missing_field() {
+        let schema = EventSchema::from_event_info("RocketPoolETH", &transfer_event_info());
+        let result = schema.encode_avro(1, &json!({ "from": "0x01" }));
+
+        assert!(matches!(result, Err(EventSchemaError::MissingField(field)) if field == "to"));
+    }
+}
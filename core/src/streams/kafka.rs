@@ -4,13 +4,25 @@ use std::time::Duration;
 use rdkafka::{
     config::ClientConfig,
     message::{Header, OwnedHeaders},
-    producer::{FutureProducer, FutureRecord},
+    producer::{FutureProducer, FutureRecord, Producer},
     util::Timeout,
 };
 use serde_json::Value;
 use thiserror::Error;
+#[cfg(not(windows))]
+use tracing::error;
 
-use crate::{manifest::stream::KafkaStreamConfig, streams::STREAM_MESSAGE_ID_KEY};
+#[cfg(not(windows))]
+use crate::shutdown::on_shutdown;
+use crate::{
+    abi::EventInfo,
+    manifest::stream::{KafkaEncoding, KafkaStreamConfig},
+    streams::{
+        event_schema::{EventSchema, EventSchemaError},
+        schema_registry::{SchemaRegistry, SchemaRegistryError},
+        STREAM_MESSAGE_ID_KEY,
+    },
+};
 
 #[derive(Error, Debug)]
 pub enum KafkaError {
@@ -19,6 +31,64 @@ pub enum KafkaError {
 
     #[error("Could not parse message: {0}")]
     CouldNotParseMessage(#[from] serde_json::Error),
+
+    #[error("Could not register schema: {0}")]
+    SchemaRegistryError(#[from] SchemaRegistryError),
+
+    #[error("Could not encode message: {0}")]
+    CouldNotEncodeMessage(#[from] EventSchemaError),
+
+    #[error("Schema registry is required for {0:?} encoding")]
+    SchemaRegistryRequired(KafkaEncoding),
+}
+
+pub type KafkaHeaders = Vec<(&'static str, String)>;
+
+/// The registered schema used to encode events for a topic
+#[derive(Debug, Clone)]
+pub struct KafkaTopicSchema {
+    encoding: KafkaEncoding,
+    schema: EventSchema,
+    schema_id: u32,
+}
+
+impl KafkaTopicSchema {
+    pub async fn register(
+        config: &KafkaStreamConfig,
+        topic: &str,
+        encoding: &KafkaEncoding,
+        contract_name: &str,
+        event_info: &EventInfo,
+    ) -> Result<Self, KafkaError> {
+        let registry_config = config
+            .schema_registry
+            .as_ref()
+            .ok_or_else(|| KafkaError::SchemaRegistryRequired(encoding.clone()))?;
+        let registry = SchemaRegistry::new(registry_config);
+        let schema = EventSchema::from_event_info(contract_name, event_info);
+
+        let (schema_type, schema_raw) = match encoding {
+            KafkaEncoding::Avro => ("AVRO", schema.to_avro_schema()),
+            KafkaEncoding::Protobuf => ("PROTOBUF", schema.to_protobuf_schema()),
+            KafkaEncoding::Json => unreachable!("JSON encoding does not register a schema"),
+        };
+
+        // topic record name strategy as a topic can hold many different events
+        let subject = format!("{}-{}", topic, schema.full_name());
+        let schema_id = registry.register(&subject, schema_type, &schema_raw).await?;
+
+        Ok(Self { encoding: encoding.clone(), schema, schema_id })
+    }
+
+    pub fn encode(&self, event_data: &Value) -> Result<Vec<u8>, KafkaError> {
+        let encoded = match self.encoding {
+            KafkaEncoding::Avro => self.schema.encode_avro(self.schema_id, event_data)?,
+            KafkaEncoding::Protobuf => self.schema.encode_protobuf(self.schema_id, event_data)?,
+            KafkaEncoding::Json => serde_json::to_vec(event_data)?,
+        };
+
+        Ok(encoded)
+    }
 }
 
 #[derive(Clone)]
@@ -52,6 +122,18 @@ impl Kafka {
             let producer: FutureProducer =
                 client_config.create().map_err(|e| KafkaError::RdkafkaError(e.to_string()))?;
 
+            // deliver anything still queued in the producer before rindexer exits
+            let shutdown_producer = producer.clone();
+            on_shutdown(async move {
+                let flushed = tokio::task::spawn_blocking(move || {
+                    shutdown_producer.flush(Timeout::After(Duration::from_secs(5)))
+                })
+                .await;
+                if let Ok(Err(e)) = flushed {
+                    error!("Could not flush the kafka producer: {}", e);
+                }
+            });
+
             Ok(Self { producer })
         }
 
@@ -66,22 +148,22 @@ impl Kafka {
         id: &str,
         topic: &str,
         key: &Option<String>,
-        message: &Value,
+        headers: &KafkaHeaders,
+        payload: &[u8],
     ) -> Result<(), KafkaError> {
         #[cfg(not(windows))]
         {
-            let message_body = serde_json::to_vec(message)?;
-
-            let record = if key.is_some() {
-                FutureRecord::to(topic).key(key.as_ref().unwrap()).payload(&message_body).headers(
-                    OwnedHeaders::new()
-                        .insert(Header { key: STREAM_MESSAGE_ID_KEY, value: Some(id) }),
-                )
-            } else {
-                FutureRecord::to(topic).payload(&message_body).headers(
-                    OwnedHeaders::new()
-                        .insert(Header { key: STREAM_MESSAGE_ID_KEY, value: Some(id) }),
-                )
+            let mut owned_headers =
+                OwnedHeaders::new().insert(Header { key: STREAM_MESSAGE_ID_KEY, value: Some(id) });
+            for (header_key, header_value) in headers {
+                owned_headers =
+                    owned_headers.insert(Header { key: header_key, value: Some(header_value) });
+            }
+
+            let record = FutureRecord::to(topic).payload(payload).headers(owned_headers);
+            let record = match key {
+                Some(key) => record.key(key),
+                None => record,
             };
 
             self.producer
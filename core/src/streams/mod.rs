@@ -7,9 +7,20 @@ pub use webhook::{Webhook, WebhookError};
 mod rabbitmq;
 pub use rabbitmq::{RabbitMQ, RabbitMQError};
 
+mod event_schema;
 mod kafka;
+mod schema_registry;
+
+mod checkpoint;
+pub use checkpoint::{StreamCheckpointError, StreamCheckpoints};
 
 mod clients;
-pub use clients::StreamsClients;
+pub use clients::{StreamsClients, StreamsClientsError};
 
 pub const STREAM_MESSAGE_ID_KEY: &str = "x-rindexer-id";
+pub const STREAM_NETWORK_KEY: &str = "x-rindexer-network";
+pub const STREAM_CONTRACT_KEY: &str = "x-rindexer-contract";
+pub const STREAM_EVENT_KEY: &str = "x-rindexer-event";
+pub const STREAM_BLOCK_NUMBER_KEY: &str = "x-rindexer-block-number";
+pub const STREAM_FROM_BLOCK_KEY: &str = "x-rindexer-from-block";
+pub const STREAM_TO_BLOCK_KEY: &str = "x-rindexer-to-block";
@@ -3,7 +3,7 @@ use deadpool_lapin::{Manager, Pool};
 use lapin::{options::*, types::FieldTable, BasicProperties, ConnectionProperties, ExchangeKind};
 use serde_json::Value;
 
-use crate::manifest::stream::ExchangeKindWrapper;
+use crate::{manifest::stream::ExchangeKindWrapper, shutdown::on_shutdown};
 
 #[derive(thiserror::Error, Debug)]
 pub enum RabbitMQError {
@@ -27,6 +27,11 @@ impl RabbitMQ {
         let manager = Manager::new(uri, ConnectionProperties::default());
         let pool = Pool::builder(manager).max_size(16).build().expect("Failed to create pool");
 
+        let shutdown_pool = pool.clone();
+        on_shutdown(async move {
+            shutdown_pool.close();
+        });
+
         Self { pool }
     }
 
@@ -0,0 +1,68 @@
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::manifest::stream::KafkaSchemaRegistryConfig;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SchemaRegistryError {
+    #[error("Request error: {0}")]
+    RequestError(#[from] reqwest::Error),
+
+    #[error("Schema registry rejected schema for subject {0}: {1}")]
+    SchemaRejected(String, String),
+}
+
+#[derive(Deserialize)]
+struct RegisterSchemaResponse {
+    id: u32,
+}
+
+/// A client for a confluent compatible schema registry
+#[derive(Debug, Clone)]
+pub struct SchemaRegistry {
+    client: Client,
+    config: KafkaSchemaRegistryConfig,
+}
+
+impl SchemaRegistry {
+    pub fn new(config: &KafkaSchemaRegistryConfig) -> Self {
+        Self { client: Client::new(), config: config.clone() }
+    }
+
+    /// Registers the schema under the subject returning the schema id, if the schema already
+    /// exists the registry returns the existing id
+    pub async fn register(
+        &self,
+        subject: &str,
+        schema_type: &str,
+        schema: &str,
+    ) -> Result<u32, SchemaRegistryError> {
+        let mut request = self
+            .client
+            .post(format!(
+                "{}/subjects/{}/versions",
+                self.config.url.trim_end_matches('/'),
+                subject
+            ))
+            .header("Content-Type", "application/vnd.schemaregistry.v1+json")
+            .json(&json!({ "schemaType": schema_type, "schema": schema }));
+
+        if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(SchemaRegistryError::SchemaRejected(
+                subject.to_string(),
+                format!("{} - {}", status, body),
+            ));
+        }
+
+        Ok(response.json::<RegisterSchemaResponse>().await?.id)
+    }
+}
@@ -34,7 +34,6 @@ else
 fi
 
 BIN_URL="https://rindexer.xyz/releases/${PLATFORM}-${ARCH_TYPE}/rindexer_${PLATFORM}-${ARCH_TYPE}.${EXT}"
-RESOURCES_URL="https://rindexer.xyz/releases/resources.zip"
 
 log() {
    echo -e "\033[1;32m$1\033[0m"
@@ -63,9 +62,8 @@ spinner() {
 # Install or uninstall based on the command line option
 case "$1" in
     --local)
-        log "Using local binary from $LOCAL_BIN_PATH and resources from $LOCAL_RESOURCES_PATH..."
+        log "Using local binary from $LOCAL_BIN_PATH..."
         cp "$LOCAL_BIN_PATH" "$BIN_PATH"
-        unzip -o "$LOCAL_RESOURCES_PATH" -d "$RINDEXER_DIR/resources"
         ;;
     --uninstall)
         log "Uninstalling rindexer..."
@@ -95,14 +93,8 @@ case "$1" in
 
         log "Extracted binary to $RINDEXER_BIN_DIR"
 
-        mkdir -p "$RINDEXER_DIR/resources"
-        curl -sSf -L "$RESOURCES_URL" -o "$RINDEXER_DIR/resources.zip" & spinner "Downloading resources..."
-        if [[ "$OS_TYPE" == "Linux" ]]; then
-            unzip -o "$RINDEXER_DIR/resources.zip" -d "$RINDEXER_DIR/resources" > /dev/null
-        else
-            tar -xzf "$RINDEXER_DIR/resources.zip" -C "$RINDEXER_DIR/resources" > /dev/null
-        fi
-        rm "$RINDEXER_DIR/resources.zip"
+        # the GraphQL server is built into the binary so older resources are no longer needed
+        rm -rf "$RINDEXER_DIR/resources"
         ;;
 esac
 
@@ -147,7 +139,6 @@ echo "Updating rindexer..."
 if [ "\$1" == "--local" ]; then
     echo "Using local binary for update..."
     cp "$LOCAL_BIN_PATH" "$BIN_PATH"
-    unzip -o "$LOCAL_RESOURCES_PATH" -d "$RINDEXER_DIR/resources" > /dev/null
 else
     echo "Downloading the latest binary from $BIN_URL..."
     curl -sSf -L "$BIN_URL" -o "$RINDEXER_DIR/rindexer.${EXT}"
@@ -158,14 +149,7 @@ else
         unzip -o "$RINDEXER_DIR/rindexer.${EXT}" -d "$RINDEXER_BIN_DIR"
         mv "$RINDEXER_BIN_DIR/rindexer_cli.exe" "$BIN_PATH" || mv "$RINDEXER_BIN_DIR/rindexer.exe" "$BIN_PATH"
     fi
-    mkdir -p "$RINDEXER_DIR/resources"
-    curl -sSf -L "$RESOURCES_URL" -o "$RINDEXER_DIR/resources.zip"
-    if [[ "$OS_TYPE" == "Linux" ]]; then
-        unzip -o "$RINDEXER_DIR/resources.zip" -d "$RINDEXER_DIR/resources" > /dev/null
-    else
-        tar -xzf "$RINDEXER_DIR/resources.zip" -C "$RINDEXER_DIR/resources" > /dev/null
-    fi
-    rm "$RINDEXER_DIR/resources.zip"
+    rm -rf "$RINDEXER_DIR/resources"
 fi
 chmod +x "$BIN_PATH"
 echo "rindexer has been updated to the latest version."
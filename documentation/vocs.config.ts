@@ -51,6 +51,10 @@ export default defineConfig({
             { text: 'Storage', link: '/docs/start-building/yaml-config/storage' },
             { text: 'Contracts', link: '/docs/start-building/yaml-config/contracts' },
             { text: 'GraphQL', link: '/docs/start-building/yaml-config/graphql' },
+            { text: 'REST', link: '/docs/start-building/yaml-config/rest' },
+            { text: 'Health', link: '/docs/start-building/yaml-config/health' },
+            { text: 'Telemetry', link: '/docs/start-building/yaml-config/telemetry' },
+            { text: 'RPC Cache', link: '/docs/start-building/yaml-config/rpc-cache' },
             { text: 'Global', link: '/docs/start-building/yaml-config/global' },
           ],
         },
@@ -77,6 +81,9 @@ export default defineConfig({
             { text: 'Telegram', link: '/docs/start-building/chatbots/telegram' },
             { text: 'Discord', link: '/docs/start-building/chatbots/discord' },
             { text: 'Slack', link: '/docs/start-building/chatbots/slack' },
+            { text: 'Microsoft Teams', link: '/docs/start-building/chatbots/teams' },
+            { text: 'Matrix', link: '/docs/start-building/chatbots/matrix' },
+            { text: 'Email', link: '/docs/start-building/chatbots/email' },
           ],
         },
         {
@@ -108,6 +115,7 @@ export default defineConfig({
       text: 'Accessing Data',
       items: [
         { text: 'GraphQL', link: '/docs/accessing-data/graphql' },
+        { text: 'REST', link: '/docs/accessing-data/rest' },
         { text: 'Direct SQL', link: '/docs/accessing-data/direct-sql' },
       ],
     },
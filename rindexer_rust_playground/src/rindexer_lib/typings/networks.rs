@@ -14,6 +14,7 @@ use rindexer::{
 
 #[allow(dead_code)]
 fn create_shadow_client(
+    network: &str,
     rpc_url: &str,
     compute_units_per_second: Option<u64>,
     max_block_range: Option<U64>,
@@ -23,11 +24,12 @@ fn create_shadow_client(
         "X-SHADOW-API-KEY",
         public_read_env_value("RINDEXER_PHANTOM_API_KEY").unwrap().parse().unwrap(),
     );
-    create_client(rpc_url, compute_units_per_second, max_block_range, header)
+    create_client(network, rpc_url, compute_units_per_second, max_block_range, header)
 }
 
 lazy_static! {
     static ref ETHEREUM_PROVIDER: Arc<JsonRpcCachedProvider> = create_client(
+        "ethereum",
         &public_read_env_value("https://mainnet.gateway.tenderly.co")
             .unwrap_or("https://mainnet.gateway.tenderly.co".to_string()),
         None,
@@ -36,6 +38,7 @@ lazy_static! {
     )
     .expect("Error creating provider");
     static ref YOMINET_PROVIDER: Arc<JsonRpcCachedProvider> = create_client(
+        "yominet",
         &public_read_env_value("https://yominet.rpc.caldera.xyz/http")
             .unwrap_or("https://yominet.rpc.caldera.xyz/http".to_string()),
         None,
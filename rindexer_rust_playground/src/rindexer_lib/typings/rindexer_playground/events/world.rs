@@ -284,6 +284,7 @@ where
                     indexing_contract_setup: c.indexing_contract_setup(),
                     start_block: c.start_block,
                     end_block: c.end_block,
+                    historic_source: c.historic_source.clone(),
                     disable_logs_bloom_checks: rindexer_yaml
                         .networks
                         .iter()
@@ -291,8 +292,11 @@ where
                         .map_or(false, |n| n.disable_logs_bloom_checks.unwrap_or_default()),
                 })
                 .collect(),
+            log_buffer_size: contract_details.get_log_buffer_size(),
             abi: contract_details.abi,
             reorg_safe_distance: contract_details.reorg_safe_distance.unwrap_or_default(),
+            historic_segments: contract_details.historic_segments.unwrap_or(1),
+            combine_get_logs: contract_details.combine_get_logs.unwrap_or_default(),
         };
 
         let callback: Arc<
@@ -315,6 +319,7 @@ where
             topic_id: topic_id.parse::<H256>().unwrap(),
             contract,
             callback,
+            stream_checkpoints: None,
         });
     }
 }